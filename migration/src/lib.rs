@@ -3,6 +3,19 @@ pub use sea_orm_migration::prelude::*;
 mod m20220101_000001_create_table;
 mod m20250107_000001_create_provider_cache;
 mod m20250109_000001_add_provider_cache_unique;
+mod m20250115_000001_add_film_cache_match_confidence;
+mod m20250120_000001_create_subscriptions;
+mod m20250122_000001_create_release_date_history;
+mod m20250124_000001_add_provider_cache_display_priority;
+mod m20250126_000001_create_released_regions;
+mod m20250128_000001_create_slug_aliases;
+mod m20250130_000001_create_match_reports;
+mod m20250201_000001_add_film_cache_first_seen;
+mod m20250203_000001_add_release_cache_meta_confirmed_empty;
+mod m20250205_000001_add_release_cache_fallback;
+mod m20250207_000001_add_subscription_confirmation;
+mod m20250209_000001_add_release_cache_raw_note;
+mod m20250211_000001_add_match_reports_unique;
 
 pub struct Migrator;
 
@@ -13,6 +26,19 @@ impl MigratorTrait for Migrator {
             Box::new(m20220101_000001_create_table::Migration),
             Box::new(m20250107_000001_create_provider_cache::Migration),
             Box::new(m20250109_000001_add_provider_cache_unique::Migration),
+            Box::new(m20250115_000001_add_film_cache_match_confidence::Migration),
+            Box::new(m20250120_000001_create_subscriptions::Migration),
+            Box::new(m20250122_000001_create_release_date_history::Migration),
+            Box::new(m20250124_000001_add_provider_cache_display_priority::Migration),
+            Box::new(m20250126_000001_create_released_regions::Migration),
+            Box::new(m20250128_000001_create_slug_aliases::Migration),
+            Box::new(m20250130_000001_create_match_reports::Migration),
+            Box::new(m20250201_000001_add_film_cache_first_seen::Migration),
+            Box::new(m20250203_000001_add_release_cache_meta_confirmed_empty::Migration),
+            Box::new(m20250205_000001_add_release_cache_fallback::Migration),
+            Box::new(m20250207_000001_add_subscription_confirmation::Migration),
+            Box::new(m20250209_000001_add_release_cache_raw_note::Migration),
+            Box::new(m20250211_000001_add_match_reports_unique::Migration),
         ]
     }
 }
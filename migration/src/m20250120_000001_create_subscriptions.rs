@@ -0,0 +1,57 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Subscriptions::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Subscriptions::Id))
+                    .col(string(Subscriptions::Username))
+                    .col(string(Subscriptions::Country))
+                    .col(string(Subscriptions::WebhookUrl))
+                    .col(text_null(Subscriptions::Snapshot))
+                    .col(big_integer(Subscriptions::CreatedAt))
+                    .col(big_integer_null(Subscriptions::CheckedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_subscriptions_unique")
+                    .table(Subscriptions::Table)
+                    .col(Subscriptions::Username)
+                    .col(Subscriptions::Country)
+                    .col(Subscriptions::WebhookUrl)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(Subscriptions::Table).to_owned()).await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Subscriptions {
+    Table,
+    Id,
+    Username,
+    Country,
+    WebhookUrl,
+    Snapshot,
+    CreatedAt,
+    CheckedAt,
+}
@@ -0,0 +1,55 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ReleaseDateHistory::Table)
+                    .if_not_exists()
+                    .col(pk_auto(ReleaseDateHistory::Id))
+                    .col(integer(ReleaseDateHistory::TmdbId))
+                    .col(string(ReleaseDateHistory::Country))
+                    .col(integer(ReleaseDateHistory::ReleaseType))
+                    .col(string(ReleaseDateHistory::PreviousDate))
+                    .col(string(ReleaseDateHistory::NewDate))
+                    .col(big_integer(ReleaseDateHistory::ChangedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_release_date_history_tmdb_country")
+                    .table(ReleaseDateHistory::Table)
+                    .col(ReleaseDateHistory::TmdbId)
+                    .col(ReleaseDateHistory::Country)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(ReleaseDateHistory::Table).to_owned()).await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ReleaseDateHistory {
+    Table,
+    Id,
+    TmdbId,
+    Country,
+    ReleaseType,
+    PreviousDate,
+    NewDate,
+    ChangedAt,
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ReleasedRegions::Table)
+                    .if_not_exists()
+                    .col(integer(ReleasedRegions::TmdbId).primary_key())
+                    .col(string(ReleasedRegions::Countries))
+                    .col(big_integer(ReleasedRegions::CachedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(ReleasedRegions::Table).to_owned()).await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ReleasedRegions {
+    Table,
+    TmdbId,
+    Countries,
+    CachedAt,
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SlugAliases::Table)
+                    .if_not_exists()
+                    .col(string(SlugAliases::Slug).primary_key())
+                    .col(string(SlugAliases::CanonicalSlug))
+                    .col(big_integer(SlugAliases::CreatedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(SlugAliases::Table).to_owned()).await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum SlugAliases {
+    Table,
+    Slug,
+    CanonicalSlug,
+    CreatedAt,
+}
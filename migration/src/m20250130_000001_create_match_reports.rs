@@ -0,0 +1,69 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MatchReports::Table)
+                    .if_not_exists()
+                    .col(pk_auto(MatchReports::Id))
+                    .col(string(MatchReports::Slug))
+                    .col(integer(MatchReports::TmdbId))
+                    .col(big_integer(MatchReports::CreatedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_match_reports_slug")
+                    .table(MatchReports::Table)
+                    .col(MatchReports::Slug)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(MatchOverrides::Table)
+                    .if_not_exists()
+                    .col(string(MatchOverrides::Slug).primary_key())
+                    .col(integer(MatchOverrides::TmdbId))
+                    .col(big_integer(MatchOverrides::CreatedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(MatchOverrides::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(MatchReports::Table).to_owned()).await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum MatchReports {
+    Table,
+    Id,
+    Slug,
+    TmdbId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum MatchOverrides {
+    Table,
+    Slug,
+    TmdbId,
+    CreatedAt,
+}
@@ -0,0 +1,50 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(FilmCache::Table)
+                    .add_column(big_integer_null(FilmCache::FirstSeenAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing films were first seen no later than their last update
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(FilmCache::Table)
+                    .value(FilmCache::FirstSeenAt, Expr::col(FilmCache::UpdatedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(FilmCache::Table)
+                    .drop_column(FilmCache::FirstSeenAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum FilmCache {
+    Table,
+    UpdatedAt,
+    FirstSeenAt,
+}
@@ -0,0 +1,52 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Keep the first report of each match so the unique index can be built
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "DELETE FROM match_reports WHERE id NOT IN (SELECT MIN(id) FROM match_reports \
+                 GROUP BY slug, tmdb_id)",
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_match_reports_unique")
+                    .table(MatchReports::Table)
+                    .col(MatchReports::Slug)
+                    .col(MatchReports::TmdbId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_match_reports_unique")
+                    .table(MatchReports::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum MatchReports {
+    Table,
+    Slug,
+    TmdbId,
+}
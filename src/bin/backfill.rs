@@ -0,0 +1,109 @@
+//! Populates the cache for one or more watchlists without starting the server.
+//!
+//! Usage: `backfill [--dry-run] [--file PROFILES] [USERNAME:COUNTRY ...]`
+//!
+//! A profiles file holds one `username:country` pair per line; blank lines and lines
+//! starting with `#` are ignored.
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use timeboxd::{
+    AppState,
+    config::{Config, parse_profile},
+    models::ReleaseCategory,
+    prewarm::run_profile,
+    processor::ProcessOutcome,
+};
+
+const USAGE: &str = "usage: backfill [--dry-run] [--file PROFILES] [USERNAME:COUNTRY ...]";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            std::env::var("RUST_LOG").unwrap_or_else(|_| "warn,timeboxd=info".to_string()),
+        )
+        .init();
+
+    let mut dry_run = false;
+    let mut profiles = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--file" => {
+                let path = args.next().context(USAGE)?;
+                let contents = std::fs::read_to_string(&path)
+                    .with_context(|| format!("reading profiles from {path}"))?;
+                for line in contents.lines().map(str::trim) {
+                    if line.is_empty() || line.starts_with('#') {
+                        continue;
+                    }
+                    profiles.push(
+                        parse_profile(line)
+                            .with_context(|| format!("invalid profile in {path}: {line}"))?,
+                    );
+                }
+            },
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            },
+            _ => profiles
+                .push(parse_profile(&arg).with_context(|| format!("invalid profile: {arg}"))?),
+        }
+    }
+
+    if profiles.is_empty() {
+        anyhow::bail!(USAGE);
+    }
+
+    let config = Arc::new(Config::from_env()?);
+    let state = AppState::from_config(config).await?;
+
+    let mut failures = 0;
+    for (username, country) in &profiles {
+        let started = std::time::Instant::now();
+
+        match run_profile(&state, username, *country, dry_run).await {
+            Ok((watchlist_count, ProcessOutcome::Films(films))) => {
+                let count = |category| films.iter().filter(|f| f.category == category).count();
+                println!(
+                    "{username}:{country} watchlist={watchlist_count} upcoming={} available={} \
+                     no_dates={} elapsed_ms={}",
+                    count(ReleaseCategory::LocalUpcoming),
+                    count(ReleaseCategory::LocalAlreadyAvailable),
+                    count(ReleaseCategory::NoReleases),
+                    started.elapsed().as_millis()
+                );
+            },
+            Ok((_, ProcessOutcome::Partial(_))) => {
+                failures += 1;
+                eprintln!("{username}:{country} incomplete: some cache operations failed");
+            },
+            Ok((watchlist_count, ProcessOutcome::DryRun(summary))) => {
+                println!(
+                    "{username}:{country} watchlist={watchlist_count} films={} cached_films={} \
+                     tmdb_calls={} release_fetches={} provider_fetches={} (dry run)",
+                    summary.films,
+                    summary.cached_films,
+                    summary.tmdb_calls(),
+                    summary.release_fetches,
+                    summary.provider_fetches
+                );
+            },
+            Err(err) => {
+                failures += 1;
+                eprintln!("{username}:{country} failed: {err:#}");
+            },
+        }
+    }
+
+    if failures > 0 {
+        anyhow::bail!("{failures} of {} profiles failed", profiles.len());
+    }
+
+    Ok(())
+}
@@ -1,16 +1,36 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
 
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set, TransactionTrait};
-use tracing::debug;
+use jiff::civil::Date;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, Set,
+    TransactionTrait, TryInsertResult,
+};
+use tracing::{debug, warn};
 
 use crate::{
+    countries::CountryCode,
     entities::{
-        film_cache, provider_cache, provider_cache_meta, release_cache, release_cache_meta,
+        film_cache, match_overrides, match_reports, provider_cache, provider_cache_meta,
+        release_cache, release_cache_meta, release_date_history, released_regions, slug_aliases,
     },
     error::AppResult,
-    models::{ProviderType, ReleaseDate, ReleaseType, WatchProvider},
+    models::{
+        CountryReleases, MatchConfidence, ProviderType, ReleaseDate, ReleaseDateChange,
+        ReleaseType, WatchProvider, dedup_release_dates, today_in,
+    },
 };
 
+/// Rows per multi-row INSERT. The widest table binds 8 values per row, which keeps each
+/// statement under SQLite's default 999 variable limit.
+const INSERT_CHUNK_SIZE: usize = 100;
+
+/// Reports are sent without signing in, so stop storing them past this many rather than let the
+/// table grow without bound.
+const MAX_MATCH_REPORTS: u64 = 10_000;
+
 #[derive(Clone, Debug)]
 pub struct FilmCacheData {
     pub slug: String,
@@ -18,13 +38,17 @@ pub struct FilmCacheData {
     pub title: String,
     pub year: Option<i16>,
     pub poster_path: Option<String>,
+    pub match_confidence: MatchConfidence,
 }
 
 #[derive(Clone)]
 pub struct CacheManager {
     db: DatabaseConnection,
     film_ttl_seconds: i64,
+    low_confidence_film_ttl_seconds: i64,
+    missing_poster_ttl_seconds: i64,
     release_ttl_seconds: i64,
+    empty_release_ttl_seconds: i64,
     provider_ttl_seconds: i64,
 }
 
@@ -32,17 +56,37 @@ impl CacheManager {
     pub fn new(
         db: DatabaseConnection,
         film_ttl_days: i64,
+        low_confidence_film_ttl_days: i64,
+        missing_poster_ttl_hours: i64,
         release_ttl_hours: i64,
+        empty_release_ttl_hours: i64,
         provider_ttl_days: i64,
     ) -> Self {
         Self {
             db,
             film_ttl_seconds: film_ttl_days * 86_400,
+            low_confidence_film_ttl_seconds: low_confidence_film_ttl_days * 86_400,
+            missing_poster_ttl_seconds: missing_poster_ttl_hours * 3_600,
             release_ttl_seconds: release_ttl_hours * 3_600,
+            empty_release_ttl_seconds: empty_release_ttl_hours.min(release_ttl_hours) * 3_600,
             provider_ttl_seconds: provider_ttl_days * 86_400,
         }
     }
 
+    /// A cache backed by a fresh SQLite file, so tests don't share state.
+    #[cfg(test)]
+    pub(crate) async fn for_tests() -> Self {
+        static NEXT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let path =
+            std::env::temp_dir().join(format!("timeboxd-test-{}-{n}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = crate::db::connect_and_migrate(&format!("sqlite://{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        CacheManager::new(db, 30, 7, 24, 24, 6, 7)
+    }
+
     pub fn db(&self) -> &DatabaseConnection {
         &self.db
     }
@@ -62,7 +106,7 @@ impl CacheManager {
 
         let mut result = HashMap::new();
         for film in films {
-            if self.is_film_fresh(film.updated_at) {
+            if self.is_film_fresh(&film) {
                 result.insert(film.letterboxd_slug.clone(), film);
             }
         }
@@ -70,25 +114,55 @@ impl CacheManager {
         Ok(result)
     }
 
+    /// When each of these films was first cached, regardless of freshness.
+    pub async fn first_seen(&self, slugs: &[String]) -> AppResult<HashMap<String, i64>> {
+        if slugs.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let films = film_cache::Entity::find()
+            .filter(film_cache::Column::LetterboxdSlug.is_in(slugs.iter().cloned()))
+            .all(&self.db)
+            .await?;
+
+        Ok(films.into_iter().filter_map(|f| Some((f.letterboxd_slug, f.first_seen_at?))).collect())
+    }
+
+    /// Any cached film with this TMDB ID, regardless of freshness.
+    pub async fn get_film_by_tmdb_id(&self, tmdb_id: i32) -> AppResult<Option<film_cache::Model>> {
+        Ok(film_cache::Entity::find()
+            .filter(film_cache::Column::TmdbId.eq(tmdb_id))
+            .one(&self.db)
+            .await?)
+    }
+
     pub async fn upsert_films(&self, films: Vec<FilmCacheData>) -> AppResult<()> {
         if films.is_empty() {
             return Ok(());
         }
 
         let now = now_sec();
-        let txn = self.db.begin().await?;
-
-        for film in films {
-            let model = film_cache::ActiveModel {
+        let started = std::time::Instant::now();
+        let film_count = films.len();
+        let models: Vec<film_cache::ActiveModel> = films
+            .into_iter()
+            .map(|film| film_cache::ActiveModel {
                 letterboxd_slug: Set(film.slug),
                 tmdb_id: Set(film.tmdb_id),
                 title: Set(film.title),
                 year: Set(film.year.map(|y| y as i32)),
                 poster_path: Set(film.poster_path),
                 updated_at: Set(now),
-            };
+                match_confidence: Set(film.match_confidence.as_code()),
+                // Left out of the conflict update below, so only set for new films
+                first_seen_at: Set(Some(now)),
+            })
+            .collect();
+
+        let txn = self.db.begin().await?;
 
-            film_cache::Entity::insert(model)
+        for chunk in models.chunks(INSERT_CHUNK_SIZE) {
+            film_cache::Entity::insert_many(chunk.to_vec())
                 .on_conflict(
                     sea_orm::sea_query::OnConflict::column(film_cache::Column::LetterboxdSlug)
                         .update_columns([
@@ -97,6 +171,7 @@ impl CacheManager {
                             film_cache::Column::Year,
                             film_cache::Column::PosterPath,
                             film_cache::Column::UpdatedAt,
+                            film_cache::Column::MatchConfidence,
                         ])
                         .to_owned(),
                 )
@@ -106,18 +181,188 @@ impl CacheManager {
 
         txn.commit().await?;
 
+        debug!(
+            film_count = film_count,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "upserted films"
+        );
+
+        Ok(())
+    }
+
+    pub async fn invalidate_film(&self, slug: &str) -> AppResult<()> {
+        let txn = self.db.begin().await?;
+
+        let film = film_cache::Entity::find_by_id(slug.to_string()).one(&txn).await?;
+
+        if let Some(tmdb_id) = film.and_then(|f| f.tmdb_id) {
+            release_cache::Entity::delete_many()
+                .filter(release_cache::Column::TmdbId.eq(tmdb_id))
+                .exec(&txn)
+                .await?;
+            release_cache_meta::Entity::delete_many()
+                .filter(release_cache_meta::Column::TmdbId.eq(tmdb_id))
+                .exec(&txn)
+                .await?;
+            provider_cache::Entity::delete_many()
+                .filter(provider_cache::Column::TmdbId.eq(tmdb_id))
+                .exec(&txn)
+                .await?;
+            provider_cache_meta::Entity::delete_many()
+                .filter(provider_cache_meta::Column::TmdbId.eq(tmdb_id))
+                .exec(&txn)
+                .await?;
+        }
+
+        film_cache::Entity::delete_by_id(slug.to_string()).exec(&txn).await?;
+
+        txn.commit().await?;
+
+        debug!(slug = %slug, "invalidated film cache");
+
+        Ok(())
+    }
+
+    /// Maps any renamed slugs among `slugs` to their current slug.
+    pub async fn get_slug_aliases(&self, slugs: &[String]) -> AppResult<HashMap<String, String>> {
+        if slugs.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let aliases = slug_aliases::Entity::find()
+            .filter(slug_aliases::Column::Slug.is_in(slugs.iter().cloned()))
+            .all(&self.db)
+            .await?;
+
+        Ok(aliases.into_iter().map(|a| (a.slug, a.canonical_slug)).collect())
+    }
+
+    pub async fn put_slug_aliases(&self, aliases: &[(String, String)]) -> AppResult<()> {
+        if aliases.is_empty() {
+            return Ok(());
+        }
+
+        let now = now_sec();
+        let models: Vec<slug_aliases::ActiveModel> = aliases
+            .iter()
+            .map(|(slug, canonical_slug)| slug_aliases::ActiveModel {
+                slug: Set(slug.clone()),
+                canonical_slug: Set(canonical_slug.clone()),
+                created_at: Set(now),
+            })
+            .collect();
+
+        for chunk in models.chunks(INSERT_CHUNK_SIZE) {
+            slug_aliases::Entity::insert_many(chunk.to_vec())
+                .on_conflict(
+                    sea_orm::sea_query::OnConflict::column(slug_aliases::Column::Slug)
+                        .update_columns([
+                            slug_aliases::Column::CanonicalSlug,
+                            slug_aliases::Column::CreatedAt,
+                        ])
+                        .to_owned(),
+                )
+                .exec(&self.db)
+                .await?;
+        }
+
+        debug!(alias_count = aliases.len(), "cached slug aliases");
+
+        Ok(())
+    }
+
+    /// Records that `slug` looks wrongly matched to `tmdb_id`, returning whether a new report
+    /// was stored. Only matches the cache actually made can be reported, each one once, and
+    /// nothing is stored once `MAX_MATCH_REPORTS` are waiting.
+    pub async fn record_match_report(&self, slug: &str, tmdb_id: i32) -> AppResult<bool> {
+        let matched = film_cache::Entity::find()
+            .filter(film_cache::Column::LetterboxdSlug.eq(slug))
+            .filter(film_cache::Column::TmdbId.eq(tmdb_id))
+            .one(&self.db)
+            .await?;
+        if matched.is_none() {
+            debug!(slug, tmdb_id, "ignoring report for a match that wasn't made");
+            return Ok(false);
+        }
+
+        let stored = match_reports::Entity::find().count(&self.db).await?;
+        if stored >= MAX_MATCH_REPORTS {
+            warn!(slug, tmdb_id, stored, "match report limit reached, dropping report");
+            return Ok(false);
+        }
+
+        let inserted = match_reports::Entity::insert(match_reports::ActiveModel {
+            slug: Set(slug.to_string()),
+            tmdb_id: Set(tmdb_id),
+            created_at: Set(now_sec()),
+            ..Default::default()
+        })
+        .on_conflict(
+            sea_orm::sea_query::OnConflict::columns([
+                match_reports::Column::Slug,
+                match_reports::Column::TmdbId,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .do_nothing()
+        .exec(&self.db)
+        .await?;
+
+        Ok(matches!(inserted, TryInsertResult::Inserted(_)))
+    }
+
+    pub async fn get_override(&self, slug: &str) -> AppResult<Option<i32>> {
+        let found = match_overrides::Entity::find_by_id(slug.to_string()).one(&self.db).await?;
+        Ok(found.map(|o| o.tmdb_id))
+    }
+
+    pub async fn get_overrides(&self, slugs: &[String]) -> AppResult<HashMap<String, i32>> {
+        if slugs.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let overrides = match_overrides::Entity::find()
+            .filter(match_overrides::Column::Slug.is_in(slugs.iter().cloned()))
+            .all(&self.db)
+            .await?;
+
+        Ok(overrides.into_iter().map(|o| (o.slug, o.tmdb_id)).collect())
+    }
+
+    /// Pins `slug` to `tmdb_id` and drops its cached film so the next run resolves it again.
+    pub async fn put_override(&self, slug: &str, tmdb_id: i32) -> AppResult<()> {
+        match_overrides::Entity::insert(match_overrides::ActiveModel {
+            slug: Set(slug.to_string()),
+            tmdb_id: Set(tmdb_id),
+            created_at: Set(now_sec()),
+        })
+        .on_conflict(
+            sea_orm::sea_query::OnConflict::column(match_overrides::Column::Slug)
+                .update_columns([
+                    match_overrides::Column::TmdbId,
+                    match_overrides::Column::CreatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(&self.db)
+        .await?;
+
+        self.invalidate_film(slug).await?;
+        debug!(slug, tmdb_id, "stored match override");
+
         Ok(())
     }
 
     pub async fn get_releases(
         &self,
-        requests: &[(i32, String)],
-    ) -> AppResult<HashMap<(i32, String), (Vec<ReleaseDate>, Vec<ReleaseDate>)>> {
+        requests: &[(i32, CountryCode)],
+    ) -> AppResult<HashMap<(i32, CountryCode), CountryReleases>> {
         if requests.is_empty() {
             return Ok(HashMap::new());
         }
 
-        let request_set: HashSet<(i32, String)> = requests.iter().cloned().collect();
+        let request_set = requested_keys(requests);
         let tmdb_ids: Vec<i32> = requests.iter().map(|(id, _)| *id).collect();
 
         debug!(
@@ -135,21 +380,21 @@ impl CacheManager {
         debug!(meta_count = metas.len(), "cache lookup: found meta entries");
 
         // Filter to only fresh meta entries that match our requested (tmdb_id, country) pairs
-        let fresh_requests: Vec<(i32, String)> = metas
+        let fresh_requests: Vec<(i32, CountryCode, i64, bool)> = metas
             .into_iter()
-            .filter(|meta| {
+            .filter_map(|meta| {
                 let is_fresh = self.is_release_fresh(meta.cached_at);
-                let in_request = request_set.contains(&(meta.tmdb_id, meta.country.clone()));
+                let requested = request_set.get(&(meta.tmdb_id, meta.country.as_str())).copied();
                 debug!(
                     tmdb_id = meta.tmdb_id,
                     country = %meta.country,
                     is_fresh = is_fresh,
-                    in_request = in_request,
+                    in_request = requested.is_some(),
                     "cache lookup: checking meta"
                 );
-                is_fresh && in_request
+                let country = requested.filter(|_| is_fresh)?;
+                Some((meta.tmdb_id, country, meta.cached_at, meta.confirmed_empty))
             })
-            .map(|meta| (meta.tmdb_id, meta.country))
             .collect();
 
         debug!(fresh_count = fresh_requests.len(), "cache lookup: fresh requests");
@@ -158,8 +403,11 @@ impl CacheManager {
             return Ok(HashMap::new());
         }
 
-        let fresh_tmdb_ids: Vec<i32> = fresh_requests.iter().map(|(id, _)| *id).collect();
-        let fresh_set: HashSet<(i32, String)> = fresh_requests.iter().cloned().collect();
+        let fresh_tmdb_ids: Vec<i32> = fresh_requests.iter().map(|(id, ..)| *id).collect();
+        let fresh_set: HashMap<(i32, &str), CountryCode> = fresh_requests
+            .iter()
+            .map(|&(id, country, ..)| ((id, country.as_str()), country))
+            .collect();
 
         // Query all release data for fresh tmdb_ids
         let rows = release_cache::Entity::find()
@@ -168,21 +416,27 @@ impl CacheManager {
             .await?;
 
         // Group rows by (tmdb_id, country), filtering to only requested pairs
-        let mut grouped: HashMap<(i32, String), Vec<_>> = HashMap::new();
+        let mut grouped: HashMap<(i32, CountryCode), Vec<_>> = HashMap::new();
         for row in rows {
-            let key = (row.tmdb_id, row.country.clone());
-            if fresh_set.contains(&key) {
-                grouped.entry(key).or_default().push(row);
+            if let Some(&country) = fresh_set.get(&(row.tmdb_id, row.country.as_str())) {
+                grouped.entry((row.tmdb_id, country)).or_default().push(row);
             }
         }
 
         let mut result = HashMap::new();
 
-        // Include all fresh requests in result, even if they have no release rows
-        for key in fresh_requests {
+        // Include fresh requests with no release rows too. Unless TMDB confirmed the film has no
+        // dates anywhere, revalidate those sooner in case the gap came from partial TMDB data
+        for (tmdb_id, country, cached_at, confirmed_empty) in fresh_requests {
+            let key = (tmdb_id, country);
             let rows = grouped.remove(&key).unwrap_or_default();
+            if rows.is_empty() && !confirmed_empty && !self.is_empty_release_fresh(cached_at) {
+                debug!(tmdb_id, country = %key.1, "cache lookup: empty releases expired");
+                continue;
+            }
             let mut theatrical = Vec::new();
             let mut streaming = Vec::new();
+            let mut fallback = Vec::new();
 
             for row in rows {
                 let Ok(date) = row.release_date.parse() else {
@@ -191,22 +445,87 @@ impl CacheManager {
                 let Some(kind) = ReleaseType::from_tmdb_code(row.release_type) else {
                     continue;
                 };
-                let rd = ReleaseDate { date, release_type: kind, note: row.note };
+                let rd = ReleaseDate {
+                    date,
+                    release_type: kind,
+                    note: row.note,
+                    raw_note: row.raw_note,
+                    source_country: None,
+                };
                 match kind {
-                    ReleaseType::Theatrical => theatrical.push(rd),
+                    _ if row.fallback => fallback.push(rd),
+                    ReleaseType::Premiere
+                    | ReleaseType::TheatricalLimited
+                    | ReleaseType::Theatrical => theatrical.push(rd),
                     ReleaseType::Digital => streaming.push(rd),
+                    ReleaseType::Physical => fallback.push(rd),
                 }
             }
 
-            theatrical.sort_by_key(|r| r.date);
-            streaming.sort_by_key(|r| r.date);
+            for list in [&mut theatrical, &mut streaming, &mut fallback] {
+                dedup_release_dates(list);
+            }
 
-            result.insert(key, (theatrical, streaming));
+            let country = key.1.to_string();
+            result.insert(key, CountryReleases { country, theatrical, streaming, fallback });
         }
 
         Ok(result)
     }
 
+    /// Returns the `cached_at` timestamp for each requested `(tmdb_id, country)` pair that has
+    /// been cached.
+    pub async fn release_cached_at(
+        &self,
+        requests: &[(i32, CountryCode)],
+    ) -> AppResult<Vec<(i32, CountryCode, i64)>> {
+        if requests.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let request_set = requested_keys(requests);
+        let tmdb_ids: Vec<i32> = request_set.keys().map(|(id, _)| *id).collect();
+
+        let metas = release_cache_meta::Entity::find()
+            .filter(release_cache_meta::Column::TmdbId.is_in(tmdb_ids))
+            .all(&self.db)
+            .await?;
+
+        Ok(metas
+            .into_iter()
+            .filter_map(|meta| {
+                let country = request_set.get(&(meta.tmdb_id, meta.country.as_str()))?;
+                Some((meta.tmdb_id, *country, meta.cached_at))
+            })
+            .collect())
+    }
+
+    /// When each of these `(tmdb_id, country)` provider lists was cached, for those that are.
+    pub async fn provider_cached_at(
+        &self,
+        requests: &[(i32, CountryCode)],
+    ) -> AppResult<Vec<(i32, CountryCode, i64)>> {
+        if requests.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let request_set = requested_keys(requests);
+        let tmdb_ids: Vec<i32> = request_set.keys().map(|(id, _)| *id).collect();
+
+        let metas = provider_cache_meta::Entity::find()
+            .filter(provider_cache_meta::Column::TmdbId.is_in(tmdb_ids))
+            .all(&self.db)
+            .await?;
+
+        Ok(metas
+            .into_iter()
+            .filter_map(|meta| {
+                let country = request_set.get(&(meta.tmdb_id, meta.country.as_str()))?;
+                Some((meta.tmdb_id, *country, meta.cached_at))
+            })
+            .collect())
+    }
+
     pub async fn put_releases(
         &self,
         tmdb_id: i32,
@@ -224,17 +543,23 @@ impl CacheManager {
             .exec(&txn)
             .await?;
 
-        for rel in theatrical.iter().chain(streaming.iter()) {
-            let model = release_cache::ActiveModel {
+        let release_models: Vec<release_cache::ActiveModel> = theatrical
+            .iter()
+            .chain(streaming.iter())
+            .map(|rel| release_cache::ActiveModel {
                 id: Default::default(),
                 tmdb_id: Set(tmdb_id),
                 country: Set(country.to_string()),
                 release_date: Set(rel.date.to_string()),
                 release_type: Set(rel.release_type.as_tmdb_code()),
                 note: Set(rel.note.clone()),
+                raw_note: Set(rel.raw_note.clone()),
                 cached_at: Set(now),
-            };
-            release_cache::Entity::insert(model).exec(&txn).await?;
+                fallback: Set(false),
+            })
+            .collect();
+        for chunk in release_models.chunks(INSERT_CHUNK_SIZE) {
+            release_cache::Entity::insert_many(chunk.to_vec()).exec(&txn).await?;
         }
 
         let meta = release_cache_meta::ActiveModel {
@@ -242,6 +567,7 @@ impl CacheManager {
             tmdb_id: Set(tmdb_id),
             country: Set(country.to_string()),
             cached_at: Set(now),
+            confirmed_empty: Set(false),
         };
 
         release_cache_meta::Entity::insert(meta)
@@ -250,7 +576,10 @@ impl CacheManager {
                     release_cache_meta::Column::TmdbId,
                     release_cache_meta::Column::Country,
                 ])
-                .update_columns([release_cache_meta::Column::CachedAt])
+                .update_columns([
+                    release_cache_meta::Column::CachedAt,
+                    release_cache_meta::Column::ConfirmedEmpty,
+                ])
                 .to_owned(),
             )
             .exec(&txn)
@@ -264,39 +593,100 @@ impl CacheManager {
     pub async fn put_releases_multi_country(
         &self,
         tmdb_id: i32,
-        countries: &[crate::models::CountryReleases],
+        countries: &[CountryReleases],
+        confirmed_empty: bool,
     ) -> AppResult<()> {
         let now = now_sec();
+        let started = std::time::Instant::now();
         let country_codes: Vec<String> = countries.iter().map(|c| c.country.clone()).collect();
 
         let txn = self.db.begin().await?;
 
-        // Only delete release data for the specific countries we're updating
-        release_cache::Entity::delete_many()
+        // Record moved dates before the previous rows are overwritten
+        let previous_rows = release_cache::Entity::find()
             .filter(release_cache::Column::TmdbId.eq(tmdb_id))
-            .filter(release_cache::Column::Country.is_in(country_codes))
-            .exec(&txn)
+            .filter(release_cache::Column::Country.is_in(country_codes.clone()))
+            .all(&txn)
             .await?;
-
+        let today = today_in("UTC");
         for country_data in countries {
-            for rel in country_data.theatrical.iter().chain(country_data.streaming.iter()) {
-                let model = release_cache::ActiveModel {
+            let previous = previous_rows.iter().filter(|row| row.country == country_data.country);
+            let previous = previous.filter_map(|row| {
+                Some((row.release_type, row.note.clone(), row.release_date.parse().ok()?))
+            });
+            let current = country_data
+                .theatrical
+                .iter()
+                .chain(&country_data.streaming)
+                .map(|rel| (rel.release_type.as_tmdb_code(), rel.note.clone(), rel.date));
+
+            for (release_type, previous_date, new_date) in moved_dates(previous, current, today) {
+                debug!(
+                    tmdb_id = tmdb_id,
+                    country = %country_data.country,
+                    release_type = release_type,
+                    previous_date = %previous_date,
+                    new_date = %new_date,
+                    "release date moved"
+                );
+                let change = release_date_history::ActiveModel {
                     id: Default::default(),
                     tmdb_id: Set(tmdb_id),
                     country: Set(country_data.country.clone()),
-                    release_date: Set(rel.date.to_string()),
-                    release_type: Set(rel.release_type.as_tmdb_code()),
-                    note: Set(rel.note.clone()),
-                    cached_at: Set(now),
+                    release_type: Set(release_type),
+                    previous_date: Set(previous_date.to_string()),
+                    new_date: Set(new_date.to_string()),
+                    changed_at: Set(now),
                 };
-                release_cache::Entity::insert(model).exec(&txn).await?;
+                // Only the latest move per type is ever shown, so older ones are dropped
+                release_date_history::Entity::delete_many()
+                    .filter(release_date_history::Column::TmdbId.eq(tmdb_id))
+                    .filter(release_date_history::Column::Country.eq(&country_data.country))
+                    .filter(release_date_history::Column::ReleaseType.eq(release_type))
+                    .exec(&txn)
+                    .await?;
+                release_date_history::Entity::insert(change).exec(&txn).await?;
             }
+        }
+
+        // Only delete release data for the specific countries we're updating
+        release_cache::Entity::delete_many()
+            .filter(release_cache::Column::TmdbId.eq(tmdb_id))
+            .filter(release_cache::Column::Country.is_in(country_codes))
+            .exec(&txn)
+            .await?;
+
+        let release_models: Vec<release_cache::ActiveModel> = countries
+            .iter()
+            .flat_map(|country_data| {
+                let main = country_data.theatrical.iter().chain(&country_data.streaming);
+                main.map(|rel| (rel, false))
+                    .chain(country_data.fallback.iter().map(|rel| (rel, true)))
+                    .map(|(rel, fallback)| release_cache::ActiveModel {
+                        id: Default::default(),
+                        tmdb_id: Set(tmdb_id),
+                        country: Set(country_data.country.clone()),
+                        release_date: Set(rel.date.to_string()),
+                        release_type: Set(rel.release_type.as_tmdb_code()),
+                        note: Set(rel.note.clone()),
+                        raw_note: Set(rel.raw_note.clone()),
+                        cached_at: Set(now),
+                        fallback: Set(fallback),
+                    })
+            })
+            .collect();
+        let release_count = release_models.len();
+        for chunk in release_models.chunks(INSERT_CHUNK_SIZE) {
+            release_cache::Entity::insert_many(chunk.to_vec()).exec(&txn).await?;
+        }
 
+        for country_data in countries {
             let meta = release_cache_meta::ActiveModel {
                 id: Default::default(),
                 tmdb_id: Set(tmdb_id),
                 country: Set(country_data.country.clone()),
                 cached_at: Set(now),
+                confirmed_empty: Set(confirmed_empty),
             };
 
             release_cache_meta::Entity::insert(meta)
@@ -305,7 +695,10 @@ impl CacheManager {
                         release_cache_meta::Column::TmdbId,
                         release_cache_meta::Column::Country,
                     ])
-                    .update_columns([release_cache_meta::Column::CachedAt])
+                    .update_columns([
+                        release_cache_meta::Column::CachedAt,
+                        release_cache_meta::Column::ConfirmedEmpty,
+                    ])
                     .to_owned(),
                 )
                 .exec(&txn)
@@ -314,9 +707,107 @@ impl CacheManager {
 
         txn.commit().await?;
 
+        debug!(
+            tmdb_id = tmdb_id,
+            country_count = countries.len(),
+            release_count = release_count,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "cached releases"
+        );
+
+        Ok(())
+    }
+
+    pub async fn put_released_regions(&self, tmdb_id: i32, countries: &[String]) -> AppResult<()> {
+        let model = released_regions::ActiveModel {
+            tmdb_id: Set(tmdb_id),
+            countries: Set(countries.join(",")),
+            cached_at: Set(now_sec()),
+        };
+
+        released_regions::Entity::insert(model)
+            .on_conflict(
+                sea_orm::sea_query::OnConflict::column(released_regions::Column::TmdbId)
+                    .update_columns([
+                        released_regions::Column::Countries,
+                        released_regions::Column::CachedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await?;
+
         Ok(())
     }
 
+    /// Countries each film has already been released in, as of its last release fetch.
+    pub async fn get_released_regions(
+        &self,
+        tmdb_ids: &[i32],
+    ) -> AppResult<HashMap<i32, Vec<String>>> {
+        if tmdb_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let rows = released_regions::Entity::find()
+            .filter(released_regions::Column::TmdbId.is_in(tmdb_ids.to_vec()))
+            .all(&self.db)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let countries = row
+                    .countries
+                    .split(',')
+                    .filter(|c| !c.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                (row.tmdb_id, countries)
+            })
+            .collect())
+    }
+
+    /// Returns the most recent date change per country and release type for each film.
+    pub async fn get_date_changes(
+        &self,
+        tmdb_ids: &[i32],
+    ) -> AppResult<HashMap<i32, Vec<ReleaseDateChange>>> {
+        if tmdb_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let mut rows = release_date_history::Entity::find()
+            .filter(release_date_history::Column::TmdbId.is_in(tmdb_ids.to_vec()))
+            .all(&self.db)
+            .await?;
+        rows.sort_by_key(|row| Reverse(row.changed_at));
+
+        let mut seen = HashSet::new();
+        let mut result: HashMap<i32, Vec<ReleaseDateChange>> = HashMap::new();
+        for row in rows {
+            if !seen.insert((row.tmdb_id, row.country.clone(), row.release_type)) {
+                continue;
+            }
+            let (Ok(previous_date), Ok(new_date)) =
+                (row.previous_date.parse(), row.new_date.parse())
+            else {
+                continue;
+            };
+            let Some(release_type) = ReleaseType::from_tmdb_code(row.release_type) else {
+                continue;
+            };
+            result.entry(row.tmdb_id).or_default().push(ReleaseDateChange {
+                country: row.country,
+                release_type,
+                previous_date,
+                new_date,
+            });
+        }
+
+        Ok(result)
+    }
+
     pub async fn clear_mock_release_dates(&self) -> AppResult<()> {
         release_cache::Entity::delete_many()
             .filter(release_cache::Column::Note.contains("Mock"))
@@ -328,13 +819,13 @@ impl CacheManager {
 
     pub async fn get_providers(
         &self,
-        requests: &[(i32, String)],
-    ) -> AppResult<HashMap<(i32, String), Vec<WatchProvider>>> {
+        requests: &[(i32, CountryCode)],
+    ) -> AppResult<HashMap<(i32, CountryCode), Vec<WatchProvider>>> {
         if requests.is_empty() {
             return Ok(HashMap::new());
         }
 
-        let request_set: HashSet<(i32, String)> = requests.iter().cloned().collect();
+        let request_set = requested_keys(requests);
         let tmdb_ids: Vec<i32> = requests.iter().map(|(id, _)| *id).collect();
 
         debug!(
@@ -350,14 +841,13 @@ impl CacheManager {
 
         debug!(meta_count = metas.len(), "provider cache lookup: found meta entries");
 
-        let fresh_requests: Vec<(i32, String)> = metas
+        let fresh_requests: Vec<(i32, CountryCode)> = metas
             .into_iter()
-            .filter(|meta| {
-                let is_fresh = self.is_provider_fresh(meta.cached_at);
-                let in_request = request_set.contains(&(meta.tmdb_id, meta.country.clone()));
-                is_fresh && in_request
+            .filter(|meta| self.is_provider_fresh(meta.cached_at))
+            .filter_map(|meta| {
+                let country = request_set.get(&(meta.tmdb_id, meta.country.as_str()))?;
+                Some((meta.tmdb_id, *country))
             })
-            .map(|meta| (meta.tmdb_id, meta.country))
             .collect();
 
         debug!(fresh_count = fresh_requests.len(), "provider cache lookup: fresh requests");
@@ -367,18 +857,17 @@ impl CacheManager {
         }
 
         let fresh_tmdb_ids: Vec<i32> = fresh_requests.iter().map(|(id, _)| *id).collect();
-        let fresh_set: HashSet<(i32, String)> = fresh_requests.iter().cloned().collect();
+        let fresh_set = requested_keys(&fresh_requests);
 
         let rows = provider_cache::Entity::find()
             .filter(provider_cache::Column::TmdbId.is_in(fresh_tmdb_ids))
             .all(&self.db)
             .await?;
 
-        let mut grouped: HashMap<(i32, String), Vec<_>> = HashMap::new();
+        let mut grouped: HashMap<(i32, CountryCode), Vec<_>> = HashMap::new();
         for row in rows {
-            let key = (row.tmdb_id, row.country.clone());
-            if fresh_set.contains(&key) {
-                grouped.entry(key).or_default().push(row);
+            if let Some(&country) = fresh_set.get(&(row.tmdb_id, row.country.as_str())) {
+                grouped.entry((row.tmdb_id, country)).or_default().push(row);
             }
         }
 
@@ -386,7 +875,7 @@ impl CacheManager {
 
         for key in fresh_requests {
             let rows = grouped.remove(&key).unwrap_or_default();
-            let providers: Vec<WatchProvider> = rows
+            let mut providers: Vec<WatchProvider> = rows
                 .into_iter()
                 .filter_map(|row| {
                     Some(WatchProvider {
@@ -395,9 +884,12 @@ impl CacheManager {
                         logo_path: row.logo_path,
                         link: row.link,
                         provider_type: ProviderType::from_code(row.provider_type)?,
+                        display_priority: row.display_priority,
                     })
                 })
                 .collect();
+            // Row order isn't guaranteed, so fix one for stable rendering
+            providers.sort_by(|a, b| provider_order(a).cmp(&provider_order(b)));
             result.insert(key, providers);
         }
 
@@ -407,7 +899,7 @@ impl CacheManager {
     pub async fn put_providers(
         &self,
         tmdb_id: i32,
-        country: &str,
+        country: CountryCode,
         providers: &[WatchProvider],
     ) -> AppResult<()> {
         if providers.is_empty() {
@@ -415,10 +907,10 @@ impl CacheManager {
         }
 
         let now = now_sec();
-        let txn = self.db.begin().await?;
-
-        for provider in providers {
-            let model = provider_cache::ActiveModel {
+        let started = std::time::Instant::now();
+        let models: Vec<provider_cache::ActiveModel> = providers
+            .iter()
+            .map(|provider| provider_cache::ActiveModel {
                 id: Default::default(),
                 tmdb_id: Set(tmdb_id),
                 country: Set(country.to_string()),
@@ -428,8 +920,14 @@ impl CacheManager {
                 link: Set(provider.link.clone()),
                 provider_type: Set(provider.provider_type.as_code()),
                 cached_at: Set(now),
-            };
-            provider_cache::Entity::insert(model)
+                display_priority: Set(provider.display_priority),
+            })
+            .collect();
+
+        let txn = self.db.begin().await?;
+
+        for chunk in models.chunks(INSERT_CHUNK_SIZE) {
+            provider_cache::Entity::insert_many(chunk.to_vec())
                 .on_conflict(
                     sea_orm::sea_query::OnConflict::columns([
                         provider_cache::Column::TmdbId,
@@ -442,6 +940,7 @@ impl CacheManager {
                         provider_cache::Column::LogoPath,
                         provider_cache::Column::Link,
                         provider_cache::Column::CachedAt,
+                        provider_cache::Column::DisplayPriority,
                     ])
                     .to_owned(),
                 )
@@ -470,22 +969,199 @@ impl CacheManager {
 
         txn.commit().await?;
 
+        debug!(
+            tmdb_id = tmdb_id,
+            country = %country,
+            provider_count = providers.len(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "cached providers"
+        );
+
         Ok(())
     }
 
-    fn is_film_fresh(&self, cached_at: i64) -> bool {
-        now_sec().saturating_sub(cached_at) <= self.film_ttl_seconds
+    fn is_film_fresh(&self, film: &film_cache::Model) -> bool {
+        // Films that didn't resolve are retried as soon as weak matches are
+        let low_confidence = film.tmdb_id.is_none()
+            || MatchConfidence::from_code(film.match_confidence) == Some(MatchConfidence::Low);
+        let mut ttl = if low_confidence {
+            self.low_confidence_film_ttl_seconds
+        } else {
+            self.film_ttl_seconds
+        };
+        // A missing poster is usually a failed TMDB lookup, so retry it sooner
+        if film.tmdb_id.is_some() && film.poster_path.is_none() {
+            ttl = ttl.min(self.missing_poster_ttl_seconds);
+        }
+        now_sec().saturating_sub(film.updated_at) <= ttl
     }
 
     fn is_release_fresh(&self, cached_at: i64) -> bool {
         now_sec().saturating_sub(cached_at) <= self.release_ttl_seconds
     }
 
+    fn is_empty_release_fresh(&self, cached_at: i64) -> bool {
+        now_sec().saturating_sub(cached_at) <= self.empty_release_ttl_seconds
+    }
+
     fn is_provider_fresh(&self, cached_at: i64) -> bool {
         now_sec().saturating_sub(cached_at) <= self.provider_ttl_seconds
     }
 }
 
+/// Requested `(tmdb_id, country)` pairs keyed the way cache rows store them, so rows for
+/// other countries never need parsing.
+fn requested_keys(requests: &[(i32, CountryCode)]) -> HashMap<(i32, &'static str), CountryCode> {
+    requests.iter().map(|&(tmdb_id, country)| ((tmdb_id, country.as_str()), country)).collect()
+}
+
+/// `(release type, previous date, new date)` for each release that moved, comparing the
+/// earliest date per release type and note. Only upcoming dates are compared, so a release
+/// that simply passes isn't mistaken for a move to the next one.
+fn moved_dates(
+    previous: impl IntoIterator<Item = (i32, Option<String>, Date)>,
+    current: impl IntoIterator<Item = (i32, Option<String>, Date)>,
+    today: Date,
+) -> Vec<(i32, Date, Date)> {
+    let earliest_upcoming = |releases: &mut dyn Iterator<Item = (i32, Option<String>, Date)>| {
+        let mut earliest: HashMap<(i32, Option<String>), Date> = HashMap::new();
+        for (release_type, note, date) in releases.filter(|&(_, _, date)| date > today) {
+            let entry = earliest.entry((release_type, note)).or_insert(date);
+            *entry = (*entry).min(date);
+        }
+        earliest
+    };
+    let previous = earliest_upcoming(&mut previous.into_iter());
+
+    let mut moves: Vec<_> = earliest_upcoming(&mut current.into_iter())
+        .into_iter()
+        .filter_map(|(key, new_date)| {
+            let previous_date = *previous.get(&key)?;
+            (previous_date != new_date).then_some((key.0, previous_date, new_date))
+        })
+        .collect();
+    moves.sort();
+    moves
+}
+
 fn now_sec() -> i64 {
     jiff::Timestamp::now().as_second()
 }
+
+fn provider_order(provider: &WatchProvider) -> (i32, i32, &str, i32) {
+    (
+        provider.provider_type.as_code(),
+        provider.display_priority,
+        &provider.provider_name,
+        provider.provider_id,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::civil::date;
+
+    use super::*;
+
+    const TODAY: Date = date(2025, 6, 1);
+
+    fn rel(
+        release_type: ReleaseType,
+        note: Option<&str>,
+        date: Date,
+    ) -> (i32, Option<String>, Date) {
+        (release_type.as_tmdb_code(), note.map(str::to_string), date)
+    }
+
+    #[test]
+    fn postponed_release_is_a_move() {
+        let previous = [rel(ReleaseType::Theatrical, None, date(2025, 7, 1))];
+        let current = [rel(ReleaseType::Theatrical, None, date(2025, 8, 15))];
+
+        assert_eq!(
+            moved_dates(previous, current, TODAY),
+            vec![(ReleaseType::Theatrical.as_tmdb_code(), date(2025, 7, 1), date(2025, 8, 15))]
+        );
+    }
+
+    #[test]
+    fn passing_the_earliest_date_is_not_a_move() {
+        let previous = [
+            rel(ReleaseType::Theatrical, Some("Premiere"), date(2025, 5, 20)),
+            rel(ReleaseType::Theatrical, None, date(2025, 7, 1)),
+        ];
+        let current = previous.clone();
+
+        assert!(moved_dates(previous, current, TODAY).is_empty());
+    }
+
+    #[test]
+    fn dates_with_different_notes_are_compared_separately() {
+        let previous = [rel(ReleaseType::Theatrical, Some("IMAX"), date(2025, 7, 1))];
+        let current = [
+            rel(ReleaseType::Theatrical, None, date(2025, 6, 20)),
+            rel(ReleaseType::Theatrical, Some("IMAX"), date(2025, 7, 1)),
+        ];
+
+        assert!(moved_dates(previous, current, TODAY).is_empty());
+    }
+
+    #[test]
+    fn provider_order_is_the_same_for_any_row_order() {
+        let provider = |provider_id, name: &str, provider_type, display_priority| WatchProvider {
+            provider_id,
+            provider_name: name.to_string(),
+            logo_path: String::new(),
+            link: None,
+            provider_type,
+            display_priority,
+        };
+        let sorted = vec![
+            provider(8, "Netflix", ProviderType::Stream, 1),
+            provider(337, "Disney Plus", ProviderType::Stream, 4),
+            provider(2, "Apple TV", ProviderType::Rent, 4),
+            provider(3, "Google Play", ProviderType::Rent, 4),
+            provider(10, "Amazon Video", ProviderType::Buy, 2),
+            provider(11, "Amazon Video", ProviderType::Buy, 2),
+        ];
+        let ids = |providers: &[WatchProvider]| -> Vec<i32> {
+            providers.iter().map(|p| p.provider_id).collect()
+        };
+
+        for shift in 0..sorted.len() {
+            let mut shuffled = sorted.clone();
+            shuffled.rotate_left(shift);
+            if shift % 2 == 1 {
+                shuffled.reverse();
+            }
+            shuffled.sort_by(|a, b| provider_order(a).cmp(&provider_order(b)));
+
+            assert_eq!(ids(&shuffled), ids(&sorted), "rotated by {shift}");
+        }
+    }
+
+    #[tokio::test]
+    async fn match_reports_are_stored_once_per_match() {
+        let cache = CacheManager::for_tests().await;
+        cache
+            .upsert_films(vec![FilmCacheData {
+                slug: "dune-part-two".to_string(),
+                tmdb_id: Some(693134),
+                title: "Dune: Part Two".to_string(),
+                year: Some(2024),
+                poster_path: None,
+                match_confidence: MatchConfidence::Low,
+            }])
+            .await
+            .unwrap();
+
+        assert!(cache.record_match_report("dune-part-two", 693134).await.unwrap());
+        assert!(!cache.record_match_report("dune-part-two", 693134).await.unwrap());
+        // Matches the cache never made can't be reported
+        assert!(!cache.record_match_report("dune-part-two", 1).await.unwrap());
+        assert!(!cache.record_match_report("made-up-film", 693134).await.unwrap());
+
+        let stored = match_reports::Entity::find().count(cache.db()).await.unwrap();
+        assert_eq!(stored, 1);
+    }
+}
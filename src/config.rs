@@ -2,18 +2,77 @@ use std::net::SocketAddr;
 
 use anyhow::Context;
 
+use crate::{
+    countries::CountryCode,
+    models::{ReleaseColumn, ReleaseType},
+};
+
+/// Longest look-back window in months, a century
+const MAX_WINDOW_MONTHS: i64 = 1200;
+
 #[derive(Clone, Debug)]
 pub struct Config {
     pub addr: SocketAddr,
     pub tmdb_access_token: String,
+    pub tmdb_api_key: String,
     pub tmdb_base_url: String,
     pub database_url: String,
     pub cache_ttl_days: i64,
+    pub low_confidence_cache_days: i64,
+    pub missing_poster_cache_hours: i64,
     pub release_cache_hours: i64,
+    pub empty_release_cache_hours: i64,
     pub provider_cache_days: i64,
     pub tmdb_rps: u32,
     pub max_concurrent: usize,
+    pub max_concurrent_processes: usize,
+    /// How long rendered results are reused for repeat requests; 0 disables it
+    pub results_cache_secs: u64,
+    pub upcoming_providers: bool,
+    /// Days after a theatrical release to estimate streaming for theatrical-only films; 0 disables
+    /// it
+    pub streaming_estimate_days: i64,
+    /// How long to spend resolving uncached films before showing what has resolved so far
+    pub resolve_deadline_secs: u64,
     pub letterboxd_delay_ms: u64,
+    /// Letterboxd film pages fetched at once while resolving uncached films
+    pub letterboxd_max_concurrent: usize,
+    /// Most TMDB calls a single run may make; 0 for no limit
+    pub tmdb_max_calls_per_run: usize,
+    /// TMDB popularity and vote count a search result needs to be accepted; 0 accepts any
+    pub min_match_popularity: f64,
+    pub min_match_votes: u32,
+    pub max_watchlist_films: usize,
+    pub already_available_months: i64,
+    pub recent_release_months: i64,
+    pub release_types: Vec<ReleaseType>,
+    /// Types only shown for films with no dates of `release_types`, instead of "no releases"
+    pub fallback_release_types: Vec<ReleaseType>,
+    pub release_columns: Vec<ReleaseColumn>,
+    pub prewarm_profiles: Vec<(String, CountryCode)>,
+    pub prewarm_interval_hours: u64,
+    pub webhook_check_hours: u64,
+    /// Seconds between passive WAL checkpoints; 0 leaves them to SQLite
+    pub wal_checkpoint_secs: u64,
+    pub scraper_connect_timeout_secs: u64,
+    pub scraper_timeout_secs: u64,
+    /// Replaces the browser User-Agent on Letterboxd requests, e.g. to add a contact URL
+    pub scraper_user_agent: Option<String>,
+    pub tmdb_connect_timeout_secs: u64,
+    pub tmdb_timeout_secs: u64,
+    /// Serve posters and provider logos from `/img` instead of linking to TMDB
+    pub proxy_images: bool,
+    /// Show only subscription streaming by default, hiding rent and buy
+    pub flatrate_only: bool,
+    /// Memory for proxied images, in megabytes
+    pub image_cache_mb: usize,
+    pub force_country: Option<CountryCode>,
+    /// Origins allowed to call the API cross-origin; `None` allows any origin
+    pub cors_allowed_origins: Option<Vec<String>>,
+    /// Bearer token for `/admin` endpoints; they are disabled when unset
+    pub admin_token: Option<String>,
+    /// Externally visible origin used for absolute links, without a trailing slash
+    pub public_base_url: String,
 }
 
 impl Config {
@@ -26,6 +85,7 @@ impl Config {
 
         let tmdb_access_token =
             std::env::var("TMDB_ACCESS_TOKEN").unwrap_or_else(|_| "".to_string());
+        let tmdb_api_key = std::env::var("TMDB_API_KEY").unwrap_or_else(|_| "".to_string());
         let tmdb_base_url = std::env::var("TMDB_BASE_URL")
             .unwrap_or_else(|_| "https://api.themoviedb.org/3".to_string());
 
@@ -35,9 +95,26 @@ impl Config {
         let cache_ttl_days: i64 =
             std::env::var("CACHE_TTL_DAYS").ok().and_then(|s| s.parse().ok()).unwrap_or(7);
 
+        let low_confidence_cache_days: i64 = std::env::var("LOW_CONFIDENCE_CACHE_DAYS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1);
+
+        // Films cached without a poster are re-resolved sooner so the poster can self-heal
+        let missing_poster_cache_hours: i64 = std::env::var("MISSING_POSTER_CACHE_HOURS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(24);
+
         let release_cache_hours: i64 =
             std::env::var("RELEASE_CACHE_HOURS").ok().and_then(|s| s.parse().ok()).unwrap_or(24);
 
+        // Films cached with no release dates at all are revalidated sooner
+        let empty_release_cache_hours: i64 = std::env::var("EMPTY_RELEASE_CACHE_HOURS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(6);
+
         let provider_cache_days: i64 =
             std::env::var("PROVIDER_CACHE_DAYS").ok().and_then(|s| s.parse().ok()).unwrap_or(7);
 
@@ -47,20 +124,236 @@ impl Config {
         let max_concurrent: usize =
             std::env::var("MAX_CONCURRENT_REQUESTS").ok().and_then(|s| s.parse().ok()).unwrap_or(5);
 
+        // Rejects obscure films that share a title with the one being searched for
+        let min_match_popularity: f64 =
+            std::env::var("MIN_MATCH_POPULARITY").ok().and_then(|s| s.parse().ok()).unwrap_or(0.0);
+
+        let min_match_votes: u32 =
+            std::env::var("MIN_MATCH_VOTES").ok().and_then(|s| s.parse().ok()).unwrap_or(0);
+
+        // Keeps one huge watchlist from using up a shared API key; the rest comes from the cache
+        let tmdb_max_calls_per_run: usize =
+            std::env::var("TMDB_MAX_CALLS_PER_RUN").ok().and_then(|s| s.parse().ok()).unwrap_or(0);
+
+        // Watchlists processed at once; further /process requests wait in a queue
+        let max_concurrent_processes: usize = std::env::var("MAX_CONCURRENT_PROCESSES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(2);
+
+        // Look up providers for films whose streaming release hasn't happened yet, to show
+        // where they're coming to. Off by default as it costs a TMDB call per upcoming film.
+        let upcoming_providers: bool =
+            std::env::var("UPCOMING_PROVIDERS").ok().and_then(|s| s.parse().ok()).unwrap_or(false);
+
+        let streaming_estimate_days: i64 = std::env::var("STREAMING_ESTIMATE_DAYS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(90)
+            .max(0);
+
+        let results_cache_secs: u64 =
+            std::env::var("RESULTS_CACHE_SECS").ok().and_then(|s| s.parse().ok()).unwrap_or(60);
+
+        // 0 waits for every film to resolve
+        let resolve_deadline_secs: u64 =
+            std::env::var("RESOLVE_DEADLINE_SECS").ok().and_then(|s| s.parse().ok()).unwrap_or(60);
+
         let letterboxd_delay_ms: u64 =
             std::env::var("LETTERBOXD_DELAY_MS").ok().and_then(|s| s.parse().ok()).unwrap_or(100);
 
+        let letterboxd_max_concurrent: usize = std::env::var("LETTERBOXD_MAX_CONCURRENT")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(3);
+
+        let max_watchlist_films: usize =
+            std::env::var("MAX_WATCHLIST_FILMS").ok().and_then(|s| s.parse().ok()).unwrap_or(500);
+
+        // Capped so the window can always be subtracted from today
+        let already_available_months: i64 = std::env::var("ALREADY_AVAILABLE_MONTHS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(24)
+            .clamp(0, MAX_WINDOW_MONTHS);
+
+        // Release types that count towards a film having release dates, e.g.
+        // `premiere,limited,theatrical,digital`. Physical dates are only ever a fallback
+        let mut release_types: Vec<ReleaseType> = std::env::var("RELEASE_TYPES")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(ReleaseType::from_name)
+            .filter(|kind| *kind != ReleaseType::Physical)
+            .collect();
+        if release_types.is_empty() {
+            release_types = vec![ReleaseType::Theatrical, ReleaseType::Digital];
+        }
+
+        // Types that stand in for films with none of the above, e.g. `premiere,physical`. Set
+        // it empty to leave those films without dates
+        let fallback_release_types: Vec<ReleaseType> = std::env::var("FALLBACK_RELEASE_TYPES")
+            .unwrap_or_else(|_| "premiere,physical".to_string())
+            .split(',')
+            .filter_map(ReleaseType::from_name)
+            .filter(|kind| !release_types.contains(kind))
+            .collect();
+
+        // Semicolon-separated columns, e.g. `Cinema=limited,theatrical;Home=digital`
+        let mut release_columns: Vec<ReleaseColumn> = std::env::var("RELEASE_COLUMNS")
+            .unwrap_or_default()
+            .split(';')
+            .filter_map(ReleaseColumn::parse)
+            .collect();
+        if release_columns.is_empty() {
+            release_columns = ReleaseColumn::defaults();
+        }
+
+        let recent_release_months: i64 = std::env::var("RECENT_RELEASE_MONTHS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(12)
+            .clamp(0, MAX_WINDOW_MONTHS);
+
+        // Comma-separated `username:country` pairs, e.g. `alice:NZ,bob:US`
+        let prewarm_profiles = std::env::var("PREWARM_PROFILES")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(parse_profile)
+            .collect::<Vec<_>>();
+
+        let prewarm_interval_hours: u64 =
+            std::env::var("PREWARM_INTERVAL_HOURS").ok().and_then(|s| s.parse().ok()).unwrap_or(12);
+
+        // 0 disables webhook subscriptions entirely
+        let webhook_check_hours: u64 =
+            std::env::var("WEBHOOK_CHECK_HOURS").ok().and_then(|s| s.parse().ok()).unwrap_or(0);
+
+        let wal_checkpoint_secs: u64 =
+            std::env::var("WAL_CHECKPOINT_SECS").ok().and_then(|s| s.parse().ok()).unwrap_or(300);
+
+        let scraper_connect_timeout_secs: u64 = std::env::var("SCRAPER_CONNECT_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(5);
+
+        let scraper_timeout_secs: u64 =
+            std::env::var("SCRAPER_TIMEOUT_SECS").ok().and_then(|s| s.parse().ok()).unwrap_or(30);
+
+        let scraper_user_agent = std::env::var("SCRAPER_USER_AGENT")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+
+        let tmdb_connect_timeout_secs: u64 = std::env::var("TMDB_CONNECT_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(3);
+
+        let tmdb_timeout_secs: u64 =
+            std::env::var("TMDB_TIMEOUT_SECS").ok().and_then(|s| s.parse().ok()).unwrap_or(10);
+
+        let proxy_images: bool =
+            std::env::var("PROXY_IMAGES").ok().and_then(|s| s.parse().ok()).unwrap_or(false);
+
+        let flatrate_only: bool =
+            std::env::var("FLATRATE_ONLY").ok().and_then(|s| s.parse().ok()).unwrap_or(false);
+
+        let image_cache_mb: usize =
+            std::env::var("IMAGE_CACHE_MB").ok().and_then(|s| s.parse().ok()).unwrap_or(32);
+
+        // Locks the instance to a single country, e.g. `GB`
+        let force_country = match std::env::var("FORCE_COUNTRY") {
+            Ok(code) if !code.trim().is_empty() => {
+                Some(CountryCode::parse(&code).with_context(|| {
+                    format!("FORCE_COUNTRY must be a supported country code, got {}", code.trim())
+                })?)
+            },
+            _ => None,
+        };
+
+        // Comma-separated origins, e.g. `https://example.com,https://blog.example.com`, or `*`
+        let cors_allowed_origins = std::env::var("CORS_ALLOWED_ORIGINS")
+            .ok()
+            .map(|s| s.split(',').map(|o| o.trim().to_string()).filter(|o| !o.is_empty()).collect())
+            .filter(|origins: &Vec<String>| {
+                !origins.is_empty() && !origins.iter().any(|o| o == "*")
+            });
+
+        let admin_token = std::env::var("ADMIN_TOKEN")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+
+        let addr: SocketAddr = format!("{host}:{port}").parse().context("HOST/PORT")?;
+
+        // e.g. `https://timeboxd.example.com` when running behind a reverse proxy
+        let public_base_url = match std::env::var("PUBLIC_BASE_URL") {
+            Ok(url) if !url.trim().is_empty() => {
+                let url = url.trim().trim_end_matches('/').to_string();
+                if !(url.starts_with("https://") || url.starts_with("http://")) {
+                    anyhow::bail!("PUBLIC_BASE_URL must start with http:// or https://, got {url}");
+                }
+                url
+            },
+            _ => format!("http://{addr}"),
+        };
+
         Ok(Self {
-            addr: format!("{host}:{port}").parse().context("HOST/PORT")?,
+            addr,
             tmdb_access_token,
+            tmdb_api_key,
             tmdb_base_url,
             database_url,
             cache_ttl_days,
+            low_confidence_cache_days,
+            missing_poster_cache_hours,
             release_cache_hours,
+            empty_release_cache_hours,
             provider_cache_days,
             tmdb_rps,
             max_concurrent,
+            max_concurrent_processes,
+            results_cache_secs,
+            upcoming_providers,
+            streaming_estimate_days,
+            resolve_deadline_secs,
             letterboxd_delay_ms,
+            letterboxd_max_concurrent,
+            tmdb_max_calls_per_run,
+            min_match_popularity,
+            min_match_votes,
+            max_watchlist_films,
+            already_available_months,
+            recent_release_months,
+            release_types,
+            fallback_release_types,
+            release_columns,
+            prewarm_profiles,
+            prewarm_interval_hours,
+            webhook_check_hours,
+            wal_checkpoint_secs,
+            scraper_connect_timeout_secs,
+            scraper_timeout_secs,
+            scraper_user_agent,
+            tmdb_connect_timeout_secs,
+            tmdb_timeout_secs,
+            proxy_images,
+            flatrate_only,
+            image_cache_mb,
+            force_country,
+            cors_allowed_origins,
+            admin_token,
+            public_base_url,
         })
     }
 }
+
+/// Parses a `username:country` pair, as used by `PREWARM_PROFILES`.
+pub fn parse_profile(entry: &str) -> Option<(String, CountryCode)> {
+    let (username, country) = entry.trim().split_once(':')?;
+    let username = username.trim();
+    if username.is_empty() {
+        return None;
+    }
+    Some((username.to_string(), CountryCode::parse(country).ok()?))
+}
@@ -1,3 +1,8 @@
+use std::{fmt, ops::Deref};
+
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
 pub struct Country {
     pub code: &'static str,
     pub name: &'static str,
@@ -61,6 +66,78 @@ pub const COUNTRIES: &[Country] = &[
     Country { code: "GB", name: "United Kingdom" },
 ];
 
+/// A country code from `COUNTRIES`. Requests, the processor and TMDB lookups take this rather
+/// than a bare string, so an unsupported code is rejected up front instead of quietly finding
+/// no release dates.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct CountryCode(&'static str);
+
+impl CountryCode {
+    pub const AU: CountryCode = CountryCode("AU");
+    pub const NZ: CountryCode = CountryCode("NZ");
+    pub const US: CountryCode = CountryCode("US");
+
+    /// Case-insensitive, ignoring surrounding whitespace.
+    pub fn parse(code: &str) -> anyhow::Result<Self> {
+        let code = code.trim();
+        if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+            anyhow::bail!("country must be a 2-letter code");
+        }
+        COUNTRIES
+            .iter()
+            .find(|c| c.code.eq_ignore_ascii_case(code))
+            .map(|c| CountryCode(c.code))
+            .ok_or_else(|| anyhow::anyhow!("country must be a supported code, got {code}"))
+    }
+
+    pub fn as_str(self) -> &'static str {
+        self.0
+    }
+}
+
+impl fmt::Display for CountryCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl Deref for CountryCode {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.0
+    }
+}
+
+impl AsRef<str> for CountryCode {
+    fn as_ref(&self) -> &str {
+        self.0
+    }
+}
+
+impl PartialEq<str> for CountryCode {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<String> for CountryCode {
+    fn eq(&self, other: &String) -> bool {
+        self.0 == other
+    }
+}
+
 pub fn get_country_name(code: &str) -> &str {
     COUNTRIES.iter().find(|c| c.code.eq_ignore_ascii_case(code)).map(|c| c.name).unwrap_or(code)
 }
+
+/// Flag emoji built from regional indicator symbols, or an empty string for non-ISO codes.
+pub fn get_country_flag_emoji(code: &str) -> String {
+    if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return String::new();
+    }
+    code.to_ascii_uppercase()
+        .chars()
+        .filter_map(|c| char::from_u32(0x1f1e6 + (c as u32 - 'A' as u32)))
+        .collect()
+}
@@ -1,5 +1,8 @@
-use sea_orm::{ConnectionTrait, Database, DatabaseConnection, Statement};
+use std::time::Duration;
+
+use sea_orm::{ConnectionTrait, Database, DatabaseBackend, DatabaseConnection, Statement};
 use sea_orm_migration::MigratorTrait;
+use tracing::{debug, info, warn};
 
 use crate::error::AppResult;
 
@@ -28,3 +31,63 @@ pub async fn connect_and_migrate(database_url: &str) -> AppResult<DatabaseConnec
 
     Ok(db)
 }
+
+/// Rebuilds the database file and truncates the WAL, returning the bytes reclaimed.
+pub async fn vacuum(db: &DatabaseConnection) -> AppResult<i64> {
+    let before = database_size(db).await?;
+
+    db.execute_unprepared("VACUUM").await?;
+    db.execute_unprepared("PRAGMA wal_checkpoint(TRUNCATE)").await?;
+
+    let after = database_size(db).await?;
+    Ok(before.saturating_sub(after))
+}
+
+async fn database_size(db: &DatabaseConnection) -> AppResult<i64> {
+    let pragma =
+        |name: &str| Statement::from_string(db.get_database_backend(), format!("PRAGMA {name}"));
+    let mut size = 1;
+    for name in ["page_count", "page_size"] {
+        let row = db
+            .query_one(pragma(name))
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("PRAGMA {name} returned no rows"))?;
+        size *= row.try_get_by_index::<i64>(0)?;
+    }
+    Ok(size)
+}
+
+/// Copies WAL pages back into the database without blocking readers or writers, so the `-wal`
+/// file stays small between SQLite's own checkpoints. Does nothing on other backends.
+pub async fn checkpoint(db: &DatabaseConnection) -> AppResult<()> {
+    if db.get_database_backend() != DatabaseBackend::Sqlite {
+        return Ok(());
+    }
+
+    db.execute_unprepared("PRAGMA wal_checkpoint(PASSIVE)").await?;
+    Ok(())
+}
+
+/// Checkpoints the WAL every `interval_secs` seconds; 0 disables it.
+pub fn spawn_checkpoints(db: DatabaseConnection, interval_secs: u64) {
+    if interval_secs == 0 || db.get_database_backend() != DatabaseBackend::Sqlite {
+        return;
+    }
+
+    info!(interval_secs, "WAL checkpoints enabled");
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // The first tick completes immediately
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            match checkpoint(&db).await {
+                Ok(()) => debug!("checkpointed WAL"),
+                Err(e) => warn!(error = %e, "WAL checkpoint failed"),
+            }
+        }
+    });
+}
@@ -10,6 +10,9 @@ pub struct Model {
     pub year: Option<i32>,
     pub poster_path: Option<String>,
     pub updated_at: i64,
+    pub match_confidence: i32,
+    /// When the film was first cached from any watchlist; kept across re-resolves
+    pub first_seen_at: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
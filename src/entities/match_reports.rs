@@ -0,0 +1,18 @@
+use sea_orm::entity::prelude::*;
+
+/// A user report that a slug was matched to the wrong TMDB film.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "match_reports")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub slug: String,
+    /// The TMDB film the slug was matched to when reported
+    pub tmdb_id: i32,
+    pub created_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
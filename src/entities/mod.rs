@@ -1,5 +1,11 @@
 pub mod film_cache;
+pub mod match_overrides;
+pub mod match_reports;
 pub mod provider_cache;
 pub mod provider_cache_meta;
 pub mod release_cache;
 pub mod release_cache_meta;
+pub mod release_date_history;
+pub mod released_regions;
+pub mod slug_aliases;
+pub mod subscriptions;
@@ -13,6 +13,7 @@ pub struct Model {
     pub link: Option<String>,
     pub provider_type: i32,
     pub cached_at: i64,
+    pub display_priority: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -10,7 +10,11 @@ pub struct Model {
     pub release_date: String,
     pub release_type: i32,
     pub note: Option<String>,
+    /// TMDB's note as written, when it differs from `note`
+    pub raw_note: Option<String>,
     pub cached_at: i64,
+    /// A date of `FALLBACK_RELEASE_TYPES`, kept apart from the theatrical and streaming dates
+    pub fallback: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
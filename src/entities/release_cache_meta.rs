@@ -8,6 +8,8 @@ pub struct Model {
     pub tmdb_id: i32,
     pub country: String,
     pub cached_at: i64,
+    /// TMDB answered with no release dates for any country.
+    pub confirmed_empty: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
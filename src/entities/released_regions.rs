@@ -0,0 +1,16 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "released_regions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub tmdb_id: i32,
+    /// Comma-separated country codes with a past release
+    pub countries: String,
+    pub cached_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,22 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "subscriptions")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub username: String,
+    pub country: String,
+    pub webhook_url: String,
+    pub snapshot: Option<String>,
+    pub created_at: i64,
+    pub checked_at: Option<i64>,
+    /// Secret sent in the confirmation ping, proving the subscriber receives the webhook
+    pub token: String,
+    pub confirmed: bool,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,4 +1,11 @@
-use axum::response::{Html, IntoResponse, Response};
+use axum::{
+    Json,
+    extract::Request,
+    http::{HeaderMap, StatusCode, header::ACCEPT},
+    middleware::Next,
+    response::{Html, IntoResponse, Response},
+};
+use serde_json::json;
 
 #[derive(Debug)]
 pub struct AppError(anyhow::Error);
@@ -11,6 +18,24 @@ impl std::fmt::Display for AppError {
 
 impl std::error::Error for AppError {}
 
+impl AppError {
+    pub fn is_invalid_tmdb_credentials(&self) -> bool {
+        self.0.is::<InvalidTmdbCredentials>()
+    }
+}
+
+/// TMDB rejected the configured access token or API key (HTTP 401).
+#[derive(Debug)]
+pub struct InvalidTmdbCredentials;
+
+impl std::fmt::Display for InvalidTmdbCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TMDB credentials are invalid — check TMDB_ACCESS_TOKEN or TMDB_API_KEY")
+    }
+}
+
+impl std::error::Error for InvalidTmdbCredentials {}
+
 impl From<anyhow::Error> for AppError {
     fn from(err: anyhow::Error) -> Self {
         Self(err)
@@ -35,12 +60,100 @@ impl From<jiff::Error> for AppError {
     }
 }
 
+/// Attached to error responses so `negotiate` can re-render them as JSON for API clients.
+#[derive(Clone, Debug)]
+struct ErrorBody {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let user_friendly_error = error_to_user_message(&self.0);
-        let body = crate::templates::error_page(user_friendly_error);
-        Html(body).into_response()
+        let (status, code) = error_status(&self.0);
+        let body = crate::templates::error_page(user_friendly_error.clone());
+        let mut resp = Html(body).into_response();
+        resp.extensions_mut().insert(ErrorBody { status, code, message: user_friendly_error });
+        resp
+    }
+}
+
+/// Middleware that turns `AppError` responses into `{ "error", "code" }` JSON for `/api`
+/// paths and for clients that prefer JSON over HTML.
+pub async fn negotiate(req: Request, next: Next) -> Response {
+    let wants_json = req.uri().path().starts_with("/api/") || prefers_json(req.headers());
+    let mut resp = next.run(req).await;
+
+    if wants_json && let Some(err) = resp.extensions_mut().remove::<ErrorBody>() {
+        return (err.status, Json(json!({ "error": err.message, "code": err.code })))
+            .into_response();
+    }
+
+    resp
+}
+
+fn prefers_json(headers: &HeaderMap) -> bool {
+    let Some(accept) = headers.get(ACCEPT).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    match (accept.find("application/json"), accept.find("text/html")) {
+        (Some(json), Some(html)) => json < html,
+        (Some(_), None) => true,
+        _ => false,
+    }
+}
+
+/// HTTP status and machine-readable code for an error, for API clients.
+fn error_status(err: &anyhow::Error) -> (StatusCode, &'static str) {
+    let err_string = err.to_string();
+
+    if [
+        "username is required",
+        "slug is required",
+        "country must be",
+        "webhook URL must start",
+        "webhook URL must point",
+        "unsupported Letterboxd link",
+    ]
+    .iter()
+    .any(|pattern| err_string.contains(pattern))
+    {
+        return (StatusCode::BAD_REQUEST, "invalid_request");
+    }
+
+    if err_string.contains("subscriptions are disabled") {
+        return (StatusCode::NOT_FOUND, "disabled");
+    }
+
+    if err_string.contains("subscription not found") {
+        return (StatusCode::NOT_FOUND, "not_found");
     }
+
+    if err_string.contains("webhook didn't accept") {
+        return (StatusCode::BAD_GATEWAY, "webhook_error");
+    }
+
+    if err.is::<InvalidTmdbCredentials>()
+        || err.downcast_ref::<AppError>().is_some_and(AppError::is_invalid_tmdb_credentials)
+    {
+        return (StatusCode::BAD_GATEWAY, "tmdb_credentials");
+    }
+
+    if err_string.contains("404") || err_string.contains("Not Found") {
+        return (StatusCode::NOT_FOUND, "not_found");
+    }
+
+    if err_string.contains("TMDB API")
+        || err_string.contains("themoviedb")
+        || err_string.contains("letterboxd.com")
+        || err_string.contains("network")
+        || err_string.contains("timeout")
+    {
+        return (StatusCode::BAD_GATEWAY, "upstream_error");
+    }
+
+    (StatusCode::INTERNAL_SERVER_ERROR, "internal_error")
 }
 
 pub fn error_to_user_message(err: &anyhow::Error) -> String {
@@ -51,16 +164,52 @@ pub fn error_to_user_message(err: &anyhow::Error) -> String {
         return "Please enter a Letterboxd username.".to_string();
     }
 
-    if err_string.contains("country must be a 2-letter code") {
+    if err_string.contains("unsupported Letterboxd link") {
+        return "Please paste a Letterboxd profile, watchlist or list link.".to_string();
+    }
+
+    if err_string.contains("on this instance") {
+        return "This site only shows release dates for one country.".to_string();
+    }
+
+    if err_string.contains("country must be") {
         return "Please select a valid country.".to_string();
     }
 
+    if err_string.contains("slug is required") {
+        return "Please provide a Letterboxd film slug.".to_string();
+    }
+
+    if err_string.contains("webhook URL must start with") {
+        return "Please enter a webhook URL starting with http:// or https://.".to_string();
+    }
+
+    if err_string.contains("webhook URL must point") {
+        return "Please enter a webhook URL on a public host.".to_string();
+    }
+
+    if err_string.contains("webhook didn't accept") {
+        return "The webhook didn't accept the confirmation. Check the URL and try again."
+            .to_string();
+    }
+
+    if err_string.contains("subscriptions are disabled") {
+        return "Notifications aren't enabled on this server.".to_string();
+    }
+
+    if err_string.contains("subscription not found") {
+        return "This confirmation link is invalid or the subscription was removed.".to_string();
+    }
+
     if err_string.contains("404") || err_string.contains("Not Found") {
         // This could be a user not found or a film page not found
         if err_string.contains("letterboxd.com") {
             if err_string.contains("/watchlist/") {
                 return "Letterboxd user not found. Please check the username and try again."
                     .to_string();
+            } else if err_string.contains("/list/") {
+                return "Letterboxd list not found. Please check the link and try again."
+                    .to_string();
             } else if err_string.contains("/film/") {
                 return "Unable to find film information. This film may no longer exist on \
                         Letterboxd."
@@ -69,6 +218,12 @@ pub fn error_to_user_message(err: &anyhow::Error) -> String {
         }
     }
 
+    if err.is::<InvalidTmdbCredentials>()
+        || err.downcast_ref::<AppError>().is_some_and(AppError::is_invalid_tmdb_credentials)
+    {
+        return InvalidTmdbCredentials.to_string();
+    }
+
     if err_string.contains("TMDB API") || err_string.contains("themoviedb") {
         return "Unable to fetch movie data from TMDB. Please try again later.".to_string();
     }
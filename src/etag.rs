@@ -0,0 +1,144 @@
+use axum::{
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use jiff::{Timestamp, fmt::rfc2822::DateTimePrinter};
+
+use crate::countries::CountryCode;
+
+/// Validators for a response built from cached release data.
+#[derive(Clone, Debug)]
+pub struct CacheValidators {
+    pub etag: String,
+    pub last_modified: Option<String>,
+}
+
+impl CacheValidators {
+    /// Derives validators from the `(tmdb_id, country, cached_at)` release and provider cache
+    /// entries a response was built from. `scope` covers anything else that affects the output
+    /// (query parameters, today's date), so different views of the same films don't share an
+    /// ETag.
+    pub fn from_cache_entries(
+        scope: &str,
+        releases: &[(i32, CountryCode, i64)],
+        providers: &[(i32, CountryCode, i64)],
+    ) -> Self {
+        let mut hash = Fnv1a::default();
+        hash.write(scope.as_bytes());
+        for (marker, entries) in [(1, releases), (2, providers)] {
+            let mut entries = entries.to_vec();
+            entries.sort();
+            for (tmdb_id, country, cached_at) in &entries {
+                hash.write(&[marker]);
+                hash.write(&tmdb_id.to_le_bytes());
+                hash.write(country.as_bytes());
+                hash.write(&cached_at.to_le_bytes());
+            }
+        }
+
+        let last_modified = releases
+            .iter()
+            .chain(providers)
+            .map(|(_, _, cached_at)| *cached_at)
+            .max()
+            .and_then(|secs| Timestamp::from_second(secs).ok())
+            .and_then(|ts| DateTimePrinter::new().timestamp_to_rfc9110_string(&ts).ok());
+
+        Self { etag: hash.etag(), last_modified }
+    }
+
+    /// Validators for a response with no cache times behind it, from the body itself.
+    pub fn from_body(body: &[u8]) -> Self {
+        let mut hash = Fnv1a::default();
+        hash.write(body);
+        Self { etag: hash.etag(), last_modified: None }
+    }
+
+    pub fn matches(&self, request_headers: &HeaderMap) -> bool {
+        request_headers
+            .get_all(header::IF_NONE_MATCH)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == "*" || tag == self.etag)
+    }
+
+    /// A `304 Not Modified` when the request already has this version, carrying the validators.
+    pub fn not_modified(&self, request_headers: &HeaderMap) -> Option<Response> {
+        if !self.matches(request_headers) {
+            return None;
+        }
+        let mut resp = StatusCode::NOT_MODIFIED.into_response();
+        self.apply(resp.headers_mut());
+        Some(resp)
+    }
+
+    pub fn apply(&self, response_headers: &mut HeaderMap) {
+        if let Ok(value) = HeaderValue::from_str(&self.etag) {
+            response_headers.insert(header::ETAG, value);
+        }
+        if let Some(value) =
+            self.last_modified.as_deref().and_then(|v| HeaderValue::from_str(v).ok())
+        {
+            response_headers.insert(header::LAST_MODIFIED, value);
+        }
+    }
+}
+
+/// FNV-1a, which keeps ETags stable across builds and restarts.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn etag(&self) -> String {
+        format!("\"{:016x}\"", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provider_refresh_changes_the_etag() {
+        let releases = [(550, CountryCode::NZ, 1_700_000_000)];
+        let before = CacheValidators::from_cache_entries(
+            "scope",
+            &releases,
+            &[(550, CountryCode::NZ, 1_700_000_000)],
+        );
+        let after = CacheValidators::from_cache_entries(
+            "scope",
+            &releases,
+            &[(550, CountryCode::NZ, 1_700_003_600)],
+        );
+
+        assert_ne!(before.etag, after.etag);
+        assert_ne!(before.last_modified, after.last_modified);
+    }
+
+    #[test]
+    fn matching_request_is_not_modified() {
+        let validators = CacheValidators::from_body(b"{}");
+        let mut headers = HeaderMap::new();
+        assert!(validators.not_modified(&headers).is_none());
+
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(&validators.etag).unwrap());
+        let resp = validators.not_modified(&headers).unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(resp.headers()[header::ETAG], validators.etag.as_str());
+    }
+}
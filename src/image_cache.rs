@@ -0,0 +1,133 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use axum::body::Bytes;
+
+pub const TMDB_IMAGE_BASE_URL: &str = "https://image.tmdb.org/t/p";
+
+/// Larger images are neither served nor kept. Posters at w200 are a few tens of KB.
+pub const MAX_IMAGE_BYTES: usize = 1024 * 1024;
+
+/// The images the templates show, each proxied at a single TMDB size.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ImageKind {
+    Poster,
+    Logo,
+}
+
+impl ImageKind {
+    pub fn from_path(value: &str) -> Option<Self> {
+        match value {
+            "poster" => Some(ImageKind::Poster),
+            "logo" => Some(ImageKind::Logo),
+            _ => None,
+        }
+    }
+
+    fn as_path(self) -> &'static str {
+        match self {
+            ImageKind::Poster => "poster",
+            ImageKind::Logo => "logo",
+        }
+    }
+
+    pub fn tmdb_size(self) -> &'static str {
+        match self {
+            ImageKind::Poster => "w200",
+            ImageKind::Logo => "w92",
+        }
+    }
+}
+
+/// URL for a TMDB image path such as `/abc.jpg`, served through `/img` when `proxy` is set.
+pub fn image_url(kind: ImageKind, path: &str, proxy: bool) -> String {
+    if proxy {
+        format!("/img/{}{path}", kind.as_path())
+    } else {
+        format!("{TMDB_IMAGE_BASE_URL}/{}{path}", kind.tmdb_size())
+    }
+}
+
+/// TMDB image file names are a hash and an extension, e.g. `kqjL17yufvn9OVLyXYpvtyrFfak.jpg`.
+pub fn is_valid_file_name(file: &str) -> bool {
+    file.len() <= 64
+        && file.split_once('.').is_some_and(|(name, ext)| {
+            !name.is_empty()
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                && matches!(ext, "jpg" | "png" | "webp")
+        })
+}
+
+#[derive(Clone)]
+pub struct CachedImage {
+    pub content_type: String,
+    pub bytes: Bytes,
+}
+
+/// Proxied images kept in memory up to `max_bytes` in total, dropping the oldest first. TMDB
+/// never changes the image behind a path, so entries don't expire.
+pub struct ImageCache {
+    max_bytes: usize,
+    inner: Mutex<Entries>,
+}
+
+#[derive(Default)]
+struct Entries {
+    images: HashMap<String, CachedImage>,
+    order: VecDeque<String>,
+    total_bytes: usize,
+}
+
+impl ImageCache {
+    pub fn new(max_bytes: usize) -> Self {
+        Self { max_bytes, inner: Mutex::new(Entries::default()) }
+    }
+
+    pub fn key(kind: ImageKind, file: &str) -> String {
+        format!("{}/{file}", kind.tmdb_size())
+    }
+
+    pub fn get(&self, key: &str) -> Option<CachedImage> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).images.get(key).cloned()
+    }
+
+    pub fn put(&self, key: String, image: CachedImage) {
+        if image.bytes.len() > self.max_bytes {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.images.contains_key(&key) {
+            return;
+        }
+
+        inner.total_bytes += image.bytes.len();
+        inner.order.push_back(key.clone());
+        inner.images.insert(key, image);
+
+        while inner.total_bytes > self.max_bytes {
+            let Some(oldest) = inner.order.pop_front() else {
+                break;
+            };
+            if let Some(evicted) = inner.images.remove(&oldest) {
+                inner.total_bytes -= evicted.bytes.len();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_raster_tmdb_file_names_are_proxied() {
+        assert!(is_valid_file_name("kqjL17yufvn9OVLyXYpvtyrFfak.jpg"));
+        assert!(is_valid_file_name("pbpoLLp4kvnYVfnEGiEhagpJuVZ.png"));
+        assert!(!is_valid_file_name("wwemzKWzjKYJFfCeiB57q3r4Bcm.svg"));
+        assert!(!is_valid_file_name("../secret.jpg"));
+        assert!(!is_valid_file_name(".jpg"));
+    }
+}
@@ -0,0 +1,196 @@
+pub mod cache;
+pub mod config;
+pub mod countries;
+pub mod db;
+pub mod entities;
+pub mod error;
+pub mod etag;
+pub mod image_cache;
+pub mod models;
+pub mod prewarm;
+pub mod processor;
+pub mod queue;
+pub mod request_id;
+pub mod results_cache;
+pub mod routes;
+pub mod scraper;
+pub mod sources;
+pub mod subscriptions;
+pub mod templates;
+pub mod tmdb;
+
+use std::{sync::Arc, time::Duration};
+
+use wreq_util::Emulation;
+
+use crate::{
+    cache::CacheManager, config::Config, image_cache::ImageCache, queue::ProcessQueue,
+    results_cache::ResultsCache, tmdb::TmdbClient,
+};
+
+/// Webhook receivers should answer quickly; a slow one shouldn't hold up the check loop
+const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+#[derive(Clone)]
+pub struct AppState {
+    pub config: Arc<Config>,
+    pub http: wreq::Client,
+    /// Plain client for subscriber webhooks, without the browser emulation used for scraping
+    pub webhook_http: wreq::Client,
+    pub cache: CacheManager,
+    pub tmdb: Arc<TmdbClient>,
+    pub process_queue: Arc<ProcessQueue>,
+    pub results_cache: Arc<ResultsCache>,
+    pub image_cache: Arc<ImageCache>,
+    /// TMDB isn't configured, so every release date shown is made up
+    pub mock_mode: bool,
+}
+
+impl AppState {
+    /// Builds the HTTP clients, cache and TMDB client, migrating the database first.
+    pub async fn from_config(config: Arc<Config>) -> anyhow::Result<Self> {
+        // Letterboxd pages can be slow to render, TMDB responses should be quick
+        let http = scraper_client(
+            Duration::from_secs(config.scraper_connect_timeout_secs),
+            Duration::from_secs(config.scraper_timeout_secs),
+            config.scraper_user_agent.as_deref(),
+        )?;
+
+        let tmdb_http = wreq::Client::builder()
+            .connect_timeout(Duration::from_secs(config.tmdb_connect_timeout_secs))
+            .timeout(Duration::from_secs(config.tmdb_timeout_secs))
+            .build()?;
+
+        // Redirects aren't followed, so a webhook can't bounce requests to an internal address
+        let webhook_http = wreq::Client::builder()
+            .connect_timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+            .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+            .build()?;
+
+        let db = db::connect_and_migrate(&config.database_url).await?;
+        let cache = CacheManager::new(
+            db,
+            config.cache_ttl_days,
+            config.low_confidence_cache_days,
+            config.missing_poster_cache_hours,
+            config.release_cache_hours,
+            config.empty_release_cache_hours,
+            config.provider_cache_days,
+        );
+
+        let tmdb = TmdbClient::new(
+            tmdb_http,
+            config.tmdb_access_token.clone(),
+            config.tmdb_api_key.clone(),
+            config.tmdb_base_url.clone(),
+            config.tmdb_rps,
+            config.already_available_months,
+            config.release_types.clone(),
+        )
+        .with_min_match(config.min_match_popularity, config.min_match_votes)
+        .with_fallback_release_types(config.fallback_release_types.clone());
+        let mock_mode = tmdb.is_mock();
+
+        let process_queue = Arc::new(ProcessQueue::new(config.max_concurrent_processes));
+
+        // Never keep a rendered page longer than the release data behind it stays fresh
+        let results_ttl = Duration::from_secs(config.results_cache_secs)
+            .min(Duration::from_secs(config.release_cache_hours.max(0) as u64 * 3_600));
+        let results_cache = Arc::new(ResultsCache::new(results_ttl));
+
+        let image_cache = Arc::new(ImageCache::new(config.image_cache_mb * 1024 * 1024));
+
+        Ok(Self {
+            config,
+            http,
+            webhook_http,
+            cache,
+            tmdb: Arc::new(tmdb),
+            process_queue,
+            results_cache,
+            image_cache,
+            mock_mode,
+        })
+    }
+
+    /// Absolute URL for a path on this server, e.g. `/release-dates?...`.
+    pub fn public_url(&self, path: &str) -> String {
+        format!("{}{}", self.config.public_base_url, path)
+    }
+}
+
+/// Browser-like client for Letterboxd, sending `user_agent` instead of the browser's when set.
+fn scraper_client(
+    connect_timeout: Duration,
+    timeout: Duration,
+    user_agent: Option<&str>,
+) -> wreq::Result<wreq::Client> {
+    let mut http = wreq::Client::builder()
+        .emulation(Emulation::Chrome131)
+        .connect_timeout(connect_timeout)
+        .timeout(timeout)
+        // Renamed films redirect to their new slug, which is how aliases are found
+        .redirect(wreq::redirect::Policy::limited(5));
+    // Set after the emulation so it replaces the browser User-Agent
+    if let Some(user_agent) = user_agent {
+        http = http.user_agent(user_agent);
+    }
+    http.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// Serves one request and returns its head, lowercased.
+    async fn capture_request(client: &wreq::Client) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/film/test/", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut head = Vec::new();
+            let mut buf = [0; 1024];
+            while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                let read = socket.read(&mut buf).await.unwrap();
+                if read == 0 {
+                    break;
+                }
+                head.extend_from_slice(&buf[..read]);
+            }
+            socket
+                .write_all(b"HTTP/1.1 204 No Content\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&head).to_lowercase()
+        });
+
+        client.get(url).send().await.unwrap();
+        server.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn scraper_sends_the_configured_user_agent() {
+        let timeout = Duration::from_secs(5);
+        let client = scraper_client(timeout, timeout, Some("timeboxd-test/1.0")).unwrap();
+
+        let head = capture_request(&client).await;
+
+        assert_eq!(head.matches("\r\nuser-agent:").count(), 1);
+        assert!(head.contains("\r\nuser-agent: timeboxd-test/1.0\r\n"));
+    }
+
+    #[tokio::test]
+    async fn scraper_keeps_the_browser_user_agent_by_default() {
+        let timeout = Duration::from_secs(5);
+        let client = scraper_client(timeout, timeout, None).unwrap();
+
+        let head = capture_request(&client).await;
+
+        assert!(head.contains("\r\nuser-agent: mozilla/5.0"));
+    }
+}
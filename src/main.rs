@@ -1,35 +1,17 @@
-mod cache;
-mod config;
-mod countries;
-mod db;
-mod entities;
-mod error;
-mod models;
-mod processor;
-mod routes;
-mod scraper;
-mod templates;
-mod tmdb;
+use std::sync::Arc;
 
-use std::{sync::Arc, time::Duration};
-
-use axum::{Router, routing::get};
+use anyhow::Context;
+use axum::{
+    Router, middleware,
+    routing::{get, post},
+};
+use timeboxd::{AppState, config::Config, db, error, prewarm, request_id, routes, subscriptions};
 use tower_http::{
-    cors::{Any, CorsLayer},
+    compression::CompressionLayer,
+    cors::{AllowOrigin, Any, CorsLayer},
     trace::TraceLayer,
 };
 use tracing::info;
-use wreq_util::Emulation;
-
-use crate::{cache::CacheManager, config::Config, tmdb::TmdbClient};
-
-#[derive(Clone)]
-pub struct AppState {
-    pub config: Arc<Config>,
-    pub http: wreq::Client,
-    pub cache: CacheManager,
-    pub tmdb: Arc<TmdbClient>,
-}
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -41,40 +23,83 @@ async fn main() -> anyhow::Result<()> {
         .init();
 
     let config = Arc::new(Config::from_env()?);
+    let state = Arc::new(AppState::from_config(config.clone()).await?);
 
-    let http = wreq::Client::builder()
-        .emulation(Emulation::Chrome131)
-        .timeout(Duration::from_secs(10))
-        .build()?;
-
-    let db = db::connect_and_migrate(&config.database_url).await?;
-    let cache = CacheManager::new(
-        db,
-        config.cache_ttl_days,
-        config.release_cache_hours,
-        config.provider_cache_days,
-    );
+    prewarm::spawn(state.clone());
+    subscriptions::spawn(state.clone());
+    db::spawn_checkpoints(state.cache.db().clone(), config.wal_checkpoint_secs);
 
-    let tmdb = TmdbClient::new(
-        http.clone(),
-        config.tmdb_access_token.clone(),
-        config.tmdb_base_url.clone(),
-        config.tmdb_rps,
-    );
-
-    let state = Arc::new(AppState { config: config.clone(), http, cache, tmdb: Arc::new(tmdb) });
+    let allow_origin = match &config.cors_allowed_origins {
+        Some(origins) => AllowOrigin::list(
+            origins
+                .iter()
+                .map(|o| o.parse().with_context(|| format!("CORS_ALLOWED_ORIGINS: {o}")))
+                .collect::<anyhow::Result<Vec<_>>>()?,
+        ),
+        None => AllowOrigin::any(),
+    };
 
     let app = Router::new()
         .route("/", get(routes::index))
         .route("/release-dates", get(routes::track))
         .route("/process", get(routes::process))
-        .with_state(state)
-        .layer(CorsLayer::new().allow_origin(Any).allow_headers(Any))
-        .layer(TraceLayer::new_for_http());
+        .route("/compare", get(routes::compare))
+        .route("/refresh", post(routes::refresh))
+        .route("/report", post(routes::report))
+        .route("/subscribe", post(routes::subscribe))
+        .route("/subscribe/confirm", get(routes::confirm_subscription))
+        .route("/unsubscribe", post(routes::unsubscribe))
+        .route("/api/countries", get(routes::countries))
+        .route("/api/resolve", get(routes::resolve))
+        .route("/api/film/{tmdb_id}", get(routes::film))
+        .route("/img/{kind}/{file}", get(routes::image))
+        .route("/admin/db/vacuum", post(routes::vacuum))
+        .route("/admin/match-overrides", post(routes::match_override))
+        .with_state(state.clone())
+        .layer(middleware::from_fn(error::negotiate))
+        // The default predicate skips tiny bodies, images and event streams
+        .layer(CompressionLayer::new())
+        .layer(CorsLayer::new().allow_origin(allow_origin).allow_headers(Any))
+        .layer(TraceLayer::new_for_http().make_span_with(request_id::make_span))
+        .layer(middleware::from_fn(request_id::assign));
 
     let listener = tokio::net::TcpListener::bind(config.addr).await?;
     info!(addr = %config.addr, "server listening");
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app).with_graceful_shutdown(shutdown_signal()).await?;
+
+    info!("shutting down");
+    if let Err(e) = db::checkpoint(state.cache.db()).await {
+        tracing::warn!(error = %e, "final WAL checkpoint failed");
+    }
 
     Ok(())
 }
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!(error = %e, "failed to listen for ctrl-c");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            },
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            },
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
@@ -1,33 +1,155 @@
+use std::collections::HashSet;
+
 use jiff::civil::Date;
 use serde::{Deserialize, Serialize};
 
+use crate::error::{AppError, AppResult};
+
 #[derive(Clone, Debug)]
 pub struct WishlistFilm {
     pub letterboxd_slug: String,
     pub year: Option<i16>,
+    /// Owner's rating in half stars (1-10), when the watchlist exposes it
+    pub rating: Option<u8>,
+    pub liked: bool,
+}
+
+/// Where the tracked films come from on Letterboxd.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FilmSource {
+    Watchlist(String),
+    List { username: String, slug: String },
+}
+
+impl FilmSource {
+    pub fn username(&self) -> &str {
+        match self {
+            FilmSource::Watchlist(username) | FilmSource::List { username, .. } => username,
+        }
+    }
+
+    /// A short name for titles, e.g. `alice` or `alice/best-of-2025`.
+    pub fn display_name(&self) -> String {
+        match self {
+            FilmSource::Watchlist(username) => username.clone(),
+            FilmSource::List { username, slug } => format!("{username}/{slug}"),
+        }
+    }
+
+    /// What to put back in the username field to request this source again.
+    pub fn input(&self) -> String {
+        match self {
+            FilmSource::Watchlist(username) => username.clone(),
+            FilmSource::List { .. } => self.letterboxd_url(),
+        }
+    }
+
+    pub fn letterboxd_url(&self) -> String {
+        match self {
+            FilmSource::Watchlist(username) => {
+                format!("https://letterboxd.com/{}/", urlencoding::encode(username))
+            },
+            FilmSource::List { username, slug } => format!(
+                "https://letterboxd.com/{}/list/{}/",
+                urlencoding::encode(username),
+                urlencoding::encode(slug)
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Watchlist {
+    pub films: Vec<WishlistFilm>,
+    pub truncated: bool,
+    /// Why reading stopped early when a page after the first failed; `films` holds the pages
+    /// read before it
+    pub interrupted: Option<AppError>,
+}
+
+impl Watchlist {
+    /// The watchlist, or the error that stopped it being read in full.
+    pub fn complete(self) -> AppResult<Self> {
+        match self.interrupted {
+            Some(err) => Err(err),
+            None => Ok(self),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
 pub enum ReleaseType {
+    Premiere,
+    TheatricalLimited,
     Theatrical,
     Digital,
+    Physical,
 }
 
 impl ReleaseType {
     pub fn as_tmdb_code(self) -> i32 {
         match self {
+            ReleaseType::Premiere => 1,
+            ReleaseType::TheatricalLimited => 2,
             ReleaseType::Theatrical => 3,
             ReleaseType::Digital => 4,
+            ReleaseType::Physical => 5,
         }
     }
 
     pub fn from_tmdb_code(code: i32) -> Option<Self> {
         match code {
+            1 => Some(ReleaseType::Premiere),
+            2 => Some(ReleaseType::TheatricalLimited),
             3 => Some(ReleaseType::Theatrical),
             4 => Some(ReleaseType::Digital),
+            5 => Some(ReleaseType::Physical),
             _ => None,
         }
     }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "premiere" => Some(ReleaseType::Premiere),
+            "limited" => Some(ReleaseType::TheatricalLimited),
+            "theatrical" => Some(ReleaseType::Theatrical),
+            "digital" => Some(ReleaseType::Digital),
+            "physical" => Some(ReleaseType::Physical),
+            _ => None,
+        }
+    }
+}
+
+/// A column of release dates on a film card and the release types shown in it.
+#[derive(Clone, Debug)]
+pub struct ReleaseColumn {
+    pub label: String,
+    pub types: Vec<ReleaseType>,
+}
+
+impl ReleaseColumn {
+    /// Theatrical releases of every kind in one column, streaming in the other.
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self {
+                label: "Theatrical".to_string(),
+                types: vec![
+                    ReleaseType::Premiere,
+                    ReleaseType::TheatricalLimited,
+                    ReleaseType::Theatrical,
+                ],
+            },
+            Self { label: "Streaming".to_string(), types: vec![ReleaseType::Digital] },
+        ]
+    }
+
+    /// Parses a `Label=type,type` entry, as used by `RELEASE_COLUMNS`.
+    pub fn parse(entry: &str) -> Option<Self> {
+        let (label, types) = entry.split_once('=')?;
+        let label = label.trim();
+        let types: Vec<ReleaseType> = types.split(',').filter_map(ReleaseType::from_name).collect();
+        (!label.is_empty() && !types.is_empty()).then(|| Self { label: label.to_string(), types })
+    }
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -35,6 +157,52 @@ pub struct ReleaseDate {
     pub date: Date,
     pub release_type: ReleaseType,
     pub note: Option<String>,
+    /// The note as TMDB wrote it, when cleaning it up for `note` changed it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_note: Option<String>,
+    /// Country the date applies to, set once fallbacks are applied
+    pub source_country: Option<String>,
+}
+
+/// A release date that moved since it was last cached.
+#[derive(Clone, Debug, Serialize)]
+pub struct ReleaseDateChange {
+    pub country: String,
+    pub release_type: ReleaseType,
+    pub previous_date: Date,
+    pub new_date: Date,
+}
+
+/// Note on the single past release kept for films that are already out.
+pub const ALREADY_AVAILABLE_NOTE: &str = "Already available";
+
+/// Today's date in the named IANA time zone (e.g. `Pacific/Auckland`), or in UTC when the name
+/// is empty or unknown.
+pub fn today_in(time_zone: &str) -> Date {
+    let tz = jiff::tz::TimeZone::get(time_zone.trim()).unwrap_or(jiff::tz::TimeZone::UTC);
+    jiff::Timestamp::now().to_zoned(tz).date()
+}
+
+/// Sorts by date and drops repeated entries, including a bare entry when another on the same
+/// date and of the same type carries a note.
+pub fn dedup_release_dates(releases: &mut Vec<ReleaseDate>) {
+    releases.sort_by(|a, b| {
+        (a.date, a.release_type.as_tmdb_code(), &a.note).cmp(&(
+            b.date,
+            b.release_type.as_tmdb_code(),
+            &b.note,
+        ))
+    });
+    releases
+        .dedup_by(|a, b| (a.date, a.release_type, &a.note) == (b.date, b.release_type, &b.note));
+
+    let noted: HashSet<_> = releases
+        .iter()
+        .filter(|r| r.note.is_some())
+        .map(|r| (r.date, r.release_type.as_tmdb_code()))
+        .collect();
+    releases
+        .retain(|r| r.note.is_some() || !noted.contains(&(r.date, r.release_type.as_tmdb_code())));
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
@@ -77,6 +245,31 @@ pub struct WatchProvider {
     pub logo_path: String,
     pub link: Option<String>,
     pub provider_type: ProviderType,
+    /// TMDB's recommended ordering, lower first
+    pub display_priority: i32,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum MatchConfidence {
+    High,
+    Low,
+}
+
+impl MatchConfidence {
+    pub fn as_code(self) -> i32 {
+        match self {
+            MatchConfidence::High => 1,
+            MatchConfidence::Low => 2,
+        }
+    }
+
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            1 => Some(MatchConfidence::High),
+            2 => Some(MatchConfidence::Low),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -88,25 +281,203 @@ pub struct FilmWithReleases {
     pub poster_path: Option<String>,
     pub theatrical: Vec<ReleaseDate>,
     pub streaming: Vec<ReleaseDate>,
+    /// Premiere or physical dates, only given when there are no theatrical or streaming dates
+    pub fallback_releases: Vec<ReleaseDate>,
     pub category: ReleaseCategory,
+    /// Only limited theatrical dates, with no wide or streaming release yet
+    pub limited_only: bool,
     pub streaming_providers: Vec<WatchProvider>,
+    /// The provider lookup failed, so an empty `streaming_providers` doesn't mean there are none
+    pub providers_unavailable: bool,
+    pub match_confidence: MatchConfidence,
+    pub date_changes: Vec<ReleaseDateChange>,
+    pub rating: Option<u8>,
+    pub liked: bool,
+    /// Countries where the film has already been released
+    pub released_regions: Vec<String>,
+    /// When the release dates were last fetched from TMDB, in unix seconds
+    pub releases_cached_at: Option<i64>,
+    /// Releases in the extra countries the viewer asked for, in the order requested
+    pub other_countries: Vec<CountryReleases>,
+    /// Rough streaming date for upcoming films that only have theatrical dates
+    pub estimated_streaming: Option<jiff::civil::Date>,
+    /// First cached, from any watchlist, after the viewer's `since` time
+    pub is_new: bool,
+    /// Watch providers in a second country, for already available films when the viewer
+    /// compares two countries
+    pub compare_providers: Option<CountryProviders>,
+}
+
+impl FilmWithReleases {
+    /// Theatrical, streaming and fallback dates, each list sorted by date.
+    pub fn releases(&self) -> impl Iterator<Item = &ReleaseDate> + Clone {
+        self.theatrical.iter().chain(&self.streaming).chain(&self.fallback_releases)
+    }
+
+    /// The date the film is listed under: the first theatrical date, then streaming, then
+    /// fallback.
+    pub fn first_release(&self) -> Option<&ReleaseDate> {
+        self.theatrical
+            .first()
+            .or_else(|| self.streaming.first())
+            .or_else(|| self.fallback_releases.first())
+    }
+}
+
+/// Availability of a film as of a subscription's last check.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FilmAvailability {
+    pub available: bool,
+    pub provider_ids: Vec<i32>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AvailabilityChange {
+    pub tmdb_id: i32,
+    pub title: String,
+    pub year: Option<i16>,
+    pub letterboxd_slug: String,
+    pub became_available: bool,
+    pub new_providers: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct TrackRequest {
     pub username: String,
+    #[serde(default)]
     pub country: String,
+    #[serde(default)]
+    pub sort: SortOrder,
+    /// Falls back to the `layout` cookie when not given
+    #[serde(default)]
+    pub layout: Option<Layout>,
+    /// Comma-separated countries to show alongside `country`
+    #[serde(default)]
+    pub also: String,
+    /// Leave out films without release dates; falls back to the `hide_undated` cookie
+    #[serde(default)]
+    pub hide_undated: Option<bool>,
+    /// Only show releases up to this many months ahead, 0 for all
+    #[serde(default)]
+    pub window: u32,
+    /// Unix time of the last visit, to mark films first seen since then as new
+    #[serde(default)]
+    pub since: Option<i64>,
+    /// With `since`, only show the new films
+    #[serde(default)]
+    pub new_only: bool,
+    /// Comma-separated TMDB provider ids the viewer subscribes to; falls back to the
+    /// `services` cookie
+    #[serde(default)]
+    pub services: Option<String>,
+    /// Only show subscription streaming, hiding rent and buy; falls back to the
+    /// `flatrate_only` cookie, then `FLATRATE_ONLY`
+    #[serde(default)]
+    pub flatrate_only: Option<bool>,
+    /// Order the watchlist is read in, which decides the films kept under the film cap
+    #[serde(default)]
+    pub order: WatchlistOrder,
+    /// Country to compare streaming services with for films already available
+    #[serde(default)]
+    pub compare_providers: String,
+    /// Skip saving the username and country in cookies
+    #[serde(default)]
+    pub forget: bool,
+    /// Go straight to the results page, for browsers without JavaScript
+    #[serde(default)]
+    pub nojs: bool,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Release,
+    /// Liked and highly rated films first, then by release date
+    Liked,
+}
+
+impl SortOrder {
+    pub fn as_query(self) -> &'static str {
+        match self {
+            SortOrder::Release => "release",
+            SortOrder::Liked => "liked",
+        }
+    }
+}
+
+/// One of Letterboxd's `/by/…/` sorts, used when paging through a watchlist or list.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WatchlistOrder {
+    /// Newest releases first, which lets paging stop once films get older than the cutoff
+    #[default]
+    Release,
+    Added,
+    AddedEarliest,
+    Rating,
+    Popular,
+}
+
+impl WatchlistOrder {
+    pub fn as_query(self) -> &'static str {
+        match self {
+            WatchlistOrder::Release => "release",
+            WatchlistOrder::Added => "added",
+            WatchlistOrder::AddedEarliest => "added-earliest",
+            WatchlistOrder::Rating => "rating",
+            WatchlistOrder::Popular => "popular",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    #[default]
+    Cards,
+    /// One line per film without posters, for long watchlists
+    Compact,
+}
+
+impl Layout {
+    pub fn as_query(self) -> &'static str {
+        match self {
+            Layout::Cards => "cards",
+            Layout::Compact => "compact",
+        }
+    }
+
+    pub fn from_query(value: &str) -> Option<Self> {
+        match value {
+            "cards" => Some(Layout::Cards),
+            "compact" => Some(Layout::Compact),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CountryProviders {
+    pub country: String,
+    pub providers: Vec<WatchProvider>,
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub struct CountryReleases {
     pub country: String,
     pub theatrical: Vec<ReleaseDate>,
     pub streaming: Vec<ReleaseDate>,
+    /// Dates of `FALLBACK_RELEASE_TYPES`, never mixed into the lists above
+    pub fallback: Vec<ReleaseDate>,
 }
 
 #[derive(Clone, Debug)]
 pub struct ReleaseDatesResult {
     pub requested_country: CountryReleases,
     pub all_countries: Vec<CountryReleases>,
+    /// TMDB returned an empty `results` list, i.e. no country has any release dates.
+    pub confirmed_empty: bool,
+    /// Every country with a release date in the past, sorted
+    pub released_countries: Vec<String>,
 }
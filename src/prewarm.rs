@@ -0,0 +1,101 @@
+use std::{sync::Arc, time::Duration};
+
+use tracing::{info, warn};
+
+use crate::{
+    AppState,
+    countries::CountryCode,
+    models::{FilmSource, WatchlistOrder, today_in},
+    processor::ProcessOutcome,
+};
+
+/// Periodically runs the normal processing pipeline for the configured
+/// profiles so their films are already cached when users visit.
+pub fn spawn(state: Arc<AppState>) {
+    if state.config.prewarm_profiles.is_empty() {
+        return;
+    }
+
+    let interval = Duration::from_secs(state.config.prewarm_interval_hours.max(1) * 60 * 60);
+    info!(
+        profiles = state.config.prewarm_profiles.len(),
+        interval_hours = interval.as_secs() / 3600,
+        "cache prewarming enabled"
+    );
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            // Profiles are warmed one at a time so the shared TMDB rate limiter
+            // and Letterboxd delay are left with headroom for real visitors.
+            for (username, country) in &state.config.prewarm_profiles {
+                warm_profile(&state, username, *country).await;
+            }
+        }
+    });
+}
+
+/// Fetches a watchlist and runs it through the processing pipeline, returning the
+/// watchlist size alongside the outcome.
+pub async fn run_profile(
+    state: &AppState,
+    username: &str,
+    country: CountryCode,
+    dry_run: bool,
+) -> anyhow::Result<(usize, ProcessOutcome)> {
+    let today = today_in("UTC");
+
+    let watchlist = crate::scraper::fetch_watchlist(
+        &state.http,
+        &FilmSource::Watchlist(username.to_string()),
+        state.config.letterboxd_delay_ms,
+        today.year().saturating_sub(3),
+        state.config.max_watchlist_films,
+        WatchlistOrder::Release,
+    )
+    .await?;
+    let watchlist_count = watchlist.films.len();
+
+    let outcome = crate::processor::process(
+        &state.http,
+        &state.cache,
+        &*state.tmdb,
+        watchlist.films,
+        country,
+        crate::processor::ProcessOptions::from_config(&state.config),
+        dry_run,
+    )
+    .await?;
+
+    Ok((watchlist_count, outcome))
+}
+
+async fn warm_profile(state: &AppState, username: &str, country: CountryCode) {
+    let started = std::time::Instant::now();
+
+    let result = async {
+        let (watchlist_count, outcome) = run_profile(state, username, country, false).await?;
+        let result_count = match outcome {
+            ProcessOutcome::Films(films) | ProcessOutcome::Partial(films) => films.len(),
+            ProcessOutcome::DryRun(_) => 0,
+        };
+
+        Ok::<_, anyhow::Error>((watchlist_count, result_count))
+    }
+    .await;
+
+    match result {
+        Ok((watchlist_count, result_count)) => info!(
+            username = %username,
+            country = %country,
+            watchlist_count,
+            result_count,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "prewarmed profile"
+        ),
+        Err(err) => warn!(username = %username, country = %country, error = %err, "prewarm failed"),
+    }
+}
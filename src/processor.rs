@@ -1,58 +1,459 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
 
 use futures::{StreamExt, stream};
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
 
 use crate::{
     cache::{CacheManager, FilmCacheData},
+    config::Config,
+    countries::CountryCode,
+    entities::film_cache,
     error::AppResult,
     models::{
-        CountryReleases, FilmWithReleases, ReleaseCategory, ReleaseDate, WatchProvider,
-        WishlistFilm,
+        ALREADY_AVAILABLE_NOTE, AvailabilityChange, CountryProviders, CountryReleases,
+        FilmAvailability, FilmWithReleases, MatchConfidence, ReleaseCategory, ReleaseDate,
+        ReleaseType, WatchProvider, WishlistFilm, today_in,
     },
-    scraper,
-    tmdb::TmdbClient,
+    sources::{FilmResolver, ReleaseSource},
+    tmdb::{SearchMatch, search_variants},
 };
 
+#[derive(Debug, Default)]
+struct ProcessStats {
+    film_hits: usize,
+    film_misses: usize,
+    release_hits: usize,
+    release_misses: usize,
+    provider_hits: usize,
+    provider_misses: usize,
+    budget: CallBudget,
+    /// Cache writes and secondary lookups that failed without failing the run
+    cache_failures: usize,
+    /// The resolve deadline passed before every new film was resolved
+    deadline_reached: bool,
+}
+
+/// Counts the TMDB calls of one run and stops handing them out past `limit`. Film resolution
+/// spends it from concurrent tasks, so it counts atomically.
+#[derive(Debug, Default)]
+struct CallBudget {
+    limit: Option<usize>,
+    used: AtomicUsize,
+    exhausted: AtomicBool,
+}
+
+impl CallBudget {
+    fn new(limit: Option<usize>) -> Self {
+        Self { limit, ..Default::default() }
+    }
+
+    /// Counts one call, or returns false without counting it once the budget is spent.
+    fn spend(&self) -> bool {
+        let spent = self
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                self.limit.is_none_or(|limit| used < limit).then_some(used + 1)
+            })
+            .is_ok();
+        if !spent {
+            self.exhausted.store(true, Ordering::Relaxed);
+        }
+        spent
+    }
+
+    fn used(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    fn exhausted(&self) -> bool {
+        self.exhausted.load(Ordering::Relaxed)
+    }
+}
+
+impl ProcessStats {
+    /// Cache writes only save work for later requests, and the lookups passed here only add
+    /// detail, so a failure is logged and counted and the run carries on without it.
+    fn non_fatal<T: Default>(&mut self, what: &str, result: AppResult<T>) -> T {
+        result.unwrap_or_else(|err| {
+            warn!(error = %err, what, "cache operation failed, continuing without it");
+            self.cache_failures += 1;
+            T::default()
+        })
+    }
+}
+
+/// What a real run would fetch, computed from cache lookups alone.
+#[derive(Debug, Default)]
+pub struct DryRunSummary {
+    pub films: usize,
+    pub cached_films: usize,
+    pub film_searches: usize,
+    pub release_requests: usize,
+    pub cached_release_requests: usize,
+    pub release_fetches: usize,
+    pub provider_requests: usize,
+    pub cached_provider_requests: usize,
+    pub provider_fetches: usize,
+}
+
+impl DryRunSummary {
+    pub fn tmdb_calls(&self) -> usize {
+        self.film_searches + self.release_fetches + self.provider_fetches
+    }
+}
+
+/// Per-deployment knobs for processing, taken from `Config`.
+#[derive(Clone, Debug)]
+pub struct ProcessOptions {
+    pub max_concurrent: usize,
+    /// Concurrency while resolving uncached films, which scrapes a Letterboxd page per film
+    pub letterboxd_max_concurrent: usize,
+    /// Also look up providers for films whose streaming release is still upcoming
+    pub upcoming_providers: bool,
+    /// Stop resolving uncached films after this long and continue with those resolved so far
+    pub resolve_deadline: Option<Duration>,
+    /// The viewer's date, which decides whether a release is still upcoming
+    pub today: jiff::civil::Date,
+    /// Countries shown alongside the requested one, each without fallbacks
+    pub extra_countries: Vec<CountryCode>,
+    /// Typical days from theatrical to streaming, 0 for no estimates
+    pub streaming_estimate_days: i64,
+    /// Drop past releases and those more than this many months ahead; 0 keeps every release
+    pub release_window_months: u32,
+    /// Unix time of the viewer's last visit; films the cache first saw after it, on any
+    /// watchlist, are marked new
+    pub since: Option<i64>,
+    /// With `since`, leave out films that aren't new
+    pub new_only: bool,
+    /// Most TMDB calls a run may make; cached data is used for the rest
+    pub tmdb_call_budget: Option<usize>,
+    /// Also look up providers in this country for already available films, to compare
+    pub compare_providers: Option<CountryCode>,
+    /// Skip films whose last search found no acceptable match until that entry expires. Only
+    /// worth it when match thresholds reject results; otherwise no match is retried every run.
+    pub skip_unmatched: bool,
+}
+
+impl ProcessOptions {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_concurrent: config.max_concurrent,
+            letterboxd_max_concurrent: config.letterboxd_max_concurrent,
+            upcoming_providers: config.upcoming_providers,
+            resolve_deadline: (config.resolve_deadline_secs > 0)
+                .then(|| Duration::from_secs(config.resolve_deadline_secs)),
+            today: today_in("UTC"),
+            extra_countries: Vec::new(),
+            streaming_estimate_days: config.streaming_estimate_days,
+            release_window_months: 0,
+            since: None,
+            new_only: false,
+            tmdb_call_budget: (config.tmdb_max_calls_per_run > 0)
+                .then_some(config.tmdb_max_calls_per_run),
+            compare_providers: None,
+            skip_unmatched: config.min_match_popularity > 0.0 || config.min_match_votes > 0,
+        }
+    }
+}
+
+pub enum ProcessOutcome {
+    Films(Vec<FilmWithReleases>),
+    /// Some cache operations failed, or the TMDB call budget or resolve deadline ran out, so
+    /// films or details may be missing and not everything new was cached
+    Partial(Vec<FilmWithReleases>),
+    DryRun(DryRunSummary),
+}
+
+struct ResolvedFilm {
+    slug: String,
+    tmdb_id: i32,
+    title: String,
+    year: Option<i16>,
+    poster_path: Option<String>,
+    match_confidence: MatchConfidence,
+    first_seen_at: Option<i64>,
+}
+
 pub async fn process(
-    http: &wreq::Client,
+    http: &impl FilmResolver,
     cache: &CacheManager,
-    tmdb: &TmdbClient,
+    tmdb: &impl ReleaseSource,
     films: Vec<WishlistFilm>,
-    country: &str,
-    max_concurrent: usize,
-    current_year: i16,
-) -> AppResult<Vec<FilmWithReleases>> {
-    let cutoff_year = current_year.saturating_sub(3);
+    country: CountryCode,
+    options: ProcessOptions,
+    dry_run: bool,
+) -> AppResult<ProcessOutcome> {
+    let cutoff_year = options.today.year().saturating_sub(3);
 
     debug!(total_films = films.len(), cutoff_year = cutoff_year, "filtering films by year");
 
-    let films = films
+    // Films without a watchlist year are checked again once their film page year is known
+    let mut films = films
         .into_iter()
         .filter(|f| f.year.map(|y| y >= cutoff_year).unwrap_or(true))
         .collect::<Vec<_>>();
 
     debug!(filtered_films = films.len(), "films after year filtering");
 
+    apply_slug_aliases(cache, &mut films).await?;
+
+    if dry_run {
+        return plan_films(cache, films, country, &options).await.map(ProcessOutcome::DryRun);
+    }
+
     if films.is_empty() {
-        return Ok(Vec::new());
+        return Ok(ProcessOutcome::Films(Vec::new()));
+    }
+
+    let (films, complete) =
+        process_films(http, cache, tmdb, films, country, options, Some(cutoff_year)).await?;
+    Ok(if complete { ProcessOutcome::Films(films) } else { ProcessOutcome::Partial(films) })
+}
+
+/// Whether a cached film needs no new search: it has a TMDB ID, or it recently found no match
+/// and `skip_unmatched` is set.
+fn is_resolved(cached: Option<&film_cache::Model>, skip_unmatched: bool) -> bool {
+    cached.is_some_and(|c| c.tmdb_id.is_some() || skip_unmatched)
+}
+
+/// Rewrites slugs of renamed films to their current slug so they hit the film cache.
+async fn apply_slug_aliases(cache: &CacheManager, films: &mut [WishlistFilm]) -> AppResult<()> {
+    let slugs: Vec<String> = films.iter().map(|f| f.letterboxd_slug.clone()).collect();
+    let aliases = cache.get_slug_aliases(&slugs).await?;
+
+    for film in films.iter_mut() {
+        if let Some(canonical) = aliases.get(&film.letterboxd_slug) {
+            film.letterboxd_slug = canonical.clone();
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs the cache lookups of `process_films` and counts the TMDB requests a real run would make,
+/// without scraping film pages or calling TMDB. Films that still need a TMDB search can't be
+/// planned further, so the release and provider counts only cover films already in the cache.
+async fn plan_films(
+    cache: &CacheManager,
+    films: Vec<WishlistFilm>,
+    country: CountryCode,
+    options: &ProcessOptions,
+) -> AppResult<DryRunSummary> {
+    let today = options.today;
+    let mut summary = DryRunSummary { films: films.len(), ..Default::default() };
+
+    let slugs: Vec<String> = films.iter().map(|f| f.letterboxd_slug.clone()).collect();
+    let cached_films = cache.get_films(&slugs).await?;
+    let tmdb_ids: Vec<i32> = films
+        .iter()
+        .filter_map(|f| cached_films.get(&f.letterboxd_slug).and_then(|c| c.tmdb_id))
+        .collect();
+    summary.cached_films = tmdb_ids.len();
+    summary.film_searches = films
+        .iter()
+        .filter(|f| !is_resolved(cached_films.get(&f.letterboxd_slug), options.skip_unmatched))
+        .count();
+
+    let release_requests =
+        release_request_keys(tmdb_ids.iter().copied(), country, &options.extra_countries);
+    let cached_releases = cache.get_releases(&release_requests).await?;
+    let uncached_release_ids: HashSet<i32> = release_requests
+        .iter()
+        .filter(|req| !cached_releases.contains_key(req))
+        .map(|(tmdb_id, _)| *tmdb_id)
+        .collect();
+    summary.release_requests = release_requests.len();
+    summary.cached_release_requests = cached_releases.len();
+    summary.release_fetches = uncached_release_ids.len();
+
+    // Same filters as the real provider lookups, except that films whose releases aren't
+    // cached yet are assumed to need every lookup
+    let no_new_releases = HashMap::new();
+    let window = release_window(options);
+    let compare = options.compare_providers.filter(|other| *other != country);
+    let mut provider_requests: Vec<(i32, CountryCode)> = Vec::new();
+    for film in &films {
+        let Some(cached) = cached_films.get(&film.letterboxd_slug) else { continue };
+        let Some(tmdb_id) = cached.tmdb_id else { continue };
+        if options.new_only
+            && let Some(since) = options.since
+            && cached.first_seen_at.is_none_or(|seen| seen <= since)
+        {
+            continue;
+        }
+
+        let (lookup, compare_lookup) = if uncached_release_ids.contains(&tmdb_id) {
+            (true, true)
+        } else {
+            let releases = get_releases_with_fallback_bulk(
+                &cached_releases,
+                &no_new_releases,
+                tmdb_id,
+                country,
+                "",
+                today,
+                window,
+            );
+            let upcoming_streaming = releases.streaming.iter().any(|r| r.date > today);
+            (
+                options.upcoming_providers || !upcoming_streaming,
+                releases.category == ReleaseCategory::LocalAlreadyAvailable,
+            )
+        };
+        if lookup {
+            provider_requests.push((tmdb_id, country));
+        }
+        if let Some(other) = compare.filter(|_| compare_lookup) {
+            provider_requests.push((tmdb_id, other));
+        }
     }
+    let cached_providers = cache.get_providers(&provider_requests).await?;
+    summary.provider_requests = provider_requests.len();
+    summary.cached_provider_requests = cached_providers.len();
+    summary.provider_fetches = provider_requests.len() - cached_providers.len();
+
+    info!(
+        films = summary.films,
+        film_searches = summary.film_searches,
+        release_fetches = summary.release_fetches,
+        provider_fetches = summary.provider_fetches,
+        tmdb_calls = summary.tmdb_calls(),
+        "dry run summary"
+    );
+
+    Ok(summary)
+}
+
+pub async fn refresh_film(
+    http: &impl FilmResolver,
+    cache: &CacheManager,
+    tmdb: &impl ReleaseSource,
+    slug: &str,
+    country: CountryCode,
+    options: ProcessOptions,
+) -> AppResult<Option<FilmWithReleases>> {
+    debug!(slug = %slug, country = %country, "refreshing film");
+
+    cache.invalidate_film(slug).await?;
+
+    let film =
+        WishlistFilm { letterboxd_slug: slug.to_string(), year: None, rating: None, liked: false };
+    let (results, _) = process_films(http, cache, tmdb, vec![film], country, options, None).await?;
+
+    Ok(results.into_iter().next())
+}
+
+/// Resolves one Letterboxd slug to TMDB data, serving it from the film cache when fresh.
+/// Returns the film and whether it came from the cache.
+pub async fn resolve_film(
+    http: &impl FilmResolver,
+    cache: &CacheManager,
+    tmdb: &impl ReleaseSource,
+    slug: &str,
+) -> AppResult<(FilmCacheData, bool)> {
+    let slug = cache
+        .get_slug_aliases(&[slug.to_string()])
+        .await?
+        .remove(slug)
+        .unwrap_or_else(|| slug.to_string());
+
+    if let Some(cached) = cache.get_films(std::slice::from_ref(&slug)).await?.remove(&slug)
+        && cached.tmdb_id.is_some()
+    {
+        let film = FilmCacheData {
+            slug: cached.letterboxd_slug,
+            tmdb_id: cached.tmdb_id,
+            title: cached.title,
+            year: cached.year.map(|y| y as i16),
+            poster_path: cached.poster_path,
+            match_confidence: MatchConfidence::from_code(cached.match_confidence)
+                .unwrap_or(MatchConfidence::High),
+        };
+        return Ok((film, true));
+    }
+
+    let overrides = cache.get_overrides(std::slice::from_ref(&slug)).await?;
+    let film =
+        WishlistFilm { letterboxd_slug: slug.clone(), year: None, rating: None, liked: false };
+    let budget = CallBudget::default();
+    let (resolved, aliases, _) =
+        resolve_uncached_films(http, tmdb, vec![film], &overrides, 1, &budget, None).await?;
+    let resolved =
+        resolved.into_iter().next().ok_or_else(|| anyhow::anyhow!("failed to resolve {slug}"))?;
+    cache.upsert_films(vec![resolved.clone()]).await?;
+    cache.put_slug_aliases(&aliases).await?;
+
+    Ok((resolved, false))
+}
+
+async fn process_films(
+    http: &impl FilmResolver,
+    cache: &CacheManager,
+    tmdb: &impl ReleaseSource,
+    films: Vec<WishlistFilm>,
+    country: CountryCode,
+    options: ProcessOptions,
+    cutoff_year: Option<i16>,
+) -> AppResult<(Vec<FilmWithReleases>, bool)> {
+    let started = Instant::now();
+    let mut stats =
+        ProcessStats { budget: CallBudget::new(options.tmdb_call_budget), ..Default::default() };
+    let total_films = films.len();
+
+    let mut undated: HashSet<String> =
+        films.iter().filter(|f| f.year.is_none()).map(|f| f.letterboxd_slug.clone()).collect();
+
+    // Ratings are per user, so they come from the watchlist rather than the film cache
+    let mut owner_signals: HashMap<String, (Option<u8>, bool)> =
+        films.iter().map(|f| (f.letterboxd_slug.clone(), (f.rating, f.liked))).collect();
 
     // Phase 1: Bulk load film cache
     let slugs: Vec<String> = films.iter().map(|f| f.letterboxd_slug.clone()).collect();
     let cached_films = cache.get_films(&slugs).await?;
     debug!(cached_films = cached_films.len(), "films found in cache");
 
-    // Phase 2: Partition into cached vs uncached
+    // Phase 2: Partition into cached vs uncached. With match thresholds, fresh entries without
+    // a TMDB ID are films that recently failed to resolve, which are skipped until they expire.
     let (cached, uncached): (Vec<_>, Vec<_>) = films
         .into_iter()
-        .partition(|f| cached_films.get(&f.letterboxd_slug).and_then(|c| c.tmdb_id).is_some());
+        .partition(|f| is_resolved(cached_films.get(&f.letterboxd_slug), options.skip_unmatched));
 
     debug!(cached_count = cached.len(), uncached_count = uncached.len(), "partitioned films");
+    stats.film_hits = cached.len();
+    stats.film_misses = uncached.len();
 
     // Phase 3: Resolve uncached films (scrape Letterboxd, search TMDB)
-    let newly_resolved = resolve_uncached_films(http, tmdb, uncached, max_concurrent).await?;
-    cache.upsert_films(newly_resolved.clone()).await?;
+    let concurrency =
+        effective_concurrency(options.letterboxd_max_concurrent, tmdb.rps(), uncached.len());
+    let uncached_slugs: Vec<String> = uncached.iter().map(|f| f.letterboxd_slug.clone()).collect();
+    let overrides = cache.get_overrides(&uncached_slugs).await?;
+    let (newly_resolved, aliases, deadline_reached) = resolve_uncached_films(
+        http,
+        tmdb,
+        uncached,
+        &overrides,
+        concurrency,
+        &stats.budget,
+        options.resolve_deadline,
+    )
+    .await?;
+    stats.deadline_reached = deadline_reached;
+    let stored = cache.upsert_films(newly_resolved.clone()).await;
+    stats.non_fatal("store resolved films", stored);
+    let stored = cache.put_slug_aliases(&aliases).await;
+    stats.non_fatal("store slug aliases", stored);
+    for (slug, canonical) in &aliases {
+        if let Some(signals) = owner_signals.get(slug).copied() {
+            owner_signals.insert(canonical.clone(), signals);
+        }
+        if undated.contains(slug) {
+            undated.insert(canonical.clone());
+        }
+    }
     debug!(resolved_count = newly_resolved.len(), "newly resolved films");
 
     // Phase 4: Build complete film list with TMDB IDs
@@ -60,93 +461,194 @@ pub async fn process(
 
     // Add cached films
     for film in cached {
-        if let Some(cached_film) = cached_films.get(&film.letterboxd_slug) {
-            if let Some(tmdb_id) = cached_film.tmdb_id {
-                all_films_with_tmdb.push((
-                    film.letterboxd_slug.clone(),
-                    tmdb_id,
-                    cached_film.title.clone(),
-                    cached_film.year.map(|y| y as i16),
-                    cached_film.poster_path.clone(),
-                ));
-            }
+        if let Some(cached_film) = cached_films.get(&film.letterboxd_slug)
+            && let Some(tmdb_id) = cached_film.tmdb_id
+        {
+            all_films_with_tmdb.push(ResolvedFilm {
+                slug: film.letterboxd_slug.clone(),
+                tmdb_id,
+                title: cached_film.title.clone(),
+                year: cached_film.year.map(|y| y as i16),
+                poster_path: cached_film.poster_path.clone(),
+                match_confidence: MatchConfidence::from_code(cached_film.match_confidence)
+                    .unwrap_or(MatchConfidence::High),
+                first_seen_at: cached_film.first_seen_at,
+            });
         }
     }
 
     // Add newly resolved films
     for film_data in newly_resolved {
         if let Some(tmdb_id) = film_data.tmdb_id {
-            all_films_with_tmdb.push((
-                film_data.slug,
+            all_films_with_tmdb.push(ResolvedFilm {
+                slug: film_data.slug,
                 tmdb_id,
-                film_data.title,
-                film_data.year,
-                film_data.poster_path,
-            ));
+                title: film_data.title,
+                year: film_data.year,
+                poster_path: film_data.poster_path,
+                match_confidence: film_data.match_confidence,
+                first_seen_at: None,
+            });
         }
     }
 
     debug!(total_with_tmdb = all_films_with_tmdb.len(), "films with TMDB IDs");
 
+    // Newly resolved films may have been cached before under an expired entry
+    if options.since.is_some() {
+        let slugs: Vec<String> = all_films_with_tmdb.iter().map(|f| f.slug.clone()).collect();
+        let first_seen = cache.first_seen(&slugs).await;
+        let first_seen = stats.non_fatal("load first seen times", first_seen);
+        for film in &mut all_films_with_tmdb {
+            film.first_seen_at = first_seen.get(&film.slug).copied();
+        }
+    }
+
+    if let Some(cutoff_year) = cutoff_year {
+        all_films_with_tmdb.retain(|f| {
+            !undated.contains(&f.slug) || f.year.is_none_or(|year| year >= cutoff_year)
+        });
+        debug!(
+            remaining = all_films_with_tmdb.len(),
+            cutoff_year, "films after film page year filtering"
+        );
+    }
+
+    let mut results = fetch_film_releases(
+        cache,
+        tmdb,
+        all_films_with_tmdb,
+        &owner_signals,
+        country,
+        options,
+        &mut stats,
+    )
+    .await?;
+
+    debug!(result_count = results.len(), "completed processing");
+
+    info!(
+        films = total_films,
+        results = results.len(),
+        film_cache_hits = stats.film_hits,
+        film_cache_misses = stats.film_misses,
+        release_cache_hits = stats.release_hits,
+        release_cache_misses = stats.release_misses,
+        provider_cache_hits = stats.provider_hits,
+        provider_cache_misses = stats.provider_misses,
+        tmdb_calls = stats.budget.used(),
+        tmdb_budget_exhausted = stats.budget.exhausted(),
+        cache_failures = stats.cache_failures,
+        elapsed_ms = started.elapsed().as_millis() as u64,
+        "processing summary"
+    );
+
+    results.sort_by_key(|f| f.first_release().map(|r| r.date));
+
+    let complete =
+        stats.cache_failures == 0 && !stats.budget.exhausted() && !stats.deadline_reached;
+    Ok((results, complete))
+}
+
+/// Phases 5 onwards of `process_films`: release dates with fallbacks, then watch providers,
+/// for films that already have a TMDB ID.
+async fn fetch_film_releases(
+    cache: &CacheManager,
+    tmdb: &impl ReleaseSource,
+    all_films_with_tmdb: Vec<ResolvedFilm>,
+    owner_signals: &HashMap<String, (Option<u8>, bool)>,
+    country: CountryCode,
+    options: ProcessOptions,
+    stats: &mut ProcessStats,
+) -> AppResult<Vec<FilmWithReleases>> {
     // Phase 5: Build list of all (tmdb_id, country) pairs needed
-    let release_requests = build_release_requests(&all_films_with_tmdb, country);
+    let release_requests =
+        build_release_requests(&all_films_with_tmdb, country, &options.extra_countries);
     debug!(release_requests = release_requests.len(), "release cache requests");
 
     // Phase 6: Bulk load release cache
     let cached_releases = cache.get_releases(&release_requests).await?;
     debug!(cached_releases_count = cached_releases.len(), "release sets found in cache");
-    for ((tmdb_id, country), (theatrical, streaming)) in &cached_releases {
+    for ((tmdb_id, country), releases) in &cached_releases {
         debug!(
             tmdb_id = tmdb_id,
             country = %country,
-            theatrical_count = theatrical.len(),
-            streaming_count = streaming.len(),
+            theatrical_count = releases.theatrical.len(),
+            streaming_count = releases.streaming.len(),
+            fallback_count = releases.fallback.len(),
             "cached release data"
         );
     }
 
     // Phase 7: Fetch uncached releases from TMDB
-    let uncached_requests: Vec<(i32, String)> =
+    let uncached_requests: Vec<(i32, CountryCode)> =
         release_requests.iter().filter(|req| !cached_releases.contains_key(req)).cloned().collect();
     debug!(uncached_requests_count = uncached_requests.len(), uncached = ?uncached_requests, "uncached requests");
+    stats.release_hits = cached_releases.len();
+    stats.release_misses = uncached_requests.len();
 
     let mut new_releases = HashMap::new();
     if !uncached_requests.is_empty() {
         debug!(uncached_requests = uncached_requests.len(), "fetching uncached releases from TMDB");
 
-        // Group by tmdb_id to avoid duplicate API calls
-        let mut tmdb_ids = HashMap::new();
-        for (tmdb_id, country_code) in &uncached_requests {
-            tmdb_ids.entry(*tmdb_id).or_insert_with(Vec::new).push(country_code.clone());
-        }
-
-        let items: Vec<AppResult<(i32, Vec<String>, Vec<CountryReleases>)>> =
-            stream::iter(tmdb_ids)
-                .map(|(tmdb_id, countries)| async move {
-                    let result = tmdb.get_release_dates(tmdb_id, &countries[0]).await?;
-                    let filtered_countries = result
-                        .all_countries
-                        .into_iter()
-                        .filter(|c| countries.contains(&c.country))
-                        .collect::<Vec<_>>();
-                    Ok((tmdb_id, countries, filtered_countries))
-                })
-                .buffer_unordered(max_concurrent.max(1))
-                .collect()
-                .await;
+        // Group by tmdb_id to avoid duplicate API calls, keeping watchlist order so the budget
+        // goes to the same films on every run
+        let mut tmdb_ids: Vec<(i32, Vec<CountryCode>)> = Vec::new();
+        let mut positions: HashMap<i32, usize> = HashMap::new();
+        for &(tmdb_id, country_code) in &uncached_requests {
+            let at = *positions.entry(tmdb_id).or_insert_with(|| {
+                tmdb_ids.push((tmdb_id, Vec::new()));
+                tmdb_ids.len() - 1
+            });
+            tmdb_ids[at].1.push(country_code);
+        }
+        let wanted = tmdb_ids.len();
+        tmdb_ids.retain(|_| stats.budget.spend());
+        if tmdb_ids.len() < wanted {
+            warn!(fetching = tmdb_ids.len(), "TMDB call budget reached, skipping release lookups");
+        }
+        let concurrency = effective_concurrency(options.max_concurrent, tmdb.rps(), tmdb_ids.len());
+
+        type ReleaseFetch = (i32, Vec<CountryCode>, Vec<CountryReleases>, Vec<String>, bool);
+        let items: Vec<AppResult<ReleaseFetch>> = stream::iter(tmdb_ids)
+            .map(|(tmdb_id, countries)| async move {
+                let result = tmdb.get_release_dates(tmdb_id, countries[0]).await?;
+                let filtered_countries = result
+                    .all_countries
+                    .into_iter()
+                    .filter(|c| countries.iter().any(|code| *code == c.country))
+                    .collect::<Vec<_>>();
+                Ok((
+                    tmdb_id,
+                    countries,
+                    filtered_countries,
+                    result.released_countries,
+                    result.confirmed_empty,
+                ))
+            })
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
 
         for item in items {
             match item {
-                Ok((tmdb_id, requested_countries, mut found_countries)) => {
+                Ok((
+                    tmdb_id,
+                    requested_countries,
+                    mut found_countries,
+                    released_countries,
+                    confirmed_empty,
+                )) => {
                     // Add empty entries for requested countries that had no release data
                     let found_country_codes: Vec<_> =
                         found_countries.iter().map(|c| c.country.clone()).collect();
                     for country_code in requested_countries {
-                        if !found_country_codes.contains(&country_code) {
+                        if !found_country_codes.iter().any(|c| country_code == *c) {
                             found_countries.push(CountryReleases {
-                                country: country_code,
+                                country: country_code.to_string(),
                                 theatrical: vec![],
                                 streaming: vec![],
+                                fallback: vec![],
                             });
                         }
                     }
@@ -156,9 +658,15 @@ pub async fn process(
                         countries = ?found_countries.iter().map(|c| (&c.country, c.theatrical.len(), c.streaming.len())).collect::<Vec<_>>(),
                         "caching release data"
                     );
-                    cache.put_releases_multi_country(tmdb_id, &found_countries).await?;
+                    let stored = cache
+                        .put_releases_multi_country(tmdb_id, &found_countries, confirmed_empty)
+                        .await;
+                    stats.non_fatal("store release dates", stored);
+                    let stored = cache.put_released_regions(tmdb_id, &released_countries).await;
+                    stats.non_fatal("store released regions", stored);
                     new_releases.insert(tmdb_id, found_countries);
                 },
+                Err(err) if err.is_invalid_tmdb_credentials() => return Err(err),
                 Err(err) => warn!(error = %err, "failed to fetch release dates"),
             }
         }
@@ -167,43 +675,105 @@ pub async fn process(
     }
 
     // Phase 8: Assemble final results
+    let tmdb_ids: Vec<i32> = all_films_with_tmdb.iter().map(|f| f.tmdb_id).collect();
+    let date_changes = cache.get_date_changes(&tmdb_ids).await;
+    let mut date_changes = stats.non_fatal("load date changes", date_changes);
+    let released_regions = cache.get_released_regions(&tmdb_ids).await;
+    let mut released_regions = stats.non_fatal("load released regions", released_regions);
+    let releases_cached_at = cache.release_cached_at(&release_requests).await;
+    let releases_cached_at: HashMap<(i32, CountryCode), i64> = stats
+        .non_fatal("load release cache times", releases_cached_at)
+        .into_iter()
+        .map(|(tmdb_id, country, cached_at)| ((tmdb_id, country), cached_at))
+        .collect();
     let mut results = Vec::new();
 
-    for (slug, tmdb_id, title, year, poster_path) in all_films_with_tmdb {
-        debug!(slug = %slug, tmdb_id = tmdb_id, "assembling final result");
+    let release_window = release_window(&options);
+
+    for film in all_films_with_tmdb {
+        debug!(slug = %film.slug, tmdb_id = film.tmdb_id, "assembling final result");
+
+        let FallbackReleases { theatrical, streaming, fallback, category, limited_only, source } =
+            get_releases_with_fallback_bulk(
+                &cached_releases,
+                &new_releases,
+                film.tmdb_id,
+                country,
+                &film.slug,
+                options.today,
+                release_window,
+            );
+
+        let (rating, liked) = owner_signals.get(&film.slug).copied().unwrap_or_default();
+
+        let other_countries = options
+            .extra_countries
+            .iter()
+            .map(|&extra| {
+                let data = get_release_data(&cached_releases, &new_releases, film.tmdb_id, extra);
+                let (theatrical, streaming) =
+                    data.map(|d| (d.theatrical.clone(), d.streaming.clone())).unwrap_or_default();
+                CountryReleases {
+                    country: extra.to_string(),
+                    theatrical: label_releases(theatrical, extra, options.today).0,
+                    streaming: label_releases(streaming, extra, options.today).0,
+                    fallback: vec![],
+                }
+            })
+            .collect();
 
-        let (theatrical, streaming, category) = get_releases_with_fallback_bulk(
-            &cached_releases,
-            &new_releases,
-            tmdb_id,
-            country,
-            &slug,
+        let estimated_streaming = estimate_streaming(
+            &theatrical,
+            &streaming,
+            options.today,
+            options.streaming_estimate_days,
         );
 
         results.push(FilmWithReleases {
-            title,
-            year,
-            tmdb_id,
-            letterboxd_slug: slug,
-            poster_path,
+            title: film.title,
+            year: film.year,
+            tmdb_id: film.tmdb_id,
+            letterboxd_slug: film.slug,
+            poster_path: film.poster_path,
             theatrical,
             streaming,
+            fallback_releases: fallback,
             category,
+            limited_only,
             streaming_providers: vec![],
+            providers_unavailable: false,
+            match_confidence: film.match_confidence,
+            date_changes: date_changes.remove(&film.tmdb_id).unwrap_or_default(),
+            rating,
+            liked,
+            released_regions: released_regions.remove(&film.tmdb_id).unwrap_or_default(),
+            // Dated by the country the shown dates came from, which may be a fallback
+            releases_cached_at: releases_cached_at.get(&(film.tmdb_id, source)).copied(),
+            other_countries,
+            estimated_streaming,
+            is_new: options
+                .since
+                .is_some_and(|since| film.first_seen_at.is_some_and(|seen| seen > since)),
+            compare_providers: None,
         });
     }
 
     debug!(result_count = results.len(), "completed processing releases");
 
-    let today: jiff::civil::Date = jiff::Zoned::now().into();
+    // Filtered before provider lookups so the rest of the watchlist costs nothing
+    if options.new_only && options.since.is_some() {
+        results.retain(|f| f.is_new);
+        debug!(new_count = results.len(), "kept only new films");
+    }
 
-    let provider_requests = build_provider_requests(&results, country, &today);
+    let provider_requests =
+        build_provider_requests(&results, country, &options.today, options.upcoming_providers);
     debug!(provider_requests = provider_requests.len(), "provider cache requests");
 
     let cached_providers = cache.get_providers(&provider_requests).await?;
     debug!(cached_providers_count = cached_providers.len(), "providers found in cache");
 
-    let uncached_provider_requests: Vec<(i32, String)> = provider_requests
+    let uncached_provider_requests: Vec<(i32, CountryCode)> = provider_requests
         .iter()
         .filter(|req| !cached_providers.contains_key(req))
         .cloned()
@@ -212,154 +782,491 @@ pub async fn process(
         uncached_provider_requests = uncached_provider_requests.len(),
         "uncached provider requests"
     );
+    stats.provider_hits = cached_providers.len();
+    stats.provider_misses = uncached_provider_requests.len();
 
-    let mut new_providers: HashMap<(i32, String), Vec<WatchProvider>> = HashMap::new();
+    let mut new_providers: HashMap<(i32, CountryCode), Vec<WatchProvider>> = HashMap::new();
     if !uncached_provider_requests.is_empty() {
-        let items: Vec<AppResult<(i32, String, Vec<WatchProvider>)>> =
-            stream::iter(uncached_provider_requests)
-                .map(|(tmdb_id, country_code)| async move {
-                    let (providers, _link) =
-                        tmdb.get_watch_providers(tmdb_id, &country_code).await?;
-                    Ok((tmdb_id, country_code, providers))
-                })
-                .buffer_unordered(max_concurrent.max(1))
-                .collect()
-                .await;
-
-        for item in items {
-            match item {
-                Ok((tmdb_id, country_code, providers)) => {
-                    debug!(
-                        tmdb_id = tmdb_id,
-                        country = %country_code,
-                        provider_count = providers.len(),
-                        "caching provider data"
-                    );
-                    cache.put_providers(tmdb_id, &country_code, &providers).await?;
-                    new_providers.insert((tmdb_id, country_code), providers);
-                },
-                Err(err) => warn!(error = %err, "failed to fetch watch providers"),
-            }
+        let concurrency = effective_concurrency(
+            options.max_concurrent,
+            tmdb.rps(),
+            uncached_provider_requests.len(),
+        );
+        // Films left out are shown with providers unavailable
+        let tmdb_ids: Vec<i32> = uncached_provider_requests
+            .iter()
+            .map(|(id, _)| *id)
+            .filter(|_| stats.budget.spend())
+            .collect();
+        if tmdb_ids.len() < uncached_provider_requests.len() {
+            warn!(fetching = tmdb_ids.len(), "TMDB call budget reached, skipping provider lookups");
+        }
+        let fetched = tmdb.get_watch_providers_bulk(&tmdb_ids, country, concurrency).await?;
+
+        for (tmdb_id, providers) in fetched {
+            debug!(
+                tmdb_id = tmdb_id,
+                country = %country,
+                provider_count = providers.len(),
+                "caching provider data"
+            );
+            let stored = cache.put_providers(tmdb_id, country, &providers).await;
+            stats.non_fatal("store providers", stored);
+            new_providers.insert((tmdb_id, country), providers);
         }
 
         debug!(new_providers_cached = new_providers.len(), "new providers cached");
     }
 
     for result in &mut results {
-        let key = (result.tmdb_id, country.to_string());
+        let key = (result.tmdb_id, country);
         if let Some(providers) = cached_providers.get(&key) {
             result.streaming_providers = providers.clone();
         } else if let Some(providers) = new_providers.get(&key) {
             result.streaming_providers = providers.clone();
+        } else if uncached_provider_requests.contains(&key) {
+            // Requested but missing from the bulk fetch, which skips films whose lookup failed
+            result.providers_unavailable = true;
         }
     }
 
-    debug!(result_count = results.len(), "completed processing");
-
-    results.sort_by_key(|f| f.theatrical.first().or_else(|| f.streaming.first()).map(|r| r.date));
+    if let Some(other) = options.compare_providers.filter(|other| *other != country) {
+        add_compare_providers(cache, tmdb, &mut results, other, &options, stats).await?;
+    }
 
     Ok(results)
 }
 
+/// Providers in `other` for the already available films, from the provider cache or TMDB.
+/// Upcoming films are left out to keep the extra lookups down.
+async fn add_compare_providers(
+    cache: &CacheManager,
+    tmdb: &impl ReleaseSource,
+    results: &mut [FilmWithReleases],
+    other: CountryCode,
+    options: &ProcessOptions,
+    stats: &mut ProcessStats,
+) -> AppResult<()> {
+    let requests: Vec<(i32, CountryCode)> = results
+        .iter()
+        .filter(|f| f.category == ReleaseCategory::LocalAlreadyAvailable)
+        .map(|f| (f.tmdb_id, other))
+        .collect();
+    let mut providers = cache.get_providers(&requests).await?;
+
+    let tmdb_ids: Vec<i32> = requests
+        .iter()
+        .filter(|req| !providers.contains_key(req))
+        .map(|(tmdb_id, _)| *tmdb_id)
+        .filter(|_| stats.budget.spend())
+        .collect();
+    debug!(
+        country = %other,
+        cached = providers.len(),
+        fetching = tmdb_ids.len(),
+        "comparison providers"
+    );
+    if !tmdb_ids.is_empty() {
+        let concurrency = effective_concurrency(options.max_concurrent, tmdb.rps(), tmdb_ids.len());
+        for (tmdb_id, fetched) in
+            tmdb.get_watch_providers_bulk(&tmdb_ids, other, concurrency).await?
+        {
+            let stored = cache.put_providers(tmdb_id, other, &fetched).await;
+            stats.non_fatal("store providers", stored);
+            providers.insert((tmdb_id, other), fetched);
+        }
+    }
+
+    for result in results {
+        if let Some(found) = providers.remove(&(result.tmdb_id, other)) {
+            result.compare_providers =
+                Some(CountryProviders { country: other.to_string(), providers: found });
+        }
+    }
+
+    Ok(())
+}
+
+/// Release and provider data for a single TMDB film, using the cache and the same fallback
+/// chain as watchlist processing. Title and poster come from the film cache when the film has
+/// been seen on a watchlist before, and otherwise from TMDB, which also fails for unknown IDs.
+pub async fn film_releases(
+    cache: &CacheManager,
+    tmdb: &impl ReleaseSource,
+    tmdb_id: i32,
+    country: CountryCode,
+    options: ProcessOptions,
+) -> AppResult<FilmWithReleases> {
+    let film = match cache.get_film_by_tmdb_id(tmdb_id).await? {
+        Some(cached) => ResolvedFilm {
+            slug: cached.letterboxd_slug,
+            tmdb_id,
+            title: cached.title,
+            year: cached.year.map(|y| y as i16),
+            poster_path: cached.poster_path,
+            match_confidence: MatchConfidence::from_code(cached.match_confidence)
+                .unwrap_or(MatchConfidence::High),
+            first_seen_at: cached.first_seen_at,
+        },
+        None => {
+            let details = tmdb.get_movie_details(tmdb_id).await?;
+            ResolvedFilm {
+                slug: String::new(),
+                tmdb_id,
+                title: details.title,
+                year: details.year,
+                poster_path: details.poster_path,
+                match_confidence: MatchConfidence::High,
+                first_seen_at: None,
+            }
+        },
+    };
+
+    let mut stats = ProcessStats::default();
+    fetch_film_releases(cache, tmdb, vec![film], &HashMap::new(), country, options, &mut stats)
+        .await?
+        .pop()
+        .ok_or_else(|| anyhow::anyhow!("no release data for TMDB film {tmdb_id}").into())
+}
+
+/// Searches TMDB for each variant of a title until one matches confidently, keeping the weak
+/// match from the earliest, most specific query otherwise. Every query spends from the budget, and
+/// `None` means it ran out before the search could finish.
+async fn search_film(
+    tmdb: &impl ReleaseSource,
+    title: &str,
+    year: Option<i16>,
+    budget: &CallBudget,
+) -> AppResult<Option<Option<SearchMatch>>> {
+    let mut best: Option<SearchMatch> = None;
+    for query in search_variants(title) {
+        if !budget.spend() {
+            return Ok(None);
+        }
+        match tmdb.search_movie(&query, year).await? {
+            Some(found) if found.confidence == MatchConfidence::High => {
+                return Ok(Some(Some(found)));
+            },
+            Some(found) if best.is_none() => {
+                best = Some(found);
+            },
+            _ => {},
+        }
+    }
+    Ok(Some(best))
+}
+
+/// Finds TMDB IDs for films missing from the cache. Returns the resolved films, the
+/// `(old, current)` slugs of renamed ones, and whether the deadline cut resolution short.
 async fn resolve_uncached_films(
-    http: &wreq::Client,
-    tmdb: &TmdbClient,
-    films: Vec<WishlistFilm>,
-    max_concurrent: usize,
-) -> AppResult<Vec<FilmCacheData>> {
-    debug!(uncached_count = films.len(), "resolving uncached films");
+    http: &impl FilmResolver,
+    tmdb: &impl ReleaseSource,
+    mut films: Vec<WishlistFilm>,
+    overrides: &HashMap<String, i32>,
+    concurrency: usize,
+    budget: &CallBudget,
+    deadline: Option<Duration>,
+) -> AppResult<(Vec<FilmCacheData>, Vec<(String, String)>, bool)> {
+    let total = films.len();
+    debug!(uncached_count = total, "resolving uncached films");
+
+    // Start with the newest films, which are the most likely to have upcoming releases, so a
+    // deadline cuts off the least relevant ones. Undated films are usually unreleased and go
+    // first; ties keep watchlist order.
+    films.sort_by_key(|f| std::cmp::Reverse(f.year.unwrap_or(i16::MAX)));
+
+    let deadline = async move {
+        match deadline {
+            Some(deadline) => tokio::time::sleep(deadline).await,
+            None => std::future::pending().await,
+        }
+    };
 
-    let items: Vec<AppResult<FilmCacheData>> = stream::iter(films)
+    let items: Vec<AppResult<Option<(FilmCacheData, Option<String>)>>> = stream::iter(films)
         .map(|film| async move {
+            // Each TMDB call below spends from the budget; films it can't cover stay uncached
+            // for the next run
+            if budget.exhausted() {
+                return Ok(None);
+            }
             debug!(slug = %film.letterboxd_slug, "resolving TMDB ID");
 
-            let (resolved_title, resolved_year, mut tmdb_id, mut poster_path) =
-                match scraper::fetch_letterboxd_film_data(http, &film.letterboxd_slug).await {
+            // Operator overrides skip Letterboxd and search entirely
+            if let Some(&id) = overrides.get(&film.letterboxd_slug) {
+                if !budget.spend() {
+                    return Ok(None);
+                }
+                debug!(slug = %film.letterboxd_slug, tmdb_id = id, "using film override");
+                let details = tmdb.get_movie_details(id).await?;
+                let data = FilmCacheData {
+                    slug: film.letterboxd_slug,
+                    tmdb_id: Some(id),
+                    title: details.title,
+                    year: details.year.or(film.year),
+                    poster_path: details.poster_path,
+                    match_confidence: MatchConfidence::High,
+                };
+                return Ok(Some((data, None)));
+            }
+
+            let (resolved_title, resolved_year, mut tmdb_id, mut poster_path, canonical_slug) =
+                match http.fetch_film_data(&film.letterboxd_slug).await {
                     Ok(data) => {
                         if let Some(id) = data.tmdb_id {
                             debug!(slug = %film.letterboxd_slug, tmdb_id = id, "found TMDB ID from Letterboxd");
                         }
-                        (data.title, data.year.or(film.year), data.tmdb_id, None)
+                        (data.title, data.year.or(film.year), data.tmdb_id, None, data.canonical_slug)
                     },
                     Err(err) => {
                         warn!(slug = %film.letterboxd_slug, error = %err, "failed to fetch Letterboxd data, using fallback title");
-                        let fallback_title = film.letterboxd_slug
-                            .split('-')
-                            .map(|word| {
-                                let mut chars = word.chars();
-                                match chars.next() {
-                                    None => String::new(),
-                                    Some(first) => first.to_uppercase().chain(chars.as_str().chars()).collect()
-                                }
-                            })
-                            .collect::<Vec<String>>()
-                            .join(" ");
-                        (fallback_title, film.year, None, None)
+                        (title_from_slug(&film.letterboxd_slug), film.year, None, None, None)
                     },
                 };
 
+            let mut match_confidence = MatchConfidence::High;
+
             if tmdb_id.is_none() {
                 debug!(slug = %film.letterboxd_slug, title = %resolved_title, year = ?resolved_year, "searching TMDB API");
-                if let Some((id, poster)) = tmdb.search_movie(&resolved_title, resolved_year).await? {
-                    debug!(slug = %film.letterboxd_slug, tmdb_id = id, "found TMDB ID via search");
-                    tmdb_id = Some(id);
-                    poster_path = poster;
+                let Some(found) = search_film(tmdb, &resolved_title, resolved_year, budget).await?
+                else {
+                    return Ok(None);
+                };
+                if let Some(found) = found {
+                    debug!(slug = %film.letterboxd_slug, tmdb_id = found.tmdb_id, confidence = ?found.confidence, "found TMDB ID via search");
+                    tmdb_id = Some(found.tmdb_id);
+                    poster_path = found.poster_path;
+                    match_confidence = found.confidence;
                 } else {
                     debug!(slug = %film.letterboxd_slug, "no TMDB ID found");
                 }
             } else if poster_path.is_none() {
-                poster_path = tmdb.get_movie_details(tmdb_id.unwrap()).await.ok().flatten();
+                if !budget.spend() {
+                    return Ok(None);
+                }
+                poster_path = match tmdb.get_movie_details(tmdb_id.unwrap()).await {
+                    Ok(details) => details.poster_path,
+                    Err(err) if err.is_invalid_tmdb_credentials() => return Err(err),
+                    Err(err) => {
+                        warn!(slug = %film.letterboxd_slug, error = %err, "failed to fetch poster");
+                        None
+                    },
+                };
             }
 
-            Ok(FilmCacheData {
-                slug: film.letterboxd_slug,
+            // Renamed films are cached under their current slug, with the old one kept as an
+            // alias so later runs skip the redirect
+            let (slug, previous_slug) = match canonical_slug {
+                Some(canonical) => (canonical, Some(film.letterboxd_slug)),
+                None => (film.letterboxd_slug, None),
+            };
+
+            let data = FilmCacheData {
+                slug,
                 tmdb_id,
                 title: resolved_title,
                 year: resolved_year,
                 poster_path,
-            })
+                match_confidence,
+            };
+            Ok(Some((data, previous_slug)))
         })
-        .buffer_unordered(max_concurrent.max(1))
+        .buffer_unordered(concurrency)
+        .take_until(deadline)
         .collect()
         .await;
 
+    let deadline_reached = items.len() < total;
+    if deadline_reached {
+        warn!(
+            resolved = items.len(),
+            skipped = total - items.len(),
+            "resolve deadline reached, continuing with films resolved so far"
+        );
+    }
+
+    if budget.exhausted() {
+        warn!("TMDB call budget reached, skipping the remaining uncached films");
+    }
+
     let mut results = Vec::new();
+    let mut aliases = Vec::new();
     for item in items {
         match item {
-            Ok(data) => results.push(data),
+            Ok(None) => {},
+            Ok(Some((data, previous_slug))) => {
+                if let Some(previous_slug) = previous_slug {
+                    aliases.push((previous_slug, data.slug.clone()));
+                }
+                results.push(data);
+            },
+            Err(err) if err.is_invalid_tmdb_credentials() => return Err(err),
             Err(err) => warn!(error = %err, "failed to resolve film"),
         }
     }
 
-    Ok(results)
+    Ok((results, aliases, deadline_reached))
+}
+
+/// A readable title for a film whose page couldn't be fetched, e.g. `the-thing` → `The Thing`.
+pub fn title_from_slug(slug: &str) -> String {
+    slug.split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                None => String::new(),
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Films on both watchlists and on only one of them, each in watchlist order.
+pub struct WatchlistComparison {
+    pub shared: Vec<WishlistFilm>,
+    pub only_a: Vec<WishlistFilm>,
+    pub only_b: Vec<WishlistFilm>,
+}
+
+/// Splits two watchlists by Letterboxd slug. Ratings and likes are one member's, so shared films
+/// are left without them.
+pub fn compare_watchlists(a: Vec<WishlistFilm>, b: Vec<WishlistFilm>) -> WatchlistComparison {
+    let slugs_a: HashSet<String> = a.iter().map(|f| f.letterboxd_slug.clone()).collect();
+    let slugs_b: HashSet<String> = b.iter().map(|f| f.letterboxd_slug.clone()).collect();
+
+    let (shared, only_a): (Vec<_>, Vec<_>) =
+        a.into_iter().partition(|f| slugs_b.contains(&f.letterboxd_slug));
+    let shared =
+        shared.into_iter().map(|f| WishlistFilm { rating: None, liked: false, ..f }).collect();
+    let only_b = b.into_iter().filter(|f| !slugs_a.contains(&f.letterboxd_slug)).collect();
+
+    WatchlistComparison { shared, only_a, only_b }
+}
+
+pub fn availability_snapshot(films: &[FilmWithReleases]) -> HashMap<i32, FilmAvailability> {
+    // Films whose provider lookup failed are left out rather than recorded without providers,
+    // so the next check doesn't report their existing providers as new
+    films
+        .iter()
+        .filter(|film| !film.providers_unavailable)
+        .map(|film| {
+            let availability = FilmAvailability {
+                available: matches!(film.category, ReleaseCategory::LocalAlreadyAvailable),
+                provider_ids: film.streaming_providers.iter().map(|p| p.provider_id).collect(),
+            };
+            (film.tmdb_id, availability)
+        })
+        .collect()
+}
+
+/// Films that became available or gained streaming providers since `previous` was taken.
+/// Films missing from `previous` were added to the watchlist since then and are skipped.
+pub fn availability_changes(
+    previous: &HashMap<i32, FilmAvailability>,
+    films: &[FilmWithReleases],
+) -> Vec<AvailabilityChange> {
+    let mut changes = Vec::new();
+
+    for film in films {
+        let Some(before) = previous.get(&film.tmdb_id) else {
+            continue;
+        };
+
+        let became_available =
+            !before.available && matches!(film.category, ReleaseCategory::LocalAlreadyAvailable);
+        let mut new_providers: Vec<String> = film
+            .streaming_providers
+            .iter()
+            .filter(|p| !before.provider_ids.contains(&p.provider_id))
+            .map(|p| p.provider_name.clone())
+            .collect();
+        new_providers.dedup();
+
+        if became_available || !new_providers.is_empty() {
+            changes.push(AvailabilityChange {
+                tmdb_id: film.tmdb_id,
+                title: film.title.clone(),
+                year: film.year,
+                letterboxd_slug: film.letterboxd_slug.clone(),
+                became_available,
+                new_providers,
+            });
+        }
+    }
+
+    changes
+}
+
+/// In-flight request limit for a phase with `pending` uncached lookups.
+///
+/// TMDB calls all wait on the shared `tmdb_rps` limiter, so concurrency beyond roughly two
+/// seconds' worth of requests only queues futures on the limiter. Mostly-cached runs have few
+/// pending lookups and get correspondingly few workers.
+fn effective_concurrency(max_concurrent: usize, tmdb_rps: u32, pending: usize) -> usize {
+    const MAX_IN_FLIGHT: usize = 64;
+
+    let rps_bound = (tmdb_rps as usize).saturating_mul(2);
+    max_concurrent.min(rps_bound).min(pending).clamp(1, MAX_IN_FLIGHT)
 }
 
 fn build_release_requests(
-    films: &[(String, i32, String, Option<i16>, Option<String>)],
-    country: &str,
-) -> Vec<(i32, String)> {
+    films: &[ResolvedFilm],
+    country: CountryCode,
+    extra_countries: &[CountryCode],
+) -> Vec<(i32, CountryCode)> {
+    release_request_keys(films.iter().map(|f| f.tmdb_id), country, extra_countries)
+}
+
+/// The `(tmdb_id, country)` release cache keys needed to render films for `country`,
+/// including fallback regions, and for each of `extra_countries`.
+pub fn release_request_keys(
+    tmdb_ids: impl IntoIterator<Item = i32>,
+    country: CountryCode,
+    extra_countries: &[CountryCode],
+) -> Vec<(i32, CountryCode)> {
     let mut requests = Vec::new();
-    for (_, tmdb_id, _, _, _) in films {
-        requests.push((*tmdb_id, country.to_string()));
-        if country == "NZ" {
-            requests.push((*tmdb_id, "AU".to_string()));
+    for tmdb_id in tmdb_ids {
+        let start = requests.len();
+        requests.push((tmdb_id, country));
+        if country == CountryCode::NZ {
+            requests.push((tmdb_id, CountryCode::AU));
+        }
+        if country != CountryCode::US {
+            requests.push((tmdb_id, CountryCode::US));
         }
-        if country != "US" {
-            requests.push((*tmdb_id, "US".to_string()));
+        for &extra in extra_countries {
+            if !requests[start..].iter().any(|&(_, c)| c == extra) {
+                requests.push((tmdb_id, extra));
+            }
         }
     }
     requests
 }
 
+/// The dates releases must fall between under `release_window_months`, if it is set.
+fn release_window(options: &ProcessOptions) -> Option<(jiff::civil::Date, jiff::civil::Date)> {
+    // Spans this long can't be built, so they count as no window
+    (options.release_window_months > 0)
+        .then(|| jiff::Span::new().try_months(options.release_window_months).ok())
+        .flatten()
+        .map(|span| {
+            (options.today, options.today.checked_add(span).unwrap_or(jiff::civil::Date::MAX))
+        })
+}
+
 fn build_provider_requests(
     films: &[FilmWithReleases],
-    country: &str,
+    country: CountryCode,
     today: &jiff::civil::Date,
-) -> Vec<(i32, String)> {
+    upcoming_providers: bool,
+) -> Vec<(i32, CountryCode)> {
     films
         .iter()
-        .filter(|f| needs_provider_lookup(f, today))
-        .map(|f| (f.tmdb_id, country.to_string()))
+        .filter(|f| {
+            needs_provider_lookup(f, today)
+                || (upcoming_providers && f.streaming.iter().any(|r| r.date > *today))
+        })
+        .map(|f| (f.tmdb_id, country))
         .collect()
 }
 
@@ -368,180 +1275,1066 @@ fn needs_provider_lookup(film: &FilmWithReleases, today: &jiff::civil::Date) ->
     !has_future_streaming
 }
 
+/// Release dates for a film after country fallbacks, see `get_releases_with_fallback_bulk`.
+#[derive(Debug)]
+struct FallbackReleases {
+    theatrical: Vec<ReleaseDate>,
+    streaming: Vec<ReleaseDate>,
+    /// Premiere or physical dates, only given when the chain has no theatrical or streaming dates
+    fallback: Vec<ReleaseDate>,
+    category: ReleaseCategory,
+    /// Only limited theatrical dates, with no wide or streaming release yet
+    limited_only: bool,
+    /// The country whose dates decide the category: the requested one when it has any, else
+    /// the first fallback country that does
+    source: CountryCode,
+}
+
 fn get_releases_with_fallback_bulk(
-    cached_releases: &HashMap<(i32, String), (Vec<ReleaseDate>, Vec<ReleaseDate>)>,
+    cached_releases: &HashMap<(i32, CountryCode), CountryReleases>,
     new_releases: &HashMap<i32, Vec<CountryReleases>>,
     tmdb_id: i32,
-    country: &str,
+    country: CountryCode,
     slug: &str,
-) -> (Vec<ReleaseDate>, Vec<ReleaseDate>, ReleaseCategory) {
-    let (local_theatrical, local_streaming) =
-        get_release_data(cached_releases, new_releases, tmdb_id, country);
+    today: jiff::civil::Date,
+    window: Option<(jiff::civil::Date, jiff::civil::Date)>,
+) -> FallbackReleases {
+    // New Zealand tries Australia before the US; every other country goes straight to the US
+    let mut fallback_chain = vec![country];
+    if country == CountryCode::NZ {
+        fallback_chain.push(CountryCode::AU);
+    }
+    if country != CountryCode::US {
+        fallback_chain.push(CountryCode::US);
+    }
 
-    // Separate upcoming releases from "Already available" releases
-    let (local_upcoming_theatrical, local_already_available_theatrical): (Vec<_>, Vec<_>) =
-        local_theatrical
-            .into_iter()
-            .partition(|r| r.note.as_ref().map_or(true, |n| !n.contains("Already available")));
-    let (local_upcoming_streaming, local_already_available_streaming): (Vec<_>, Vec<_>) =
-        local_streaming
-            .into_iter()
-            .partition(|r| r.note.as_ref().map_or(true, |n| !n.contains("Already available")));
+    // Theatrical and streaming fall back independently, so a card can show local theatrical
+    // dates alongside US streaming dates, each labelled with its source country
+    let mut theatrical: Option<LabelledReleases> = None;
+    let mut streaming: Option<LabelledReleases> = None;
 
-    // Check for recent "Already available" releases first (prioritize over upcoming)
-    if !local_already_available_theatrical.is_empty()
-        || !local_already_available_streaming.is_empty()
-    {
-        let mut all_theatrical = local_already_available_theatrical;
-        let mut all_streaming = local_already_available_streaming;
-        // Mark local releases with country code and include any upcoming releases too
-        for rel in &mut all_theatrical {
-            rel.note = Some(country.to_string());
+    for &source in &fallback_chain {
+        if source != country {
+            debug!(slug = %slug, source = %source, "no local releases for some types, trying fallback");
+        }
+
+        let Some(data) = get_release_data(cached_releases, new_releases, tmdb_id, source) else {
+            continue;
+        };
+
+        if theatrical.is_none() && !data.theatrical.is_empty() {
+            theatrical = Some(LabelledReleases::new(data.theatrical.clone(), source, today));
+        }
+        if streaming.is_none() && !data.streaming.is_empty() {
+            streaming = Some(LabelledReleases::new(data.streaming.clone(), source, today));
         }
-        for rel in &mut all_streaming {
-            rel.note = Some(country.to_string());
+        if theatrical.is_some() && streaming.is_some() {
+            break;
         }
-        all_theatrical.extend(local_upcoming_theatrical);
-        all_streaming.extend(local_upcoming_streaming);
-        return (all_theatrical, all_streaming, ReleaseCategory::LocalAlreadyAvailable);
     }
 
-    // Check for upcoming releases only if no already available releases
-    if !local_upcoming_theatrical.is_empty() || !local_upcoming_streaming.is_empty() {
-        // Mark local releases with country code
-        let mut all_theatrical = local_upcoming_theatrical;
-        let mut all_streaming = local_upcoming_streaming;
-        for rel in &mut all_theatrical {
-            rel.note = Some(country.to_string());
-        }
-        for rel in &mut all_streaming {
-            rel.note = Some(country.to_string());
+    // With no theatrical or streaming dates anywhere in the chain, a premiere or physical
+    // release still beats "no releases"
+    let mut fallback: Option<LabelledReleases> = None;
+    if theatrical.is_none() && streaming.is_none() {
+        fallback = fallback_chain.iter().find_map(|&source| {
+            let data = get_release_data(cached_releases, new_releases, tmdb_id, source)?;
+            (!data.fallback.is_empty()).then(|| {
+                debug!(slug = %slug, source = %source, "using fallback release types");
+                LabelledReleases::new(data.fallback.clone(), source, today)
+            })
+        });
+    }
+
+    // A festival or limited run alone isn't a release most viewers can get to, so it doesn't
+    // make the film available
+    if let Some(limited) = theatrical.as_mut().filter(|l| is_limited_only(&l.releases)) {
+        limited.already_available = false;
+    }
+
+    // Local dates decide the category, so a local cinema date isn't hidden as "already
+    // available" by a film that is already streaming in the US
+    let (category, source) = {
+        let found = [&theatrical, &streaming, &fallback];
+        let has_local = found.into_iter().flatten().any(|l| l.source == country);
+        let source = if has_local {
+            country
+        } else {
+            found.into_iter().flatten().next().map_or(country, |l| l.source)
+        };
+        let mut deciding =
+            found.into_iter().flatten().filter(|l| !has_local || l.source == country);
+
+        // Recent "Already available" releases take priority over upcoming ones
+        let category = if deciding.clone().any(|l| l.already_available) {
+            ReleaseCategory::LocalAlreadyAvailable
+        } else if deciding.any(|l| !l.releases.is_empty()) {
+            ReleaseCategory::LocalUpcoming
+        } else {
+            ReleaseCategory::NoReleases
+        };
+        (category, source)
+    };
+
+    let releases = |l: Option<LabelledReleases>| l.map(|l| l.releases).unwrap_or_default();
+    let (theatrical, streaming, fallback) =
+        (releases(theatrical), releases(streaming), releases(fallback));
+
+    // A window keeps only releases between its dates, so films left with none of those move to
+    // the no-dates section
+    let Some((start, end)) = window else {
+        let limited_only = streaming.is_empty() && is_limited_only(&theatrical);
+        return FallbackReleases {
+            theatrical,
+            streaming,
+            fallback,
+            category,
+            limited_only,
+            source,
+        };
+    };
+    let in_window = |releases: Vec<ReleaseDate>| -> Vec<ReleaseDate> {
+        releases.into_iter().filter(|r| r.date >= start && r.date <= end).collect()
+    };
+    let (theatrical, streaming, fallback) =
+        (in_window(theatrical), in_window(streaming), in_window(fallback));
+    let category = if theatrical.is_empty() && streaming.is_empty() && fallback.is_empty() {
+        ReleaseCategory::NoReleases
+    } else {
+        ReleaseCategory::LocalUpcoming
+    };
+
+    let limited_only = streaming.is_empty() && is_limited_only(&theatrical);
+    FallbackReleases { theatrical, streaming, fallback, category, limited_only, source }
+}
+
+/// Theatrical dates that are all limited runs, with no wide release among them.
+fn is_limited_only(theatrical: &[ReleaseDate]) -> bool {
+    !theatrical.is_empty()
+        && theatrical.iter().all(|r| r.release_type == ReleaseType::TheatricalLimited)
+}
+
+/// When a theatrical-only film will probably stream: `days` after its first upcoming wide (or
+/// else limited) theatrical release.
+fn estimate_streaming(
+    theatrical: &[ReleaseDate],
+    streaming: &[ReleaseDate],
+    today: jiff::civil::Date,
+    days: i64,
+) -> Option<jiff::civil::Date> {
+    if days == 0 || !streaming.is_empty() {
+        return None;
+    }
+
+    let first_upcoming = |release_type: ReleaseType| {
+        theatrical
+            .iter()
+            .filter(|r| r.release_type == release_type && r.date >= today)
+            .map(|r| r.date)
+            .min()
+    };
+    let theatrical_date = first_upcoming(ReleaseType::Theatrical)
+        .or_else(|| first_upcoming(ReleaseType::TheatricalLimited))?;
+
+    theatrical_date.checked_add(jiff::Span::new().days(days)).ok()
+}
+
+/// Orders already available entries first and records the source country on each entry.
+/// Returns whether any entry was already available.
+///
+/// Entries count as already available by date as well as by note: cached dates that were
+/// upcoming when fetched can pass before the cache expires, and keep whatever note they had.
+fn label_releases(
+    releases: Vec<ReleaseDate>,
+    source: CountryCode,
+    today: jiff::civil::Date,
+) -> (Vec<ReleaseDate>, bool) {
+    let (mut already_available, upcoming): (Vec<_>, Vec<_>) = releases
+        .into_iter()
+        .partition(|r| r.date < today || r.note.as_deref() == Some(ALREADY_AVAILABLE_NOTE));
+    let has_already_available = !already_available.is_empty();
+
+    already_available.extend(upcoming);
+    for rel in &mut already_available {
+        rel.source_country = Some(source.to_string());
+    }
+
+    (already_available, has_already_available)
+}
+
+/// One release list picked from the fallback chain, with the country it came from.
+struct LabelledReleases {
+    releases: Vec<ReleaseDate>,
+    source: CountryCode,
+    already_available: bool,
+}
+
+impl LabelledReleases {
+    fn new(releases: Vec<ReleaseDate>, source: CountryCode, today: jiff::civil::Date) -> Self {
+        let (releases, already_available) = label_releases(releases, source, today);
+        Self { releases, source, already_available }
+    }
+}
+
+fn get_release_data<'a>(
+    cached_releases: &'a HashMap<(i32, CountryCode), CountryReleases>,
+    new_releases: &'a HashMap<i32, Vec<CountryReleases>>,
+    tmdb_id: i32,
+    country: CountryCode,
+) -> Option<&'a CountryReleases> {
+    cached_releases
+        .get(&(tmdb_id, country))
+        .or_else(|| new_releases.get(&tmdb_id)?.iter().find(|c| country == c.country))
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::civil::{Date, date};
+
+    use super::*;
+    use crate::{
+        models::{ProviderType, ReleaseDatesResult},
+        scraper::LetterboxdFilmData,
+        tmdb::MovieSummary,
+    };
+
+    const TODAY: Date = date(2025, 6, 1);
+
+    fn release(date: Date, release_type: ReleaseType) -> ReleaseDate {
+        ReleaseDate { date, release_type, note: None, raw_note: None, source_country: None }
+    }
+
+    fn already_available(date: Date, release_type: ReleaseType) -> ReleaseDate {
+        ReleaseDate {
+            note: Some(ALREADY_AVAILABLE_NOTE.to_string()),
+            ..release(date, release_type)
         }
-        return (all_theatrical, all_streaming, ReleaseCategory::LocalUpcoming);
     }
 
-    if country == "US" {
-        return (vec![], vec![], ReleaseCategory::NoReleases);
+    fn country(
+        code: &str,
+        theatrical: Vec<ReleaseDate>,
+        streaming: Vec<ReleaseDate>,
+        fallback: Vec<ReleaseDate>,
+    ) -> CountryReleases {
+        CountryReleases { country: code.to_string(), theatrical, streaming, fallback }
     }
 
-    // Special logic for New Zealand: try Australia first, then US
-    if country == "NZ" {
-        debug!(slug = %slug, "no NZ releases found, trying Australia");
+    fn resolve(countries: Vec<CountryReleases>, requested: CountryCode) -> FallbackReleases {
+        let cached = countries
+            .into_iter()
+            .map(|c| ((1, CountryCode::parse(&c.country).unwrap()), c))
+            .collect();
+        get_releases_with_fallback_bulk(&cached, &HashMap::new(), 1, requested, "film", TODAY, None)
+    }
 
-        let (au_theatrical, au_streaming) =
-            get_release_data(cached_releases, new_releases, tmdb_id, "AU");
+    #[test]
+    fn source_is_the_country_whose_dates_decide() {
+        let upcoming = |t| release(date(2025, 9, 1), t);
+        let us_only = resolve(
+            vec![country("US", vec![], vec![upcoming(ReleaseType::Digital)], vec![])],
+            CountryCode::NZ,
+        );
+        let mixed = resolve(
+            vec![
+                country("NZ", vec![upcoming(ReleaseType::Theatrical)], vec![], vec![]),
+                country("AU", vec![], vec![upcoming(ReleaseType::Digital)], vec![]),
+            ],
+            CountryCode::NZ,
+        );
+        let nothing = resolve(vec![], CountryCode::NZ);
 
-        if !au_theatrical.is_empty() || !au_streaming.is_empty() {
-            // Separate AU releases into upcoming vs already available FIRST
-            let (mut au_upcoming_theatrical, mut au_already_available_theatrical): (
-                Vec<_>,
-                Vec<_>,
-            ) = au_theatrical
-                .into_iter()
-                .partition(|r| r.note.as_ref().map_or(true, |n| !n.contains("Already available")));
-            let (mut au_upcoming_streaming, mut au_already_available_streaming): (Vec<_>, Vec<_>) =
-                au_streaming.into_iter().partition(|r| {
-                    r.note.as_ref().map_or(true, |n| !n.contains("Already available"))
-                });
+        assert_eq!(us_only.source, CountryCode::US);
+        assert_eq!(mixed.source, CountryCode::NZ);
+        assert_eq!(nothing.source, CountryCode::NZ);
+    }
 
-            // Then mark with country code
-            for rel in &mut au_upcoming_theatrical {
-                rel.note = Some("AU".to_string());
-            }
-            for rel in &mut au_already_available_theatrical {
-                rel.note = Some("AU".to_string());
-            }
-            for rel in &mut au_upcoming_streaming {
-                rel.note = Some("AU".to_string());
-            }
-            for rel in &mut au_already_available_streaming {
-                rel.note = Some("AU".to_string());
-            }
+    #[test]
+    fn premiere_only_film_has_releases() {
+        let premiere = release(date(2025, 9, 1), ReleaseType::Premiere);
+        let result = resolve(vec![country("NZ", vec![], vec![], vec![premiere])], CountryCode::NZ);
 
-            // Put AU releases in appropriate local sections (prioritize already available)
-            if !au_already_available_theatrical.is_empty()
-                || !au_already_available_streaming.is_empty()
-            {
-                let mut all_theatrical = au_already_available_theatrical;
-                let mut all_streaming = au_already_available_streaming;
-                all_theatrical.extend(au_upcoming_theatrical);
-                all_streaming.extend(au_upcoming_streaming);
-                return (all_theatrical, all_streaming, ReleaseCategory::LocalAlreadyAvailable);
-            }
+        assert_eq!(result.category, ReleaseCategory::LocalUpcoming);
+        assert!(result.theatrical.is_empty() && result.streaming.is_empty());
+        assert_eq!(result.fallback.len(), 1);
+        assert_eq!(result.fallback[0].release_type, ReleaseType::Premiere);
+        assert_eq!(result.fallback[0].source_country.as_deref(), Some("NZ"));
+    }
 
-            if !au_upcoming_theatrical.is_empty() || !au_upcoming_streaming.is_empty() {
-                return (
-                    au_upcoming_theatrical,
-                    au_upcoming_streaming,
-                    ReleaseCategory::LocalUpcoming,
-                );
-            }
+    #[test]
+    fn physical_only_film_is_already_available() {
+        let physical = already_available(date(2025, 3, 1), ReleaseType::Physical);
+        let result = resolve(vec![country("US", vec![], vec![], vec![physical])], CountryCode::NZ);
+
+        assert_eq!(result.category, ReleaseCategory::LocalAlreadyAvailable);
+        assert_eq!(result.fallback.len(), 1);
+        assert_eq!(result.fallback[0].source_country.as_deref(), Some("US"));
+    }
+
+    #[test]
+    fn past_digital_with_future_physical_ignores_the_physical_date() {
+        let digital = already_available(date(2025, 3, 1), ReleaseType::Digital);
+        let physical = release(date(2025, 8, 1), ReleaseType::Physical);
+        let result =
+            resolve(vec![country("NZ", vec![], vec![digital], vec![physical])], CountryCode::NZ);
+
+        assert_eq!(result.category, ReleaseCategory::LocalAlreadyAvailable);
+        assert_eq!(result.streaming.len(), 1);
+        assert_eq!(result.streaming[0].release_type, ReleaseType::Digital);
+        assert!(result.fallback.is_empty());
+    }
+
+    #[test]
+    fn local_upcoming_dates_decide_over_fallback_streaming() {
+        let theatrical = release(date(2025, 8, 1), ReleaseType::Theatrical);
+        let digital = already_available(date(2025, 3, 1), ReleaseType::Digital);
+        let result = resolve(
+            vec![
+                country("NZ", vec![theatrical], vec![], vec![]),
+                country("US", vec![], vec![digital], vec![]),
+            ],
+            CountryCode::NZ,
+        );
+
+        assert_eq!(result.category, ReleaseCategory::LocalUpcoming);
+        assert_eq!(result.streaming.len(), 1);
+        assert_eq!(result.streaming[0].source_country.as_deref(), Some("US"));
+    }
+
+    #[test]
+    fn fallback_sources_decide_without_local_dates() {
+        let digital = already_available(date(2025, 3, 1), ReleaseType::Digital);
+        let theatrical = release(date(2025, 8, 1), ReleaseType::Theatrical);
+        let result = resolve(
+            vec![
+                country("AU", vec![theatrical], vec![], vec![]),
+                country("US", vec![], vec![digital], vec![]),
+            ],
+            CountryCode::NZ,
+        );
+
+        assert_eq!(result.category, ReleaseCategory::LocalAlreadyAvailable);
+    }
+
+    #[test]
+    fn past_limited_run_alone_is_not_available() {
+        let limited = already_available(date(2025, 2, 1), ReleaseType::TheatricalLimited);
+        let result = resolve(vec![country("NZ", vec![limited], vec![], vec![])], CountryCode::NZ);
+
+        assert_eq!(result.category, ReleaseCategory::LocalUpcoming);
+        assert!(result.limited_only);
+    }
+
+    #[test]
+    fn wide_release_is_not_limited_only() {
+        let limited = release(date(2025, 2, 1), ReleaseType::TheatricalLimited);
+        let wide = already_available(date(2025, 3, 1), ReleaseType::Theatrical);
+        let result =
+            resolve(vec![country("NZ", vec![limited, wide], vec![], vec![])], CountryCode::NZ);
+
+        assert_eq!(result.category, ReleaseCategory::LocalAlreadyAvailable);
+        assert!(!result.limited_only);
+    }
+
+    #[test]
+    fn label_releases_puts_past_dates_first_without_notes() {
+        let releases = vec![
+            release(date(2025, 8, 1), ReleaseType::Theatrical),
+            release(date(2025, 4, 1), ReleaseType::Theatrical),
+            already_available(date(2025, 5, 1), ReleaseType::Theatrical),
+        ];
+        let (labelled, has_already_available) = label_releases(releases, CountryCode::AU, TODAY);
+
+        assert!(has_already_available);
+        let dates: Vec<Date> = labelled.iter().map(|r| r.date).collect();
+        assert_eq!(dates, vec![date(2025, 4, 1), date(2025, 5, 1), date(2025, 8, 1)]);
+        assert!(labelled.iter().all(|r| r.source_country.as_deref() == Some("AU")));
+        // Labelling only sets the source, so notes are never rewritten
+        assert_eq!(labelled[0].note, None);
+        assert_eq!(labelled[2].note, None);
+    }
+
+    #[test]
+    fn label_releases_with_only_upcoming_dates_is_not_available() {
+        let releases = vec![
+            release(TODAY, ReleaseType::Digital),
+            release(date(2025, 9, 1), ReleaseType::Digital),
+        ];
+        let (labelled, has_already_available) = label_releases(releases, CountryCode::NZ, TODAY);
+
+        assert!(!has_already_available);
+        assert_eq!(labelled.len(), 2);
+    }
+
+    #[test]
+    fn label_releases_trusts_the_already_available_note_for_future_dates() {
+        let releases = vec![already_available(date(2025, 7, 1), ReleaseType::Digital)];
+        let (_, has_already_available) = label_releases(releases, CountryCode::NZ, TODAY);
+
+        assert!(has_already_available);
+    }
+
+    #[test]
+    fn no_dates_anywhere_is_no_releases() {
+        let result = resolve(vec![country("NZ", vec![], vec![], vec![])], CountryCode::NZ);
+
+        assert_eq!(result.category, ReleaseCategory::NoReleases);
+    }
+
+    fn cached_film(tmdb_id: Option<i32>) -> film_cache::Model {
+        film_cache::Model {
+            letterboxd_slug: "film".to_string(),
+            tmdb_id,
+            title: "Film".to_string(),
+            year: None,
+            poster_path: None,
+            updated_at: 0,
+            match_confidence: 0,
+            first_seen_at: None,
         }
     }
 
-    // Fall back to US for all non-US countries
-    debug!(slug = %slug, "no local releases found, trying US");
+    #[test]
+    fn unmatched_films_are_only_skipped_with_match_thresholds() {
+        let unmatched = cached_film(None);
+        assert!(!is_resolved(Some(&unmatched), false));
+        assert!(is_resolved(Some(&unmatched), true));
 
-    let (us_theatrical, us_streaming) =
-        get_release_data(cached_releases, new_releases, tmdb_id, "US");
+        let matched = cached_film(Some(550));
+        assert!(is_resolved(Some(&matched), false));
+        assert!(!is_resolved(None, true));
+    }
 
-    if !us_theatrical.is_empty() || !us_streaming.is_empty() {
-        let (mut us_upcoming_theatrical, mut us_already_available_theatrical): (Vec<_>, Vec<_>) =
-            us_theatrical
-                .into_iter()
-                .partition(|r| r.note.as_ref().map_or(true, |n| !n.contains("Already available")));
-        let (mut us_upcoming_streaming, mut us_already_available_streaming): (Vec<_>, Vec<_>) =
-            us_streaming
+    fn film(tmdb_id: i32, streaming: Vec<ReleaseDate>) -> FilmWithReleases {
+        FilmWithReleases {
+            title: "Film".to_string(),
+            year: None,
+            tmdb_id,
+            letterboxd_slug: format!("film-{tmdb_id}"),
+            poster_path: None,
+            theatrical: vec![],
+            streaming,
+            fallback_releases: vec![],
+            category: ReleaseCategory::LocalUpcoming,
+            limited_only: false,
+            streaming_providers: vec![],
+            providers_unavailable: false,
+            match_confidence: MatchConfidence::High,
+            date_changes: vec![],
+            rating: None,
+            liked: false,
+            released_regions: vec![],
+            releases_cached_at: None,
+            other_countries: vec![],
+            estimated_streaming: None,
+            is_new: false,
+            compare_providers: None,
+        }
+    }
+
+    #[test]
+    fn upcoming_streaming_only_gets_providers_when_asked() {
+        let films = [
+            film(1, vec![release(date(2025, 3, 1), ReleaseType::Digital)]),
+            film(2, vec![release(date(2025, 9, 1), ReleaseType::Digital)]),
+            film(3, vec![]),
+        ];
+        let ids = |upcoming| -> Vec<i32> {
+            build_provider_requests(&films, CountryCode::NZ, &TODAY, upcoming)
                 .into_iter()
-                .partition(|r| r.note.as_ref().map_or(true, |n| !n.contains("Already available")));
+                .map(|(id, _)| id)
+                .collect()
+        };
+
+        assert_eq!(ids(false), vec![1, 3]);
+        assert_eq!(ids(true), vec![1, 2, 3]);
+    }
+
+    fn wishlist_film(slug: &str) -> WishlistFilm {
+        WishlistFilm { letterboxd_slug: slug.to_string(), year: None, rating: None, liked: false }
+    }
+
+    /// Letterboxd film pages, with the TMDB ID each slug's page links to and its year when it
+    /// isn't 2025. Stalled pages never load.
+    #[derive(Default)]
+    struct FakeLetterboxd {
+        tmdb_ids: HashMap<String, i32>,
+        years: HashMap<String, i16>,
+        stalled: HashSet<String>,
+        fetched: std::sync::Mutex<Vec<String>>,
+    }
 
-        for rel in &mut us_upcoming_theatrical {
-            rel.note = Some("US".to_string());
+    impl FilmResolver for FakeLetterboxd {
+        async fn fetch_film_data(&self, slug: &str) -> AppResult<LetterboxdFilmData> {
+            self.fetched.lock().unwrap().push(slug.to_string());
+            if self.stalled.contains(slug) {
+                std::future::pending::<()>().await;
+            }
+            Ok(LetterboxdFilmData {
+                title: title_from_slug(slug),
+                year: Some(self.years.get(slug).copied().unwrap_or(2025)),
+                tmdb_id: self.tmdb_ids.get(slug).copied(),
+                canonical_slug: None,
+            })
         }
-        for rel in &mut us_already_available_theatrical {
-            rel.note = Some("US".to_string());
+    }
+
+    /// TMDB release dates per film and search matches per query, with no watch providers.
+    /// Unknown films 404 like TMDB does.
+    #[derive(Default)]
+    struct FakeTmdb {
+        releases: HashMap<i32, Vec<CountryReleases>>,
+        unknown: HashSet<i32>,
+        matches: HashMap<String, (i32, MatchConfidence)>,
+        searches: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl ReleaseSource for FakeTmdb {
+        fn rps(&self) -> u32 {
+            10
         }
-        for rel in &mut us_upcoming_streaming {
-            rel.note = Some("US".to_string());
+
+        async fn search_movie(
+            &self,
+            title: &str,
+            _: Option<i16>,
+        ) -> AppResult<Option<SearchMatch>> {
+            self.searches.lock().unwrap().push(title.to_string());
+            Ok(self.matches.get(title).map(|&(tmdb_id, confidence)| SearchMatch {
+                tmdb_id,
+                poster_path: None,
+                confidence,
+            }))
         }
-        for rel in &mut us_already_available_streaming {
-            rel.note = Some("US".to_string());
+
+        async fn get_movie_details(&self, tmdb_id: i32) -> AppResult<MovieSummary> {
+            if self.unknown.contains(&tmdb_id) {
+                return Err(anyhow::anyhow!("HTTP status client error (404 Not Found)").into());
+            }
+            Ok(MovieSummary {
+                title: format!("Film {tmdb_id}"),
+                year: Some(2025),
+                poster_path: None,
+            })
         }
 
-        if !us_already_available_theatrical.is_empty() || !us_already_available_streaming.is_empty()
-        {
-            let mut all_theatrical = us_already_available_theatrical;
-            let mut all_streaming = us_already_available_streaming;
-            all_theatrical.extend(us_upcoming_theatrical);
-            all_streaming.extend(us_upcoming_streaming);
-            return (all_theatrical, all_streaming, ReleaseCategory::LocalAlreadyAvailable);
+        async fn get_release_dates(
+            &self,
+            tmdb_id: i32,
+            country: CountryCode,
+        ) -> AppResult<ReleaseDatesResult> {
+            let all_countries = self.releases.get(&tmdb_id).cloned().unwrap_or_default();
+            let requested_country =
+                all_countries.iter().find(|c| country == c.country).cloned().unwrap_or_else(|| {
+                    CountryReleases {
+                        country: country.to_string(),
+                        theatrical: vec![],
+                        streaming: vec![],
+                        fallback: vec![],
+                    }
+                });
+            Ok(ReleaseDatesResult {
+                requested_country,
+                confirmed_empty: all_countries.is_empty(),
+                all_countries,
+                released_countries: vec![],
+            })
         }
 
-        if !us_upcoming_theatrical.is_empty() || !us_upcoming_streaming.is_empty() {
-            return (us_upcoming_theatrical, us_upcoming_streaming, ReleaseCategory::LocalUpcoming);
+        async fn get_watch_providers(
+            &self,
+            _: i32,
+            _: CountryCode,
+        ) -> AppResult<(Vec<WatchProvider>, Option<String>)> {
+            Ok((vec![], None))
+        }
+
+        async fn get_watch_providers_bulk(
+            &self,
+            tmdb_ids: &[i32],
+            _: CountryCode,
+            _: usize,
+        ) -> AppResult<HashMap<i32, Vec<WatchProvider>>> {
+            Ok(tmdb_ids.iter().map(|&id| (id, vec![])).collect())
         }
     }
 
-    (vec![], vec![], ReleaseCategory::NoReleases)
-}
+    fn test_options() -> ProcessOptions {
+        ProcessOptions {
+            max_concurrent: 2,
+            letterboxd_max_concurrent: 2,
+            upcoming_providers: false,
+            resolve_deadline: None,
+            today: TODAY,
+            extra_countries: vec![],
+            streaming_estimate_days: 0,
+            release_window_months: 0,
+            since: None,
+            new_only: false,
+            tmdb_call_budget: None,
+            compare_providers: None,
+            skip_unmatched: false,
+        }
+    }
 
-fn get_release_data(
-    cached_releases: &HashMap<(i32, String), (Vec<ReleaseDate>, Vec<ReleaseDate>)>,
-    new_releases: &HashMap<i32, Vec<CountryReleases>>,
-    tmdb_id: i32,
-    country: &str,
-) -> (Vec<ReleaseDate>, Vec<ReleaseDate>) {
-    // Try cached data first
-    if let Some((theatrical, streaming)) = cached_releases.get(&(tmdb_id, country.to_string())) {
-        return (theatrical.clone(), streaming.clone());
+    /// Runs the whole pipeline for `slug → tmdb_id` films against the fakes, returning the
+    /// results keyed by TMDB ID.
+    async fn process_fakes(
+        films: &[(&str, i32)],
+        releases: Vec<(i32, Vec<CountryReleases>)>,
+        country: CountryCode,
+        options: ProcessOptions,
+    ) -> HashMap<i32, FilmWithReleases> {
+        let letterboxd = FakeLetterboxd {
+            tmdb_ids: films.iter().map(|&(slug, id)| (slug.to_string(), id)).collect(),
+            ..Default::default()
+        };
+        let tmdb = FakeTmdb { releases: releases.into_iter().collect(), ..Default::default() };
+        let watchlist = films.iter().map(|&(slug, _)| wishlist_film(slug)).collect();
+
+        let outcome = process(
+            &letterboxd,
+            &CacheManager::for_tests().await,
+            &tmdb,
+            watchlist,
+            country,
+            options,
+            false,
+        )
+        .await
+        .unwrap();
+        let ProcessOutcome::Films(results) = outcome else {
+            panic!("expected a complete run");
+        };
+        results.into_iter().map(|f| (f.tmdb_id, f)).collect()
     }
 
-    // Try new data
-    if let Some(countries) = new_releases.get(&tmdb_id) {
-        if let Some(country_data) = countries.iter().find(|c| c.country == country) {
-            return (country_data.theatrical.clone(), country_data.streaming.clone());
+    #[tokio::test]
+    async fn new_zealand_falls_back_to_australia_then_the_us() {
+        let results = process_fakes(
+            &[("local", 1), ("australian", 2), ("american", 3)],
+            vec![
+                (
+                    1,
+                    vec![
+                        country(
+                            "NZ",
+                            vec![release(date(2025, 7, 1), ReleaseType::Theatrical)],
+                            vec![],
+                            vec![],
+                        ),
+                        country(
+                            "AU",
+                            vec![release(date(2025, 6, 20), ReleaseType::Theatrical)],
+                            vec![],
+                            vec![],
+                        ),
+                    ],
+                ),
+                (
+                    2,
+                    vec![
+                        country(
+                            "AU",
+                            vec![release(date(2025, 8, 1), ReleaseType::Theatrical)],
+                            vec![],
+                            vec![],
+                        ),
+                        country(
+                            "US",
+                            vec![release(date(2025, 7, 15), ReleaseType::Theatrical)],
+                            vec![],
+                            vec![],
+                        ),
+                    ],
+                ),
+                (
+                    3,
+                    vec![country(
+                        "US",
+                        vec![],
+                        vec![release(date(2025, 9, 1), ReleaseType::Digital)],
+                        vec![],
+                    )],
+                ),
+            ],
+            CountryCode::NZ,
+            test_options(),
+        )
+        .await;
+
+        let source = |id: i32| -> Option<String> {
+            results[&id].first_release().and_then(|r| r.source_country.clone())
+        };
+        assert_eq!(source(1).as_deref(), Some("NZ"));
+        assert_eq!(source(2).as_deref(), Some("AU"));
+        assert_eq!(source(3).as_deref(), Some("US"));
+        assert_eq!(results[&2].theatrical[0].date, date(2025, 8, 1));
+        assert!(results.values().all(|f| f.category == ReleaseCategory::LocalUpcoming));
+    }
+
+    #[tokio::test]
+    async fn already_available_films_are_categorised_and_windowed() {
+        let releases = || {
+            vec![
+                (
+                    1,
+                    vec![country(
+                        "US",
+                        vec![],
+                        vec![already_available(date(2025, 4, 1), ReleaseType::Digital)],
+                        vec![],
+                    )],
+                ),
+                (
+                    2,
+                    vec![country(
+                        "NZ",
+                        vec![release(date(2025, 7, 1), ReleaseType::Theatrical)],
+                        vec![],
+                        vec![],
+                    )],
+                ),
+            ]
+        };
+        let films = [("streaming", 1), ("upcoming", 2)];
+
+        let results = process_fakes(&films, releases(), CountryCode::NZ, test_options()).await;
+        assert_eq!(results[&1].category, ReleaseCategory::LocalAlreadyAvailable);
+        assert_eq!(results[&2].category, ReleaseCategory::LocalUpcoming);
+
+        // A release window only keeps dates from today on, so the streaming film has none left
+        let options = ProcessOptions { release_window_months: 3, ..test_options() };
+        let results = process_fakes(&films, releases(), CountryCode::NZ, options).await;
+        assert_eq!(results[&1].category, ReleaseCategory::NoReleases);
+        assert_eq!(results[&2].category, ReleaseCategory::LocalUpcoming);
+    }
+
+    #[tokio::test]
+    async fn renamed_slugs_resolve_through_aliases() {
+        let cache = CacheManager::for_tests().await;
+        cache
+            .put_slug_aliases(&[("old-name".to_string(), "new-name-2025".to_string())])
+            .await
+            .unwrap();
+
+        let mut films = vec![wishlist_film("old-name"), wishlist_film("unrelated")];
+        apply_slug_aliases(&cache, &mut films).await.unwrap();
+
+        assert_eq!(films[0].letterboxd_slug, "new-name-2025");
+        assert_eq!(films[1].letterboxd_slug, "unrelated");
+    }
+
+    fn fake_matches(matches: &[(&str, i32, MatchConfidence)]) -> FakeTmdb {
+        FakeTmdb {
+            matches: matches
+                .iter()
+                .map(|&(query, id, confidence)| (query.to_string(), (id, confidence)))
+                .collect(),
+            ..Default::default()
         }
     }
 
-    (vec![], vec![])
+    #[tokio::test]
+    async fn search_keeps_the_weak_match_from_the_most_specific_query() {
+        let tmdb = fake_matches(&[
+            ("Léon: The Professional", 1, MatchConfidence::Low),
+            ("Leon: The Professional", 2, MatchConfidence::Low),
+            ("Leon", 3, MatchConfidence::Low),
+        ]);
+
+        let found = search_film(&tmdb, "Léon: The Professional", None, &CallBudget::default())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(found.tmdb_id, 1);
+        assert_eq!(tmdb.searches.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn confident_search_match_stops_retrying() {
+        let tmdb = fake_matches(&[
+            ("Thing, The", 1, MatchConfidence::Low),
+            ("Thing", 2, MatchConfidence::High),
+        ]);
+        let budget = CallBudget::default();
+
+        let found = search_film(&tmdb, "Thing, The", Some(1982), &budget).await.unwrap().unwrap();
+
+        assert_eq!(found.map(|m| m.tmdb_id), Some(2));
+        assert_eq!(budget.used(), 2);
+    }
+
+    #[tokio::test]
+    async fn each_search_retry_spends_the_budget() {
+        let tmdb = FakeTmdb::default();
+        let budget = CallBudget::new(Some(2));
+
+        let found = search_film(&tmdb, "Amélie: A Story", None, &budget).await.unwrap();
+
+        // The third variant didn't fit, so the search is left unsettled for the next run
+        assert!(found.is_none());
+        assert_eq!(*tmdb.searches.lock().unwrap(), ["Amélie: A Story", "Amelie: A Story"]);
+        assert!(budget.exhausted());
+    }
+
+    #[tokio::test]
+    async fn budget_counts_calls_rather_than_films() {
+        let letterboxd = FakeLetterboxd {
+            tmdb_ids: HashMap::from([("linked".to_string(), 1)]),
+            ..Default::default()
+        };
+        let tmdb = FakeTmdb::default();
+        let films = vec![wishlist_film("linked"), wishlist_film("missing-film")];
+        let budget = CallBudget::new(Some(10));
+
+        let (resolved, _, _) =
+            resolve_uncached_films(&letterboxd, &tmdb, films, &HashMap::new(), 1, &budget, None)
+                .await
+                .unwrap();
+
+        assert_eq!(resolved.len(), 2);
+        // One details call for the linked film, and one search per title variant for the other
+        assert_eq!(budget.used(), 1 + tmdb.searches.lock().unwrap().len());
+        assert!(!budget.exhausted());
+    }
+
+    #[tokio::test]
+    async fn release_budget_goes_to_the_first_films_in_watchlist_order() {
+        let ids = [5, 3, 8, 1, 9];
+        let tmdb = FakeTmdb {
+            releases: ids
+                .iter()
+                .map(|&id| {
+                    let upcoming = release(date(2025, 9, 1), ReleaseType::Theatrical);
+                    (id, vec![country("US", vec![upcoming], vec![], vec![])])
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let films = ids
+            .iter()
+            .map(|&tmdb_id| ResolvedFilm {
+                slug: format!("film-{tmdb_id}"),
+                tmdb_id,
+                title: format!("Film {tmdb_id}"),
+                year: None,
+                poster_path: None,
+                match_confidence: MatchConfidence::High,
+                first_seen_at: None,
+            })
+            .collect();
+        let mut stats = ProcessStats { budget: CallBudget::new(Some(3)), ..Default::default() };
+
+        let results = fetch_film_releases(
+            &CacheManager::for_tests().await,
+            &tmdb,
+            films,
+            &HashMap::new(),
+            CountryCode::US,
+            test_options(),
+            &mut stats,
+        )
+        .await
+        .unwrap();
+
+        let dated: Vec<i32> =
+            results.iter().filter(|f| !f.theatrical.is_empty()).map(|f| f.tmdb_id).collect();
+        assert_eq!(dated, [5, 3, 8]);
+    }
+
+    #[tokio::test]
+    async fn match_override_skips_letterboxd_and_search() {
+        let letterboxd = FakeLetterboxd {
+            tmdb_ids: HashMap::from([("mismatched".to_string(), 1)]),
+            ..Default::default()
+        };
+        let tmdb = fake_matches(&[("Mismatched", 2, MatchConfidence::High)]);
+        let overrides = HashMap::from([("mismatched".to_string(), 42)]);
+
+        let (resolved, _, _) = resolve_uncached_films(
+            &letterboxd,
+            &tmdb,
+            vec![wishlist_film("mismatched")],
+            &overrides,
+            1,
+            &CallBudget::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].tmdb_id, Some(42));
+        assert_eq!(resolved[0].match_confidence, MatchConfidence::High);
+        assert!(letterboxd.fetched.lock().unwrap().is_empty());
+        assert!(tmdb.searches.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn undated_watchlist_films_are_cut_by_their_film_page_year() {
+        let letterboxd = FakeLetterboxd {
+            tmdb_ids: HashMap::from([
+                ("old-classic".to_string(), 1),
+                ("new-release".to_string(), 2),
+                ("dated-classic".to_string(), 3),
+            ]),
+            years: HashMap::from([("old-classic".to_string(), 1990)]),
+            ..Default::default()
+        };
+        let watchlist = vec![
+            wishlist_film("old-classic"),
+            wishlist_film("new-release"),
+            WishlistFilm { year: Some(1985), ..wishlist_film("dated-classic") },
+        ];
+
+        let outcome = process(
+            &letterboxd,
+            &CacheManager::for_tests().await,
+            &FakeTmdb::default(),
+            watchlist,
+            CountryCode::NZ,
+            test_options(),
+            false,
+        )
+        .await
+        .unwrap();
+
+        let ProcessOutcome::Films(results) = outcome else {
+            panic!("expected a complete run");
+        };
+        let ids: Vec<i32> = results.iter().map(|f| f.tmdb_id).collect();
+        assert_eq!(ids, vec![2]);
+        // Only films without a watchlist year need their page to decide
+        let mut fetched = letterboxd.fetched.lock().unwrap().clone();
+        fetched.sort();
+        assert_eq!(fetched, ["new-release", "old-classic"]);
+    }
+
+    #[test]
+    fn mostly_cached_runs_use_few_workers() {
+        // Two films left to fetch out of a large, mostly cached watchlist
+        assert_eq!(effective_concurrency(20, 40, 2), 2);
+        assert_eq!(effective_concurrency(20, 40, 0), 1);
+    }
+
+    #[test]
+    fn concurrency_is_bounded_by_rps_and_the_hard_cap() {
+        assert_eq!(effective_concurrency(20, 40, 500), 20);
+        assert_eq!(effective_concurrency(50, 4, 500), 8);
+        assert_eq!(effective_concurrency(1_000, 1_000, 5_000), 64);
+        assert_eq!(effective_concurrency(0, 40, 500), 1);
+    }
+
+    #[tokio::test]
+    async fn films_cut_by_the_resolve_deadline_make_a_partial_run() {
+        let letterboxd = FakeLetterboxd {
+            tmdb_ids: HashMap::from([("quick-film".to_string(), 1), ("slow-film".to_string(), 2)]),
+            stalled: HashSet::from(["slow-film".to_string()]),
+            ..Default::default()
+        };
+        let options =
+            ProcessOptions { resolve_deadline: Some(Duration::from_millis(50)), ..test_options() };
+
+        let outcome = process(
+            &letterboxd,
+            &CacheManager::for_tests().await,
+            &FakeTmdb::default(),
+            vec![wishlist_film("quick-film"), wishlist_film("slow-film")],
+            CountryCode::NZ,
+            options,
+            false,
+        )
+        .await
+        .unwrap();
+
+        let ProcessOutcome::Partial(films) = outcome else {
+            panic!("expected a partial run");
+        };
+        assert_eq!(films.iter().map(|f| f.tmdb_id).collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn dry_run_counts_the_provider_lookups_a_real_run_makes() {
+        let cache = CacheManager::for_tests().await;
+        let letterboxd = FakeLetterboxd {
+            tmdb_ids: HashMap::from([("out".to_string(), 1), ("streaming-soon".to_string(), 2)]),
+            ..Default::default()
+        };
+        let tmdb = FakeTmdb {
+            releases: HashMap::from([
+                (
+                    1,
+                    vec![country(
+                        "NZ",
+                        vec![already_available(date(2025, 5, 1), ReleaseType::Theatrical)],
+                        vec![],
+                        vec![],
+                    )],
+                ),
+                (
+                    2,
+                    vec![country(
+                        "NZ",
+                        vec![],
+                        vec![release(date(2025, 8, 1), ReleaseType::Digital)],
+                        vec![],
+                    )],
+                ),
+            ]),
+            ..Default::default()
+        };
+        let watchlist = || vec![wishlist_film("out"), wishlist_film("streaming-soon")];
+        let plan = |options| async {
+            let outcome =
+                process(&letterboxd, &cache, &tmdb, watchlist(), CountryCode::NZ, options, true)
+                    .await
+                    .unwrap();
+            let ProcessOutcome::DryRun(summary) = outcome else {
+                panic!("expected a dry run");
+            };
+            summary
+        };
+
+        process(&letterboxd, &cache, &tmdb, watchlist(), CountryCode::NZ, test_options(), false)
+            .await
+            .unwrap();
+        let netflix = WatchProvider {
+            provider_id: 8,
+            provider_name: "Netflix".to_string(),
+            logo_path: String::new(),
+            link: None,
+            provider_type: ProviderType::Stream,
+            display_priority: 1,
+        };
+        cache.put_providers(1, CountryCode::NZ, &[netflix]).await.unwrap();
+
+        let summary = plan(ProcessOptions {
+            upcoming_providers: true,
+            compare_providers: Some(CountryCode::US),
+            ..test_options()
+        })
+        .await;
+        assert_eq!(summary.provider_requests, 3);
+        assert_eq!(summary.cached_provider_requests, 1);
+        assert_eq!(summary.provider_fetches, 2);
+
+        let summary =
+            plan(ProcessOptions { since: Some(i64::MAX), new_only: true, ..test_options() }).await;
+        assert_eq!(summary.provider_requests, 0);
+    }
+
+    #[tokio::test]
+    async fn single_film_lookup_checks_the_film_exists() {
+        let cache = CacheManager::for_tests().await;
+        let tmdb = FakeTmdb { unknown: HashSet::from([404]), ..Default::default() };
+
+        let known = film_releases(&cache, &tmdb, 7, CountryCode::NZ, test_options()).await.unwrap();
+        let unknown = film_releases(&cache, &tmdb, 404, CountryCode::NZ, test_options()).await;
+
+        assert_eq!(known.title, "Film 7");
+        assert!(unknown.unwrap_err().to_string().contains("404"));
+    }
 }
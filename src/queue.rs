@@ -0,0 +1,117 @@
+use std::{
+    collections::BTreeSet,
+    sync::{
+        Mutex, MutexGuard,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Bounds how many watchlists are processed at once. Waiters are served in arrival order,
+/// so simultaneous requests share the TMDB rate limit instead of all slowing down together.
+/// A caller that times out and retries joins the back of the queue again.
+pub struct ProcessQueue {
+    slots: Semaphore,
+    next_ticket: AtomicU64,
+    /// Tickets of the callers still waiting for a slot, oldest first
+    waiting: Mutex<BTreeSet<u64>>,
+}
+
+impl ProcessQueue {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            slots: Semaphore::new(limit.max(1)),
+            next_ticket: AtomicU64::new(0),
+            waiting: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Waits up to `max_wait` for a slot. On timeout returns the caller's position in the
+    /// queue, counting from 1: one more than the callers that arrived earlier and are still
+    /// waiting.
+    pub async fn acquire(&self, max_wait: Duration) -> Result<SemaphorePermit<'_>, usize> {
+        if let Ok(permit) = self.slots.try_acquire() {
+            return Ok(permit);
+        }
+
+        let ticket = Ticket::take(self);
+        match tokio::time::timeout(max_wait, self.slots.acquire()).await {
+            Ok(Ok(permit)) => Ok(permit),
+            // The semaphore is never closed, so only the timeout lands here
+            _ => Err(ticket.position()),
+        }
+    }
+
+    fn waiting(&self) -> MutexGuard<'_, BTreeSet<u64>> {
+        self.waiting.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A caller's place in the queue, given up when it gets a slot, times out or is cancelled.
+struct Ticket<'a> {
+    queue: &'a ProcessQueue,
+    number: u64,
+}
+
+impl<'a> Ticket<'a> {
+    fn take(queue: &'a ProcessQueue) -> Self {
+        let number = queue.next_ticket.fetch_add(1, Ordering::Relaxed);
+        queue.waiting().insert(number);
+        Self { queue, number }
+    }
+
+    fn position(&self) -> usize {
+        self.queue.waiting().range(..self.number).count() + 1
+    }
+}
+
+impl Drop for Ticket<'_> {
+    fn drop(&mut self) {
+        self.queue.waiting().remove(&self.number);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn timed_out_caller_gets_its_own_position() {
+        let queue = Arc::new(ProcessQueue::new(1));
+        let _running = queue.acquire(Duration::ZERO).await.unwrap();
+
+        let mut earlier = Vec::new();
+        for _ in 0..2 {
+            let queue = queue.clone();
+            earlier.push(tokio::spawn(async move {
+                queue.acquire(Duration::from_secs(5)).await.map(drop)
+            }));
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(queue.acquire(Duration::from_millis(10)).await.err(), Some(3));
+        for waiter in earlier {
+            waiter.abort();
+        }
+    }
+
+    #[tokio::test]
+    async fn cancelled_callers_leave_the_queue() {
+        let queue = Arc::new(ProcessQueue::new(1));
+        let _running = queue.acquire(Duration::ZERO).await.unwrap();
+
+        let cancelled = {
+            let queue = queue.clone();
+            tokio::spawn(async move { queue.acquire(Duration::from_secs(5)).await.map(drop) })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        cancelled.abort();
+        let _ = cancelled.await;
+
+        assert_eq!(queue.acquire(Duration::from_millis(10)).await.err(), Some(1));
+    }
+}
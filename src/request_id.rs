@@ -0,0 +1,43 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response};
+use tracing::Span;
+
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Correlation id for one HTTP request, also recorded on the request's tracing span so every
+/// log line emitted while handling it carries the id.
+#[derive(Clone, Debug)]
+pub struct RequestId(pub String);
+
+pub async fn assign(mut req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && v.len() <= 64)
+        .map(str::to_string)
+        .unwrap_or_else(generate);
+
+    req.extensions_mut().insert(RequestId(id.clone()));
+
+    let mut resp = next.run(req).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        resp.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    resp
+}
+
+pub fn make_span<B>(req: &axum::http::Request<B>) -> Span {
+    let id = req.extensions().get::<RequestId>().map(|id| id.0.as_str()).unwrap_or("-");
+    tracing::info_span!("request", request_id = %id, method = %req.method(), uri = %req.uri())
+}
+
+fn generate() -> String {
+    // Start-up time keeps ids unique across restarts without pulling in a uuid crate
+    static START: std::sync::OnceLock<i64> = std::sync::OnceLock::new();
+    let start = START.get_or_init(|| jiff::Timestamp::now().as_second());
+    format!("{:x}-{:x}", start, NEXT_ID.fetch_add(1, Ordering::Relaxed))
+}
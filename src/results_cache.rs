@@ -0,0 +1,107 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::etag::CacheValidators;
+
+/// Rendered results fragments and API responses kept for a short time, so repeat views of a
+/// popular watchlist skip scraping and rendering. Entries live no longer than the release cache
+/// they were built from.
+pub struct ResultsCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, CachedResults>>,
+}
+
+struct CachedResults {
+    stored_at: Instant,
+    html: String,
+    validators: CacheValidators,
+    /// Films the entry shows, so refreshing one only drops the entries it appears in
+    tmdb_ids: HashSet<i32>,
+}
+
+impl ResultsCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, entries: Mutex::new(HashMap::new()) }
+    }
+
+    /// `view` covers everything else the rendered page depends on, see `ResultsView::cache_key`.
+    pub fn key(username: &str, country: &str, view: &str) -> String {
+        format!("{}|{country}|{view}", username.to_lowercase())
+    }
+
+    pub fn get(&self, key: &str) -> Option<(String, CacheValidators)> {
+        if self.ttl.is_zero() {
+            return None;
+        }
+
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        match entries.get(key) {
+            Some(entry) if entry.stored_at.elapsed() <= self.ttl => {
+                Some((entry.html.clone(), entry.validators.clone()))
+            },
+            Some(_) => {
+                entries.remove(key);
+                None
+            },
+            None => None,
+        }
+    }
+
+    pub fn put(
+        &self,
+        key: String,
+        html: String,
+        validators: CacheValidators,
+        tmdb_ids: impl IntoIterator<Item = i32>,
+    ) {
+        if self.ttl.is_zero() {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, entry| entry.stored_at.elapsed() <= self.ttl);
+        entries.insert(
+            key,
+            CachedResults {
+                stored_at: Instant::now(),
+                html,
+                validators,
+                tmdb_ids: tmdb_ids.into_iter().collect(),
+            },
+        );
+    }
+
+    /// Drops the entries showing any of these films, e.g. after their cached data was refreshed.
+    pub fn remove_films(&self, tmdb_ids: &[i32]) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, entry| !tmdb_ids.iter().any(|id| entry.tmdb_ids.contains(id)));
+    }
+
+    /// Drops every entry, e.g. after a match override changed which film a slug shows.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removing_a_film_keeps_entries_without_it() {
+        let cache = ResultsCache::new(Duration::from_secs(60));
+        let validators = CacheValidators::from_body(b"");
+        cache.put("alice".to_string(), "a".to_string(), validators.clone(), [1, 2]);
+        cache.put("bob".to_string(), "b".to_string(), validators.clone(), [3]);
+        cache.put("api/film/2".to_string(), "{}".to_string(), validators, [2]);
+
+        cache.remove_films(&[2]);
+
+        assert!(cache.get("alice").is_none());
+        assert!(cache.get("api/film/2").is_none());
+        assert_eq!(cache.get("bob").map(|(html, _)| html).as_deref(), Some("b"));
+    }
+}
@@ -1,37 +1,186 @@
 use std::sync::Arc;
 
 use axum::{
-    extract::{Query, State},
-    http::{HeaderValue, StatusCode},
-    response::{Html, IntoResponse, Response},
+    extract::{Form, Path, Query, State},
+    http::{
+        HeaderMap, HeaderValue, StatusCode,
+        header::{AUTHORIZATION, CACHE_CONTROL, CONTENT_TYPE, X_CONTENT_TYPE_OPTIONS},
+    },
+    response::{Html, IntoResponse, Json, Redirect, Response},
 };
 use axum_extra::extract::{CookieJar, cookie::Cookie};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
 use time::Duration;
 use tracing::{error, info};
 
-use crate::{AppState, error::AppResult, models::TrackRequest, templates};
+use crate::{
+    AppState,
+    countries::CountryCode,
+    error::AppResult,
+    etag::CacheValidators,
+    image_cache::{CachedImage, ImageCache, ImageKind, MAX_IMAGE_BYTES},
+    models::{
+        FilmSource, Layout, MatchConfidence, ReleaseCategory, ReleaseDate, SortOrder, TrackRequest,
+        WatchProvider, WatchlistOrder, today_in,
+    },
+    processor::ProcessOutcome,
+    results_cache::ResultsCache,
+    templates::{self, SubscriptionStatus},
+};
 
-pub async fn index(jar: CookieJar) -> Html<String> {
+#[derive(Debug, Deserialize)]
+pub struct IndexQuery {
+    /// Plain form controls for browsers without JavaScript
+    #[serde(default)]
+    nojs: bool,
+}
+
+pub async fn index(
+    State(state): State<Arc<AppState>>,
+    jar: CookieJar,
+    Query(q): Query<IndexQuery>,
+) -> Html<String> {
     let username = jar.get("username").map(|c| c.value().to_string());
-    let country = jar.get("country").map(|c| c.value().to_string());
+    let forced_country = state.config.force_country.as_deref();
+    let country = match forced_country {
+        Some(code) => Some(code.to_string()),
+        None => jar.get("country").map(|c| c.value().to_string()),
+    };
+
+    Html(templates::index_page(
+        username.as_deref(),
+        country.as_deref(),
+        forced_country,
+        state.mock_mode,
+        q.nojs,
+    ))
+}
+
+/// A plain username tracks that member's watchlist; pasted Letterboxd links are parsed.
+fn requested_source(input: &str) -> anyhow::Result<FilmSource> {
+    let input = input.trim();
+    if input.is_empty() {
+        anyhow::bail!("username is required");
+    }
+    if input.contains("letterboxd.com") {
+        return crate::scraper::parse_letterboxd_url(input)
+            .ok_or_else(|| anyhow::anyhow!("unsupported Letterboxd link: {input}"));
+    }
+    Ok(FilmSource::Watchlist(input.to_string()))
+}
+
+/// Each extra country adds a release lookup per film, so only a few are shown.
+const MAX_EXTRA_COUNTRIES: usize = 3;
+
+/// Supported codes from a comma-separated `also` parameter, without duplicates or `country`.
+fn requested_extra_countries(also: &str, country: CountryCode) -> Vec<CountryCode> {
+    let mut extra: Vec<CountryCode> = Vec::new();
+    for code in also.split(',').filter_map(|c| CountryCode::parse(c).ok()) {
+        if code != country && !extra.contains(&code) {
+            extra.push(code);
+        }
+    }
+    extra.truncate(MAX_EXTRA_COUNTRIES);
+    extra
+}
 
-    Html(templates::index_page(username.as_deref(), country.as_deref()))
+/// Longest release window a viewer can ask for, in months.
+const MAX_WINDOW_MONTHS: u32 = 120;
+
+/// Provider ids are TMDB's, so only a handful of services are worth remembering.
+const MAX_SERVICES: usize = 20;
+
+/// Sorted provider ids from a `services` parameter or cookie, separated by any non-digit.
+fn requested_services(services: &str) -> Vec<i32> {
+    let mut ids: Vec<i32> = services
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|id| id.parse().ok())
+        .filter(|id| *id > 0)
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+    ids.truncate(MAX_SERVICES);
+    ids
+}
+
+/// A supported country other than `country` to compare streaming services with.
+fn requested_compare_country(compare: &str, country: CountryCode) -> Option<CountryCode> {
+    CountryCode::parse(compare).ok().filter(|other| *other != country)
+}
+
+/// The requested country, or the configured `FORCE_COUNTRY` when none was given.
+fn requested_country(state: &AppState, country: &str) -> anyhow::Result<CountryCode> {
+    pick_country(state.config.force_country, country)
+}
+
+/// A forced country is the only one served, so other codes are rejected rather than quietly
+/// swapped for it.
+fn pick_country(forced: Option<CountryCode>, country: &str) -> anyhow::Result<CountryCode> {
+    let Some(forced) = forced else {
+        return CountryCode::parse(country);
+    };
+    if country.trim().is_empty() {
+        return Ok(forced);
+    }
+    let requested = CountryCode::parse(country)?;
+    anyhow::ensure!(
+        requested == forced,
+        "country must be {forced} on this instance, got {requested}"
+    );
+    Ok(forced)
 }
 
 pub async fn track(
+    State(state): State<Arc<AppState>>,
     jar: CookieJar,
     Query(req): Query<TrackRequest>,
-) -> AppResult<(CookieJar, Html<String>)> {
-    let username = req.username.trim().to_string();
-    let country = req.country.trim().to_uppercase();
+) -> AppResult<(CookieJar, Response)> {
+    let source = requested_source(&req.username)?;
+    let username = source.input();
+    let country = requested_country(&state, &req.country)?;
 
-    if username.is_empty() {
-        return Err(anyhow::anyhow!("username is required").into());
-    }
+    let layout = req
+        .layout
+        .or_else(|| jar.get("layout").and_then(|c| Layout::from_query(c.value())))
+        .unwrap_or_default();
+    let hide_undated = req
+        .hide_undated
+        .or_else(|| jar.get("hide_undated").and_then(|c| c.value().parse().ok()))
+        .unwrap_or(false);
+    let services = match &req.services {
+        Some(services) => requested_services(services),
+        None => jar.get("services").map(|c| requested_services(c.value())).unwrap_or_default(),
+    };
+    let flatrate_only = req
+        .flatrate_only
+        .or_else(|| jar.get("flatrate_only").and_then(|c| c.value().parse().ok()))
+        .unwrap_or(state.config.flatrate_only);
+    let view = templates::ResultsView {
+        sort: req.sort,
+        layout,
+        // Decided by the browser's time zone once results are requested
+        today: today_in("UTC"),
+        also: requested_extra_countries(&req.also, country),
+        hide_undated,
+        window_months: req.window.min(MAX_WINDOW_MONTHS),
+        since: req.since,
+        new_only: req.new_only,
+        order: req.order,
+        services,
+        flatrate_only,
+        compare_providers: requested_compare_country(&req.compare_providers, country),
+        nojs: req.nojs,
+    };
+    // Without JavaScript nothing would fetch the results, so go straight to them
+    let page = if view.nojs {
+        Redirect::to(&view.process_url(&source, &country)).into_response()
+    } else {
+        Html(templates::processing_page(&source, &country, &view)).into_response()
+    };
 
-    if country.len() != 2 || !country.chars().all(|c| c.is_ascii_alphabetic()) {
-        return Err(anyhow::anyhow!("country must be a 2-letter code").into());
+    if req.forget {
+        return Ok((jar, page));
     }
 
     let max_age = Duration::days(365);
@@ -42,86 +191,937 @@ pub async fn track(
         .same_site(cookie::SameSite::Lax)
         .build();
 
-    let country_cookie = Cookie::build(("country", country.clone()))
+    let country_cookie = Cookie::build(("country", country.as_str()))
         .path("/")
         .max_age(max_age)
         .same_site(cookie::SameSite::Lax)
         .build();
 
-    let jar = jar.add(username_cookie).add(country_cookie);
+    let layout_cookie = Cookie::build(("layout", layout.as_query()))
+        .path("/")
+        .max_age(max_age)
+        .same_site(cookie::SameSite::Lax)
+        .build();
+
+    let hide_undated_cookie = Cookie::build(("hide_undated", hide_undated.to_string()))
+        .path("/")
+        .max_age(max_age)
+        .same_site(cookie::SameSite::Lax)
+        .build();
+
+    let flatrate_only_cookie = Cookie::build(("flatrate_only", flatrate_only.to_string()))
+        .path("/")
+        .max_age(max_age)
+        .same_site(cookie::SameSite::Lax)
+        .build();
 
-    Ok((jar, Html(templates::processing_page(&username, &country))))
+    // Commas aren't allowed in cookie values
+    let services_value =
+        view.services.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(".");
+    let services_cookie = Cookie::build(("services", services_value))
+        .path("/")
+        .max_age(max_age)
+        .same_site(cookie::SameSite::Lax)
+        .build();
+
+    let jar = jar
+        .add(username_cookie)
+        .add(country_cookie)
+        .add(layout_cookie)
+        .add(hide_undated_cookie)
+        .add(flatrate_only_cookie)
+        .add(services_cookie);
+
+    Ok((jar, page))
 }
 
+/// How long a /process request waits for a free slot before asking the client to retry.
+const PROCESS_QUEUE_WAIT: std::time::Duration = std::time::Duration::from_secs(15);
+const PROCESS_QUEUE_RETRY_SECS: u64 = 2;
+
 #[derive(Debug, Deserialize)]
 pub struct ProcessQuery {
     username: String,
+    #[serde(default)]
     country: String,
+    #[serde(default)]
+    sort: SortOrder,
+    #[serde(default)]
+    layout: Layout,
+    #[serde(default)]
+    also: String,
+    #[serde(default)]
+    hide_undated: bool,
+    #[serde(default)]
+    flatrate_only: Option<bool>,
+    #[serde(default)]
+    window: u32,
+    #[serde(default)]
+    since: Option<i64>,
+    #[serde(default)]
+    new_only: bool,
+    #[serde(default)]
+    order: WatchlistOrder,
+    #[serde(default)]
+    services: String,
+    #[serde(default)]
+    compare_providers: String,
+    /// Render a whole page with links that work without JavaScript
+    #[serde(default)]
+    nojs: bool,
+    /// Undocumented: report what would be fetched instead of calling TMDB
+    #[serde(default)]
+    dry_run: bool,
+    /// The viewer's IANA time zone, used to decide which releases are still upcoming
+    #[serde(default)]
+    tz: String,
 }
 
 pub async fn process(
     State(state): State<Arc<AppState>>,
     Query(q): Query<ProcessQuery>,
+    headers: HeaderMap,
 ) -> Response {
     let username = q.username.trim().to_string();
-    let country = q.country.trim().to_uppercase();
+    // Opened directly rather than fetched by the processing page, e.g. with JavaScript off
+    let full_page =
+        q.nojs || headers.get("sec-fetch-mode").is_some_and(|mode| mode.as_bytes() == b"navigate");
+    let country = match requested_country(&state, &q.country) {
+        Ok(country) => country,
+        Err(err) => {
+            let message = crate::error::error_to_user_message(&err);
+            let body = templates::error_fragment(message);
+            if full_page {
+                return Html(templates::results_page(&username, &body)).into_response();
+            }
+            return results_response(body, None, &headers);
+        },
+    };
+    let today = today_in(&q.tz);
+    let also = requested_extra_countries(&q.also, country);
+    let view = templates::ResultsView {
+        sort: q.sort,
+        layout: q.layout,
+        today,
+        also,
+        hide_undated: q.hide_undated,
+        window_months: q.window.min(MAX_WINDOW_MONTHS),
+        since: q.since,
+        new_only: q.new_only,
+        order: q.order,
+        services: requested_services(&q.services),
+        flatrate_only: q.flatrate_only.unwrap_or(state.config.flatrate_only),
+        compare_providers: requested_compare_country(&q.compare_providers, country),
+        nojs: full_page,
+    };
+    let cache_key = ResultsCache::key(&username, &country, &view.cache_key());
+
+    if !q.dry_run
+        && let Some((body, validators)) = state.results_cache.get(&cache_key)
+    {
+        info!(username = %username, country = %country, "serving cached results");
+        if full_page {
+            return Html(templates::results_page(&username, &body)).into_response();
+        }
+        return results_response(body, Some(validators), &headers);
+    }
+
+    let _permit = match state.process_queue.acquire(PROCESS_QUEUE_WAIT).await {
+        Ok(permit) => permit,
+        Err(position) => {
+            info!(username = %username, position, "process queue full");
+            let body = templates::queued_fragment(position);
+            let mut resp = if full_page {
+                Html(templates::results_page(&username, &body)).into_response()
+            } else {
+                Html(body).into_response()
+            };
+            *resp.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+            resp.headers_mut().insert("retry-after", HeaderValue::from(PROCESS_QUEUE_RETRY_SECS));
+            resp.headers_mut().insert("x-queue-position", HeaderValue::from(position));
+            if full_page {
+                // Nothing runs the retry script, so have the browser reload instead
+                resp.headers_mut().insert("refresh", HeaderValue::from(PROCESS_QUEUE_RETRY_SECS));
+            }
+            return resp;
+        },
+    };
 
     info!(username = %username, country = %country, "processing request");
 
     let result = async {
-        if username.is_empty() {
-            anyhow::bail!("username is required");
-        }
-        if country.len() != 2 || !country.chars().all(|c| c.is_ascii_alphabetic()) {
-            anyhow::bail!("country must be a 2-letter code");
-        }
+        let source = requested_source(&username)?;
 
-        let today: jiff::civil::Date = jiff::Zoned::now().into();
-        let current_year = today.year();
-        let cutoff_year = current_year.saturating_sub(3);
+        let cutoff_year = today.year().saturating_sub(3);
 
         let watchlist = crate::scraper::fetch_watchlist(
             &state.http,
-            &username,
+            &source,
             state.config.letterboxd_delay_ms,
             cutoff_year,
+            state.config.max_watchlist_films,
+            view.order,
         )
         .await?;
-        info!(username = %username, film_count = watchlist.len(), "fetched watchlist");
+        info!(
+            username = %username,
+            film_count = watchlist.films.len(),
+            truncated = watchlist.truncated,
+            interrupted = watchlist.interrupted.is_some(),
+            "fetched watchlist"
+        );
+
+        let film_cap = watchlist.truncated.then_some(state.config.max_watchlist_films);
+        let mut coverage = templates::Coverage {
+            film_cap,
+            watchlist_interrupted: watchlist.interrupted.is_some(),
+            partial: false,
+        };
 
-        if watchlist.is_empty() {
+        if watchlist.films.is_empty() {
             info!(username = %username, "empty watchlist");
-            return Ok(templates::results_fragment(&username, &country, &[]));
+            return Ok((
+                templates::results_fragment(
+                    &source,
+                    &country,
+                    &[],
+                    coverage,
+                    &view,
+                    &state.config,
+                    state.mock_mode,
+                ),
+                None,
+            ));
         }
 
-        let films = crate::processor::process(
+        let outcome = crate::processor::process(
             &state.http,
             &state.cache,
             &*state.tmdb,
-            watchlist,
-            &country,
-            state.config.max_concurrent,
-            current_year,
+            watchlist.films,
+            country,
+            crate::processor::ProcessOptions {
+                today,
+                extra_countries: view.also.clone(),
+                release_window_months: view.window_months,
+                since: view.since,
+                new_only: view.new_only,
+                compare_providers: view.compare_providers,
+                ..crate::processor::ProcessOptions::from_config(&state.config)
+            },
+            q.dry_run,
         )
         .await?;
-        info!(username = %username, result_count = films.len(), "completed processing");
+        let films = match outcome {
+            ProcessOutcome::Films(films) => films,
+            ProcessOutcome::Partial(films) => {
+                coverage.partial = true;
+                films
+            },
+            ProcessOutcome::DryRun(summary) => {
+                return Ok((
+                    templates::dry_run_fragment(source.username(), &country, &summary),
+                    None,
+                ));
+            },
+        };
+        info!(username = %username, result_count = films.len(), partial = coverage.partial, "completed processing");
+        let html = templates::results_fragment(
+            &source,
+            &country,
+            &films,
+            coverage,
+            &view,
+            &state.config,
+            state.mock_mode,
+        );
 
-        Ok::<_, anyhow::Error>(templates::results_fragment(&username, &country, &films))
+        // Not reused or revalidated, so the next request tries again
+        if coverage.partial || coverage.watchlist_interrupted {
+            return Ok((html, None));
+        }
+
+        let release_keys = crate::processor::release_request_keys(
+            films.iter().map(|f| f.tmdb_id),
+            country,
+            &view.also,
+        );
+        let release_entries = state.cache.release_cached_at(&release_keys).await?;
+        let provider_keys: Vec<_> = films
+            .iter()
+            .flat_map(|f| {
+                std::iter::once((f.tmdb_id, country))
+                    .chain(view.compare_providers.map(|compare| (f.tmdb_id, compare)))
+            })
+            .collect();
+        let provider_entries = state.cache.provider_cached_at(&provider_keys).await?;
+        let validators = CacheValidators::from_cache_entries(
+            &format!("{username}|{country}|{film_cap:?}|{}", view.cache_key()),
+            &release_entries,
+            &provider_entries,
+        );
+
+        let tmdb_ids: Vec<i32> = films.iter().map(|f| f.tmdb_id).collect();
+        Ok::<_, anyhow::Error>((html, Some((validators, tmdb_ids))))
     }
     .await;
 
-    let body = match result {
-        Ok(html) => html,
+    let (body, cacheable) = match result {
+        Ok(rendered) => rendered,
         Err(err) => {
             error!(username = %username, error = %err, "request failed");
             let user_friendly_error = crate::error::error_to_user_message(&err);
-            templates::error_fragment(user_friendly_error)
+            (templates::error_fragment(user_friendly_error), None)
         },
     };
 
+    let validators = cacheable.map(|(validators, tmdb_ids)| {
+        state.results_cache.put(cache_key, body.clone(), validators.clone(), tmdb_ids);
+        validators
+    });
+
+    // Validators describe the fragment, so whole pages aren't revalidated against them
+    if full_page {
+        return Html(templates::results_page(&username, &body)).into_response();
+    }
+    results_response(body, validators, &headers)
+}
+
+fn results_response(
+    body: String,
+    validators: Option<CacheValidators>,
+    headers: &HeaderMap,
+) -> Response {
+    if let Some(resp) = validators.as_ref().and_then(|v| v.not_modified(headers)) {
+        return resp;
+    }
+
     let mut resp = Html(body).into_response();
     *resp.status_mut() = StatusCode::OK;
+    if let Some(validators) = &validators {
+        validators.apply(resp.headers_mut());
+    }
     resp.headers_mut().insert("datastar-selector", HeaderValue::from_static("#content"));
     resp.headers_mut().insert("datastar-mode", HeaderValue::from_static("outer"));
     resp
 }
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshQuery {
+    slug: String,
+    country: String,
+    #[serde(default)]
+    also: String,
+    #[serde(default)]
+    flatrate_only: Option<bool>,
+    #[serde(default)]
+    compare_providers: String,
+    #[serde(default)]
+    tz: String,
+}
+
+pub async fn refresh(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<RefreshQuery>,
+) -> Response {
+    let slug = q.slug.trim().to_string();
+    let today = today_in(&q.tz);
+
+    info!(slug = %slug, country = %q.country, "refreshing film");
+
+    let result = async {
+        if slug.is_empty() {
+            anyhow::bail!("slug is required");
+        }
+        let country = CountryCode::parse(&q.country)?;
+
+        // The refresh can match the slug to a different film, so pages showing the old one go too
+        let previous = state.cache.get_films(std::slice::from_ref(&slug)).await?;
+        let mut refreshed: Vec<i32> = previous.values().filter_map(|f| f.tmdb_id).collect();
+
+        let film = crate::processor::refresh_film(
+            &state.http,
+            &state.cache,
+            &*state.tmdb,
+            &slug,
+            country,
+            crate::processor::ProcessOptions {
+                today,
+                extra_countries: requested_extra_countries(&q.also, country),
+                compare_providers: requested_compare_country(&q.compare_providers, country),
+                ..crate::processor::ProcessOptions::from_config(&state.config)
+            },
+        )
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("no TMDB match for {slug}"))?;
+
+        let flatrate_only = q.flatrate_only.unwrap_or(state.config.flatrate_only);
+        refreshed.push(film.tmdb_id);
+        let html =
+            templates::film_card_fragment(&film, &country, &state.config, today, flatrate_only);
+        Ok::<_, anyhow::Error>((html, refreshed))
+    }
+    .await;
+
+    let body = match result {
+        Ok((html, refreshed)) => {
+            state.results_cache.remove_films(&refreshed);
+            html
+        },
+        Err(err) => {
+            error!(slug = %slug, error = %err, "refresh failed");
+            templates::refresh_error_fragment(
+                &slug,
+                "Unable to refresh this film. Please try again.",
+            )
+        },
+    };
+
+    let selector = format!("#{}", templates::film_card_id(&slug));
+
+    let mut resp = Html(body).into_response();
+    *resp.status_mut() = StatusCode::OK;
+    if let Ok(value) = HeaderValue::from_str(&selector) {
+        resp.headers_mut().insert("datastar-selector", value);
+    }
+    resp.headers_mut().insert("datastar-mode", HeaderValue::from_static("outer"));
+    resp
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubscriptionForm {
+    username: String,
+    country: String,
+    webhook_url: String,
+}
+
+impl SubscriptionForm {
+    fn validated(self) -> anyhow::Result<(String, CountryCode, String)> {
+        let username = self.username.trim().to_string();
+        let webhook_url = self.webhook_url.trim().to_string();
+
+        if username.is_empty() {
+            anyhow::bail!("username is required");
+        }
+        let country = CountryCode::parse(&self.country)?;
+        if !(webhook_url.starts_with("https://") || webhook_url.starts_with("http://")) {
+            anyhow::bail!("webhook URL must start with http:// or https://");
+        }
+
+        Ok((username, country, webhook_url))
+    }
+}
+
+pub async fn subscribe(
+    State(state): State<Arc<AppState>>,
+    Form(form): Form<SubscriptionForm>,
+) -> AppResult<Html<String>> {
+    if !crate::subscriptions::enabled(&state.config) {
+        return Err(anyhow::anyhow!("subscriptions are disabled").into());
+    }
+    let (username, country, webhook_url) = form.validated()?;
+
+    let confirmed =
+        crate::subscriptions::subscribe(&state, &username, country, &webhook_url).await?;
+    let status =
+        if confirmed { SubscriptionStatus::Confirmed } else { SubscriptionStatus::Pending };
+
+    Ok(Html(templates::subscription_page(&username, &country, &webhook_url, status)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmQuery {
+    #[serde(default)]
+    token: String,
+}
+
+/// Opened from the link in the confirmation ping, turning notifications on.
+pub async fn confirm_subscription(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ConfirmQuery>,
+) -> AppResult<Html<String>> {
+    if !crate::subscriptions::enabled(&state.config) {
+        return Err(anyhow::anyhow!("subscriptions are disabled").into());
+    }
+    let sub = crate::subscriptions::confirm(&state, query.token.trim()).await?;
+
+    Ok(Html(templates::subscription_page(
+        &sub.username,
+        &sub.country,
+        &sub.webhook_url,
+        SubscriptionStatus::Confirmed,
+    )))
+}
+
+pub async fn unsubscribe(
+    State(state): State<Arc<AppState>>,
+    Form(form): Form<SubscriptionForm>,
+) -> AppResult<Html<String>> {
+    if !crate::subscriptions::enabled(&state.config) {
+        return Err(anyhow::anyhow!("subscriptions are disabled").into());
+    }
+    let (username, country, webhook_url) = form.validated()?;
+
+    let removed =
+        crate::subscriptions::unsubscribe(&state, &username, country, &webhook_url).await?;
+    info!(username = %username, country = %country, removed, "unsubscribed");
+
+    Ok(Html(templates::subscription_page(
+        &username,
+        &country,
+        &webhook_url,
+        SubscriptionStatus::Removed,
+    )))
+}
+
+/// Every country the country picker offers, as `[{ "code", "name" }]`.
+pub async fn countries() -> Json<&'static [crate::countries::Country]> {
+    Json(crate::countries::COUNTRIES)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveQuery {
+    slug: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResolvedFilmJson {
+    slug: String,
+    tmdb_id: Option<i32>,
+    title: String,
+    year: Option<i16>,
+    poster_path: Option<String>,
+    match_confidence: MatchConfidence,
+    cached: bool,
+}
+
+pub async fn resolve(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<ResolveQuery>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let slug = q.slug.trim().to_string();
+    if slug.is_empty() {
+        return Err(anyhow::anyhow!("slug is required").into());
+    }
+
+    info!(slug = %slug, "resolving film");
+
+    let (film, cached) =
+        crate::processor::resolve_film(&state.http, &state.cache, &*state.tmdb, &slug)
+            .await
+            .inspect_err(|err| error!(slug = %slug, error = %err, "resolve failed"))?;
+
+    let body = serde_json::to_string(&ResolvedFilmJson {
+        slug: film.slug,
+        tmdb_id: film.tmdb_id,
+        title: film.title,
+        year: film.year,
+        poster_path: film.poster_path,
+        match_confidence: film.match_confidence,
+        cached,
+    })
+    .map_err(anyhow::Error::from)?;
+
+    // Nothing here has a cache time of its own, so the body is its own validator
+    let validators = CacheValidators::from_body(body.as_bytes());
+    Ok(json_response(body, &validators, &headers))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FilmQuery {
+    #[serde(default)]
+    country: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FilmReleasesJson {
+    tmdb_id: i32,
+    country: CountryCode,
+    title: Option<String>,
+    year: Option<i16>,
+    letterboxd_slug: Option<String>,
+    poster_path: Option<String>,
+    category: ReleaseCategory,
+    theatrical: Vec<ReleaseDate>,
+    streaming: Vec<ReleaseDate>,
+    /// Premiere or physical dates for films with no theatrical or streaming dates
+    fallback: Vec<ReleaseDate>,
+    providers: Vec<WatchProvider>,
+}
+
+pub async fn film(
+    State(state): State<Arc<AppState>>,
+    Path(tmdb_id): Path<i32>,
+    Query(q): Query<FilmQuery>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let country = requested_country(&state, &q.country)?;
+    let options = crate::processor::ProcessOptions::from_config(&state.config);
+
+    // Kept with the results pages, so repeat requests skip the cache lookups entirely
+    let cache_key =
+        ResultsCache::key(&format!("api/film/{tmdb_id}"), &country, &options.today.to_string());
+    if let Some((body, validators)) = state.results_cache.get(&cache_key) {
+        return Ok(json_response(body, &validators, &headers));
+    }
+
+    info!(tmdb_id, country = %country, "fetching film releases");
+
+    let today = options.today;
+    let film =
+        crate::processor::film_releases(&state.cache, &*state.tmdb, tmdb_id, country, options)
+            .await
+            .inspect_err(|err| error!(tmdb_id, error = %err, "film releases failed"))?;
+
+    let release_keys = crate::processor::release_request_keys([tmdb_id], country, &[]);
+    let release_entries = state.cache.release_cached_at(&release_keys).await?;
+    let provider_entries = state.cache.provider_cached_at(&[(tmdb_id, country)]).await?;
+    let validators = CacheValidators::from_cache_entries(
+        &format!("api/film|{tmdb_id}|{country}|{today}"),
+        &release_entries,
+        &provider_entries,
+    );
+
+    let body = serde_json::to_string(&FilmReleasesJson {
+        tmdb_id: film.tmdb_id,
+        country,
+        title: (!film.title.is_empty()).then_some(film.title),
+        year: film.year,
+        letterboxd_slug: (!film.letterboxd_slug.is_empty()).then_some(film.letterboxd_slug),
+        poster_path: film.poster_path,
+        category: film.category,
+        theatrical: film.theatrical,
+        streaming: film.streaming,
+        fallback: film.fallback_releases,
+        providers: film.streaming_providers,
+    })
+    .map_err(anyhow::Error::from)?;
+
+    state.results_cache.put(cache_key, body.clone(), validators.clone(), [tmdb_id]);
+    Ok(json_response(body, &validators, &headers))
+}
+
+/// A JSON body with its validators, or `304 Not Modified` when the client has it already.
+fn json_response(body: String, validators: &CacheValidators, headers: &HeaderMap) -> Response {
+    if let Some(resp) = validators.not_modified(headers) {
+        return resp;
+    }
+
+    let mut resp = ([(CONTENT_TYPE, "application/json")], body).into_response();
+    validators.apply(resp.headers_mut());
+    resp
+}
+
+/// Serves a TMDB poster or provider logo through this server when `PROXY_IMAGES` is set,
+/// keeping it in memory after the first request.
+pub async fn image(
+    State(state): State<Arc<AppState>>,
+    Path((kind, file)): Path<(String, String)>,
+) -> Response {
+    let Some(kind) = ImageKind::from_path(&kind) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    if !state.config.proxy_images || !crate::image_cache::is_valid_file_name(&file) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let key = ImageCache::key(kind, &file);
+    let image = match state.image_cache.get(&key) {
+        Some(image) => image,
+        None => match fetch_image(&state, kind, &file).await {
+            Ok(Some(image)) => {
+                state.image_cache.put(key, image.clone());
+                image
+            },
+            Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+            Err(err) => {
+                error!(file = %file, error = %err, "image proxy failed");
+                return StatusCode::BAD_GATEWAY.into_response();
+            },
+        },
+    };
+
+    (
+        [
+            (CONTENT_TYPE, image.content_type),
+            // TMDB never changes the image behind a file name
+            (CACHE_CONTROL, "public, max-age=31536000, immutable".to_string()),
+            // Served from this origin, so never let a browser treat one as a document
+            (X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        image.bytes,
+    )
+        .into_response()
+}
+
+/// The image from TMDB, or `None` when TMDB doesn't have it.
+async fn fetch_image(
+    state: &AppState,
+    kind: ImageKind,
+    file: &str,
+) -> anyhow::Result<Option<CachedImage>> {
+    let resp = state.tmdb.get_image(kind.tmdb_size(), file).await?;
+    if resp.status() == wreq::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let resp = resp.error_for_status()?;
+
+    let content_type = resp
+        .headers()
+        .get(wreq::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| v.starts_with("image/"))
+        .ok_or_else(|| anyhow::anyhow!("TMDB image response is not an image"))?
+        .to_string();
+    if resp.content_length().is_some_and(|len| len > MAX_IMAGE_BYTES as u64) {
+        anyhow::bail!("TMDB image is larger than {MAX_IMAGE_BYTES} bytes");
+    }
+
+    let bytes = resp.bytes().await?;
+    if bytes.len() > MAX_IMAGE_BYTES {
+        anyhow::bail!("TMDB image is larger than {MAX_IMAGE_BYTES} bytes");
+    }
+
+    Ok(Some(CachedImage { content_type, bytes }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct VacuumJson {
+    reclaimed_bytes: i64,
+}
+
+/// Status to reject an admin request with, if it lacks the configured bearer token. Admin
+/// endpoints 404 when no token is configured.
+fn admin_rejection(state: &AppState, headers: &HeaderMap) -> Option<StatusCode> {
+    let Some(admin_token) = &state.config.admin_token else {
+        return Some(StatusCode::NOT_FOUND);
+    };
+    let provided = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    // Compared in constant time so response timing doesn't reveal how much of a guess matched
+    let matches: bool = provided.as_bytes().ct_eq(admin_token.as_bytes()).into();
+    (!matches).then_some(StatusCode::UNAUTHORIZED)
+}
+
+pub async fn vacuum(State(state): State<Arc<AppState>>, headers: HeaderMap) -> AppResult<Response> {
+    if let Some(status) = admin_rejection(&state, &headers) {
+        return Ok(status.into_response());
+    }
+
+    let started = std::time::Instant::now();
+    let reclaimed_bytes = crate::db::vacuum(state.cache.db()).await?;
+    info!(reclaimed_bytes, elapsed_ms = started.elapsed().as_millis(), "vacuumed database");
+
+    Ok(Json(VacuumJson { reclaimed_bytes }).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompareQuery {
+    #[serde(default)]
+    user_a: String,
+    #[serde(default)]
+    user_b: String,
+    #[serde(default)]
+    country: String,
+}
+
+/// Films two members both want to see, with release dates for the shared ones, and the films
+/// only one of them has. Without both usernames it shows the form.
+pub async fn compare(
+    State(state): State<Arc<AppState>>,
+    jar: CookieJar,
+    Query(q): Query<CompareQuery>,
+) -> AppResult<Response> {
+    let forced_country = state.config.force_country.as_deref();
+    if q.user_a.trim().is_empty() || q.user_b.trim().is_empty() {
+        let country = match q.country.trim() {
+            "" => jar.get("country").map(|c| c.value().to_string()),
+            country => Some(country.to_uppercase()),
+        };
+        let html = templates::compare_form_page(
+            q.user_a.trim(),
+            q.user_b.trim(),
+            country.as_deref(),
+            forced_country,
+        );
+        return Ok(Html(html).into_response());
+    }
+
+    let source_a = requested_source(&q.user_a)?;
+    let source_b = requested_source(&q.user_b)?;
+    let country = requested_country(&state, &q.country)?;
+
+    let _permit = match state.process_queue.acquire(PROCESS_QUEUE_WAIT).await {
+        Ok(permit) => permit,
+        Err(position) => {
+            info!(position, "process queue full");
+            let message = "Timeboxd is busy right now. Try again in a moment.".to_string();
+            let mut resp = Html(templates::error_page(message)).into_response();
+            *resp.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+            resp.headers_mut().insert("retry-after", HeaderValue::from(PROCESS_QUEUE_RETRY_SECS));
+            return Ok(resp);
+        },
+    };
+
+    info!(user_a = %source_a.display_name(), user_b = %source_b.display_name(), country = %country, "comparing watchlists");
+
+    let today = today_in("UTC");
+    let cutoff_year = today.year().saturating_sub(3);
+    let fetch = |source| {
+        crate::scraper::fetch_watchlist(
+            &state.http,
+            source,
+            state.config.letterboxd_delay_ms,
+            cutoff_year,
+            state.config.max_watchlist_films,
+            WatchlistOrder::Release,
+        )
+    };
+    let (watchlist_a, watchlist_b) = futures::try_join!(fetch(&source_a), fetch(&source_b))?;
+    // Films missing from an unfinished watchlist would show up as only on the other one
+    let (watchlist_a, watchlist_b) = (watchlist_a.complete()?, watchlist_b.complete()?);
+    let comparison = crate::processor::compare_watchlists(watchlist_a.films, watchlist_b.films);
+
+    let shared = if comparison.shared.is_empty() {
+        Vec::new()
+    } else {
+        let outcome = crate::processor::process(
+            &state.http,
+            &state.cache,
+            &*state.tmdb,
+            comparison.shared,
+            country,
+            crate::processor::ProcessOptions {
+                today,
+                ..crate::processor::ProcessOptions::from_config(&state.config)
+            },
+            false,
+        )
+        .await?;
+        match outcome {
+            ProcessOutcome::Films(films) | ProcessOutcome::Partial(films) => films,
+            ProcessOutcome::DryRun(_) => Vec::new(),
+        }
+    };
+
+    // Titles of films only one member has come from the film cache when they've been seen before
+    let unique_slugs: Vec<String> = comparison
+        .only_a
+        .iter()
+        .chain(comparison.only_b.iter())
+        .map(|f| f.letterboxd_slug.clone())
+        .collect();
+    let cached = state.cache.get_films(&unique_slugs).await?;
+    let listed = |films: Vec<crate::models::WishlistFilm>| -> Vec<templates::ListedFilm> {
+        films
+            .into_iter()
+            .map(|film| {
+                let cached = cached.get(&film.letterboxd_slug);
+                templates::ListedFilm {
+                    title: cached.map(|c| c.title.clone()).unwrap_or_else(|| {
+                        crate::processor::title_from_slug(&film.letterboxd_slug)
+                    }),
+                    year: film.year.or_else(|| cached.and_then(|c| c.year.map(|y| y as i16))),
+                    slug: film.letterboxd_slug,
+                }
+            })
+            .collect()
+    };
+    let only_a = listed(comparison.only_a);
+    let only_b = listed(comparison.only_b);
+
+    Ok(Html(templates::compare_page(
+        (&source_a, &source_b),
+        &country,
+        &shared,
+        (&only_a, &only_b),
+        today,
+        &state.config,
+    ))
+    .into_response())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReportQuery {
+    slug: String,
+    tmdb_id: i32,
+}
+
+pub async fn report(State(state): State<Arc<AppState>>, Query(q): Query<ReportQuery>) -> Response {
+    let slug = q.slug.trim().to_string();
+
+    let result = async {
+        if slug.is_empty() {
+            anyhow::bail!("slug is required");
+        }
+        if state.cache.record_match_report(&slug, q.tmdb_id).await? {
+            info!(slug = %slug, tmdb_id = q.tmdb_id, "recorded wrong match report");
+        }
+        Ok::<_, anyhow::Error>(())
+    }
+    .await;
+
+    let message = match result {
+        Ok(()) => "Thanks, we'll take a look.",
+        Err(err) => {
+            error!(slug = %slug, error = %err, "match report failed");
+            "Couldn't send the report. Please try again."
+        },
+    };
+
+    let mut resp = Html(templates::report_ack_fragment(&slug, message)).into_response();
+    if let Ok(value) = HeaderValue::from_str(&format!("#{}", templates::report_button_id(&slug))) {
+        resp.headers_mut().insert("datastar-selector", value);
+    }
+    resp.headers_mut().insert("datastar-mode", HeaderValue::from_static("outer"));
+    resp
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MatchOverrideForm {
+    slug: String,
+    tmdb_id: i32,
+}
+
+pub async fn match_override(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Form(form): Form<MatchOverrideForm>,
+) -> AppResult<Response> {
+    if let Some(status) = admin_rejection(&state, &headers) {
+        return Ok(status.into_response());
+    }
+
+    let slug = form.slug.trim();
+    if slug.is_empty() {
+        return Err(anyhow::anyhow!("slug is required").into());
+    }
+
+    let previous = state.cache.get_override(slug).await?;
+    state.cache.put_override(slug, form.tmdb_id).await?;
+    state.results_cache.clear();
+    info!(slug, tmdb_id = form.tmdb_id, previous = ?previous, "stored film override");
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forced_country_fills_in_and_rejects_other_codes() {
+        let forced = Some(CountryCode::parse("GB").unwrap());
+
+        assert_eq!(pick_country(forced, "").unwrap().as_str(), "GB");
+        assert_eq!(pick_country(forced, " gb ").unwrap().as_str(), "GB");
+        assert!(pick_country(forced, "NZ").is_err());
+        assert!(pick_country(forced, "XX").is_err());
+        assert_eq!(pick_country(None, "NZ").unwrap(), CountryCode::NZ);
+        assert!(pick_country(None, "").is_err());
+    }
+}
@@ -4,40 +4,101 @@ use std::{
 };
 
 use scraper::{Html, Selector};
-use tracing::debug;
+use tracing::{debug, warn};
 use wreq::header::REFERER;
 
-use crate::{error::AppResult, models::WishlistFilm};
+use crate::{
+    error::AppResult,
+    models::{FilmSource, Watchlist, WatchlistOrder, WishlistFilm},
+    sources::FilmResolver,
+};
+
+/// Reads a pasted Letterboxd link: a profile or watchlist URL tracks that member's watchlist, a
+/// list URL tracks the list. Returns `None` for other Letterboxd pages and non-Letterboxd input.
+pub fn parse_letterboxd_url(input: &str) -> Option<FilmSource> {
+    let input = input.trim();
+    let rest =
+        input.strip_prefix("https://").or_else(|| input.strip_prefix("http://")).unwrap_or(input);
+    let path = rest.strip_prefix("www.").unwrap_or(rest).strip_prefix("letterboxd.com/")?;
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    // Copied links may have non-ASCII segments percent-encoded
+    let decoded: Vec<String> = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(|s| urlencoding::decode(s).map(|d| d.into_owned()).unwrap_or_else(|_| s.to_string()))
+        .collect();
+    let segments: Vec<&str> = decoded.iter().map(String::as_str).collect();
+
+    let valid = |s: &str| s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    match segments.as_slice() {
+        [username] | [username, "watchlist", ..] if valid(username) => {
+            Some(FilmSource::Watchlist(username.to_string()))
+        },
+        [username, "list", slug, ..] if valid(username) && valid(slug) => {
+            Some(FilmSource::List { username: username.to_string(), slug: slug.to_string() })
+        },
+        _ => None,
+    }
+}
+
+/// Letterboxd page for a film, with the slug percent-encoded.
+pub fn film_url(slug: &str) -> String {
+    format!("https://letterboxd.com/film/{}/", urlencoding::encode(slug))
+}
+
+/// Base of a watchlist's or list's paged URLs, with each path segment percent-encoded.
+fn source_pages_url(source: &FilmSource) -> String {
+    match source {
+        FilmSource::Watchlist(username) => {
+            format!("https://letterboxd.com/{}/watchlist", urlencoding::encode(username))
+        },
+        FilmSource::List { username, slug } => format!(
+            "https://letterboxd.com/{}/list/{}",
+            urlencoding::encode(username),
+            urlencoding::encode(slug)
+        ),
+    }
+}
 
 pub async fn fetch_watchlist(
     client: &wreq::Client,
-    username: &str,
+    source: &FilmSource,
     delay_ms: u64,
     cutoff_year: i16,
-) -> AppResult<Vec<WishlistFilm>> {
-    debug!(username = %username, cutoff_year = cutoff_year, "fetching watchlist");
+    max_films: usize,
+    order: WatchlistOrder,
+) -> AppResult<Watchlist> {
+    let username = source.username();
+    debug!(username = %username, source = ?source, cutoff_year = cutoff_year, max_films = max_films, order = ?order, "fetching watchlist");
+
+    let base_url = source_pages_url(source);
 
     let mut out = Vec::new();
     let mut seen = HashSet::new();
+    let mut truncated = false;
+    let mut interrupted = None;
+    let mut jitter = PageJitter::from_clock();
+    let is_recent = |f: &WishlistFilm| f.year.is_none_or(|y| y >= cutoff_year);
 
     let mut page = 1;
 
     loop {
         let url = if page == 1 {
-            format!("https://letterboxd.com/{}/watchlist/by/release/", username)
+            format!("{base_url}/by/{}/", order.as_query())
         } else {
-            format!("https://letterboxd.com/{}/watchlist/by/release/page/{}/", username, page)
+            format!("{base_url}/by/{}/page/{page}/", order.as_query())
         };
 
         debug!(page = page, "fetching watchlist page");
-        let html = client
-            .get(&url)
-            .header(REFERER, "https://letterboxd.com/")
-            .send()
-            .await?
-            .error_for_status()?
-            .text()
-            .await?;
+        let html = match fetch_watchlist_page(client, &url).await {
+            Ok(html) => html,
+            Err(err) if out.is_empty() => return Err(err),
+            Err(err) => {
+                warn!(username = %username, page = page, films = out.len(), error = %err, "watchlist page failed, keeping the pages already read");
+                interrupted = Some(err);
+                break;
+            },
+        };
 
         let films = parse_watchlist_page(&html)?;
         debug!(page = page, films_found = films.len(), "parsed watchlist page");
@@ -46,7 +107,9 @@ pub async fn fetch_watchlist(
             break;
         }
 
-        let all_old = films.iter().all(|f| f.year.map(|y| y < cutoff_year).unwrap_or(false));
+        // Only a release-ordered watchlist has nothing recent after a page of old films
+        let all_old = order == WatchlistOrder::Release
+            && films.iter().all(|f| f.year.map(|y| y < cutoff_year).unwrap_or(false));
 
         for film in films {
             if seen.insert(film.letterboxd_slug.clone()) {
@@ -58,22 +121,57 @@ pub async fn fetch_watchlist(
             break;
         }
 
+        if out.iter().filter(|f| is_recent(f)).count() >= max_films {
+            debug!(username = %username, max_films = max_films, "watchlist cap reached");
+            out.retain(|f| is_recent(f));
+            truncated = true;
+            out.truncate(max_films);
+            break;
+        }
+
         page += 1;
-        let delay = delay_ms + jitter_ms(150);
-        tokio::time::sleep(Duration::from_millis(delay)).await;
+        tokio::time::sleep(jitter.delay(delay_ms)).await;
     }
 
-    debug!(username = %username, total_films = out.len(), "completed watchlist fetch");
-    Ok(out)
+    debug!(username = %username, total_films = out.len(), truncated = truncated, "completed watchlist fetch");
+    Ok(Watchlist { films: out, truncated, interrupted })
+}
+
+/// Attempts per watchlist page. Only connection failures and server errors are retried, so a
+/// missing profile still fails straight away.
+const PAGE_ATTEMPTS: u32 = 2;
+const PAGE_RETRY_DELAY_MS: u64 = 1000;
+
+async fn fetch_watchlist_page(client: &wreq::Client, url: &str) -> AppResult<String> {
+    let mut attempt = 1;
+    loop {
+        let result = client.get(url).header(REFERER, "https://letterboxd.com/").send().await;
+        let retryable = match &result {
+            Ok(resp) => resp.status().is_server_error(),
+            Err(_) => true,
+        };
+        if retryable && attempt < PAGE_ATTEMPTS {
+            debug!(url = %url, attempt = attempt, "retrying watchlist page");
+            attempt += 1;
+            tokio::time::sleep(Duration::from_millis(PAGE_RETRY_DELAY_MS)).await;
+            continue;
+        }
+
+        return Ok(result?.error_for_status()?.text().await?);
+    }
 }
 
 fn parse_watchlist_page(html: &str) -> AppResult<Vec<WishlistFilm>> {
     let doc = Html::parse_document(html);
-    let selector = Selector::parse("li.griditem div.react-component[data-item-slug]").unwrap();
+    // Watchlists render `griditem`s, lists render `posteritem`s around the same poster component
+    let item_selector = Selector::parse("li.griditem, li.posteritem").unwrap();
+    let film_selector = Selector::parse("div.react-component[data-item-slug]").unwrap();
+    let liked_selector = Selector::parse(".icon-liked, .like.liked-micro").unwrap();
 
     let mut out = Vec::new();
 
-    for el in doc.select(&selector) {
+    for item in doc.select(&item_selector) {
+        let Some(el) = item.select(&film_selector).next() else { continue };
         let slug = el.value().attr("data-item-slug");
         let title = el.value().attr("data-item-name");
         let Some(slug) = slug else { continue };
@@ -81,19 +179,47 @@ fn parse_watchlist_page(html: &str) -> AppResult<Vec<WishlistFilm>> {
 
         let year = parse_year_from_title(title);
 
-        out.push(WishlistFilm { letterboxd_slug: slug.to_string(), year });
+        // Rating and like data are only rendered when the owner exposes them, in half stars
+        let rating = item
+            .value()
+            .attr("data-owner-rating")
+            .and_then(|r| r.parse::<u8>().ok())
+            .filter(|r| (1..=10).contains(r));
+        let liked = item.select(&liked_selector).next().is_some();
+
+        out.push(WishlistFilm { letterboxd_slug: slug.to_string(), year, rating, liked });
     }
 
     Ok(out)
 }
 
-fn jitter_ms(max: u64) -> u64 {
-    if max == 0 {
-        return 0;
+/// Spreads the pauses between watchlist pages over ±25% of the configured delay, so page
+/// requests don't arrive at a fixed interval. Seeded once per fetch; the same seed gives the
+/// same delays.
+struct PageJitter(u64);
+
+impl PageJitter {
+    fn from_clock() -> Self {
+        let nanos =
+            SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+        Self::with_seed(nanos)
+    }
+
+    fn with_seed(seed: u64) -> Self {
+        // xorshift never leaves a zero state
+        Self(seed.max(1))
+    }
+
+    fn delay(&mut self, base_ms: u64) -> Duration {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+
+        let spread = base_ms / 4;
+        Duration::from_millis(base_ms - spread + x % (2 * spread + 1))
     }
-    let nanos =
-        SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos() as u64).unwrap_or(0);
-    nanos % (max + 1)
 }
 
 fn parse_year_from_title(title: &str) -> Option<i16> {
@@ -118,22 +244,29 @@ pub struct LetterboxdFilmData {
     pub title: String,
     pub year: Option<i16>,
     pub tmdb_id: Option<i32>,
+    /// The film's current slug when the requested one redirected to a renamed page
+    pub canonical_slug: Option<String>,
 }
 
 pub async fn fetch_letterboxd_film_data(
     client: &wreq::Client,
     slug: &str,
 ) -> AppResult<LetterboxdFilmData> {
-    let url = format!("https://letterboxd.com/film/{}/", slug);
+    let url = film_url(slug);
     debug!(slug = %slug, "fetching Letterboxd film page");
-    let html = client
+    let response = client
         .get(&url)
         .header(REFERER, "https://letterboxd.com/")
         .send()
         .await?
-        .error_for_status()?
-        .text()
-        .await?;
+        .error_for_status()?;
+
+    let canonical_slug = film_slug_from_path(response.uri().path()).filter(|s| s != slug);
+    if let Some(canonical) = &canonical_slug {
+        debug!(slug = %slug, canonical_slug = %canonical, "Letterboxd film page redirected");
+    }
+
+    let html = response.text().await?;
 
     let doc = Html::parse_document(&html);
 
@@ -169,7 +302,20 @@ pub async fn fetch_letterboxd_film_data(
 
     debug!(slug = %slug, title = %title, year = ?year, tmdb_id = ?tmdb_id, "parsed Letterboxd film data");
 
-    Ok(LetterboxdFilmData { title: title.to_string(), year, tmdb_id })
+    Ok(LetterboxdFilmData { title: title.to_string(), year, tmdb_id, canonical_slug })
+}
+
+fn film_slug_from_path(path: &str) -> Option<String> {
+    let slug = path.strip_prefix("/film/")?.split('/').next()?;
+    // Stored decoded, like slugs scraped from watchlist pages
+    let slug = urlencoding::decode(slug).ok()?;
+    (!slug.is_empty()).then(|| slug.into_owned())
+}
+
+impl FilmResolver for wreq::Client {
+    async fn fetch_film_data(&self, slug: &str) -> AppResult<LetterboxdFilmData> {
+        fetch_letterboxd_film_data(self, slug).await
+    }
 }
 
 fn extract_tmdb_id_from_url(url: &str) -> Option<i32> {
@@ -192,3 +338,71 @@ fn parse_title_and_year(title_with_year: &str) -> (&str, Option<i16>) {
         (trimmed, None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redirected_path_gives_the_canonical_slug() {
+        assert_eq!(film_slug_from_path("/film/new-name-2025/").as_deref(), Some("new-name-2025"));
+        assert_eq!(film_slug_from_path("/film/new-name-2025").as_deref(), Some("new-name-2025"));
+        assert_eq!(film_slug_from_path("/film/am%C3%A9lie/").as_deref(), Some("amélie"));
+    }
+
+    #[test]
+    fn page_jitter_stays_within_a_quarter_of_the_base() {
+        for seed in [0, 1, 42, u64::MAX] {
+            let mut jitter = PageJitter::with_seed(seed);
+            for _ in 0..1_000 {
+                let delay = jitter.delay(1000).as_millis();
+                assert!((750..=1250).contains(&delay), "seed {seed} gave {delay}ms");
+            }
+        }
+    }
+
+    #[test]
+    fn page_jitter_is_deterministic_per_seed() {
+        let delays = |seed| {
+            let mut jitter = PageJitter::with_seed(seed);
+            (0..20).map(|_| jitter.delay(1000)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(delays(7), delays(7));
+        assert_ne!(delays(7), delays(8));
+        assert_eq!(PageJitter::with_seed(3).delay(0), Duration::ZERO);
+    }
+
+    #[test]
+    fn non_film_paths_have_no_slug() {
+        assert_eq!(film_slug_from_path("/film/"), None);
+        assert_eq!(film_slug_from_path("/someone/watchlist/"), None);
+    }
+
+    #[test]
+    fn unusual_usernames_and_slugs_are_percent_encoded() {
+        assert_eq!(film_url("amélie"), "https://letterboxd.com/film/am%C3%A9lie/");
+        assert_eq!(film_url("what?-2025"), "https://letterboxd.com/film/what%3F-2025/");
+        assert_eq!(
+            source_pages_url(&FilmSource::Watchlist("jürgen_k".to_string())),
+            "https://letterboxd.com/j%C3%BCrgen_k/watchlist"
+        );
+        assert_eq!(
+            source_pages_url(&FilmSource::List {
+                username: "ümit".to_string(),
+                slug: "café-noir".to_string(),
+            }),
+            "https://letterboxd.com/%C3%BCmit/list/caf%C3%A9-noir"
+        );
+    }
+
+    #[test]
+    fn encoded_source_links_parse_back() {
+        for source in [
+            FilmSource::Watchlist("jürgen_k".to_string()),
+            FilmSource::List { username: "ümit".to_string(), slug: "café-noir".to_string() },
+        ] {
+            assert_eq!(parse_letterboxd_url(&source.letterboxd_url()), Some(source));
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Seams between the processor and its network data sources, so processing can run against
+//! canned data instead of TMDB and Letterboxd.
+
+use std::{collections::HashMap, future::Future};
+
+use crate::{
+    countries::CountryCode,
+    error::AppResult,
+    models::{ReleaseDatesResult, WatchProvider},
+    scraper::LetterboxdFilmData,
+    tmdb::{MovieSummary, SearchMatch},
+};
+
+/// Film metadata, release dates and watch providers, implemented by `TmdbClient`.
+pub trait ReleaseSource: Send + Sync {
+    /// Requests per second the source allows, used to size concurrent fetches.
+    fn rps(&self) -> u32;
+
+    fn search_movie(
+        &self,
+        title: &str,
+        year: Option<i16>,
+    ) -> impl Future<Output = AppResult<Option<SearchMatch>>> + Send;
+
+    fn get_movie_details(
+        &self,
+        tmdb_id: i32,
+    ) -> impl Future<Output = AppResult<MovieSummary>> + Send;
+
+    fn get_release_dates(
+        &self,
+        tmdb_id: i32,
+        country: CountryCode,
+    ) -> impl Future<Output = AppResult<ReleaseDatesResult>> + Send;
+
+    fn get_watch_providers(
+        &self,
+        tmdb_id: i32,
+        country: CountryCode,
+    ) -> impl Future<Output = AppResult<(Vec<WatchProvider>, Option<String>)>> + Send;
+
+    /// Watch providers for many films in one region, keyed by TMDB ID. Films whose lookup
+    /// failed are missing from the map.
+    fn get_watch_providers_bulk(
+        &self,
+        tmdb_ids: &[i32],
+        country: CountryCode,
+        concurrency: usize,
+    ) -> impl Future<Output = AppResult<HashMap<i32, Vec<WatchProvider>>>> + Send;
+}
+
+/// Letterboxd film page data for a slug, implemented by the scraper's HTTP client.
+pub trait FilmResolver: Send + Sync {
+    fn fetch_film_data(
+        &self,
+        slug: &str,
+    ) -> impl Future<Output = AppResult<LetterboxdFilmData>> + Send;
+}
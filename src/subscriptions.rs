@@ -0,0 +1,411 @@
+use std::{collections::HashMap, net::IpAddr, sync::Arc, time::Duration};
+
+use anyhow::Context;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::{
+    AppState,
+    config::Config,
+    countries::CountryCode,
+    entities::subscriptions,
+    error::AppResult,
+    models::{AvailabilityChange, FilmAvailability, FilmSource, WatchlistOrder, today_in},
+    processor::{self, ProcessOutcome},
+};
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    username: &'a str,
+    country: &'a str,
+    /// Results page for this watchlist
+    results_url: String,
+    changes: &'a [AvailabilityChange],
+}
+
+/// Sent once when subscribing. Notifications only start after `confirm_url` is opened, so a
+/// webhook only gets notifications its owner asked for.
+#[derive(Serialize)]
+struct ConfirmationPayload<'a> {
+    username: &'a str,
+    country: &'a str,
+    confirm_url: String,
+}
+
+pub fn enabled(config: &Config) -> bool {
+    config.webhook_check_hours > 0
+}
+
+/// Records a subscription and pings its webhook with a confirmation link, returning whether
+/// it was already confirmed by an earlier request.
+pub async fn subscribe(
+    state: &AppState,
+    username: &str,
+    country: CountryCode,
+    webhook_url: &str,
+) -> AppResult<bool> {
+    check_webhook_url(webhook_url).await?;
+
+    let model = subscriptions::ActiveModel {
+        username: Set(username.to_string()),
+        country: Set(country.to_string()),
+        webhook_url: Set(webhook_url.to_string()),
+        snapshot: Set(None),
+        created_at: Set(jiff::Timestamp::now().as_second()),
+        checked_at: Set(None),
+        token: Set(new_token()?),
+        confirmed: Set(false),
+        ..Default::default()
+    };
+
+    subscriptions::Entity::insert(model)
+        .on_conflict(
+            sea_orm::sea_query::OnConflict::columns([
+                subscriptions::Column::Username,
+                subscriptions::Column::Country,
+                subscriptions::Column::WebhookUrl,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .do_nothing()
+        .exec(state.cache.db())
+        .await?;
+
+    let sub = subscriptions::Entity::find()
+        .filter(subscriptions::Column::Username.eq(username))
+        .filter(subscriptions::Column::Country.eq(country.as_str()))
+        .filter(subscriptions::Column::WebhookUrl.eq(webhook_url))
+        .one(state.cache.db())
+        .await?
+        .context("subscription missing after insert")?;
+    if sub.confirmed {
+        return Ok(true);
+    }
+
+    // Subscriptions from before confirmation existed have no token yet
+    let token = if sub.token.is_empty() {
+        let token = new_token()?;
+        let mut model: subscriptions::ActiveModel = sub.into();
+        model.token = Set(token.clone());
+        model.update(state.cache.db()).await?;
+        token
+    } else {
+        sub.token
+    };
+
+    let payload = ConfirmationPayload {
+        username,
+        country: country.as_str(),
+        confirm_url: state.public_url(&format!("/subscribe/confirm?token={token}")),
+    };
+    state
+        .webhook_http
+        .post(webhook_url)
+        .json(&payload)
+        .send()
+        .await
+        .and_then(wreq::Response::error_for_status)
+        .context("webhook didn't accept the confirmation")?;
+
+    info!(username = %username, country = %country, "subscription created, awaiting confirmation");
+    Ok(false)
+}
+
+/// Marks the subscription holding `token` as confirmed, so its checks start.
+pub async fn confirm(state: &AppState, token: &str) -> AppResult<subscriptions::Model> {
+    let sub = match token {
+        "" => None,
+        token => {
+            subscriptions::Entity::find()
+                .filter(subscriptions::Column::Token.eq(token))
+                .one(state.cache.db())
+                .await?
+        },
+    }
+    .context("subscription not found")?;
+
+    let mut model: subscriptions::ActiveModel = sub.into();
+    model.confirmed = Set(true);
+    let sub = model.update(state.cache.db()).await?;
+
+    info!(username = %sub.username, country = %sub.country, "subscription confirmed");
+    Ok(sub)
+}
+
+fn new_token() -> anyhow::Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(|err| anyhow::anyhow!("no randomness for token: {err}"))?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Rejects webhook URLs whose host resolves to this machine or a private network, so
+/// subscriptions can't be used to reach internal services.
+pub async fn check_webhook_url(url: &str) -> anyhow::Result<()> {
+    let (host, port) = webhook_host(url).context("webhook URL must point to a public address")?;
+    let addrs: Vec<_> = tokio::net::lookup_host((host, port))
+        .await
+        .context("webhook URL must point to a public address")?
+        .collect();
+
+    if addrs.is_empty() || !addrs.iter().all(|addr| is_public(addr.ip())) {
+        anyhow::bail!("webhook URL must point to a public address");
+    }
+    Ok(())
+}
+
+/// Host and port of an `http(s)://` URL. User info and backslashes are refused rather than
+/// parsed, since clients disagree on where the host starts when they're present.
+fn webhook_host(url: &str) -> Option<(&str, u16)> {
+    let (rest, default_port) = match url.strip_prefix("https://") {
+        Some(rest) => (rest, 443),
+        None => (url.strip_prefix("http://")?, 80),
+    };
+    let authority = rest.split(['/', '?', '#']).next()?;
+    if authority.contains(['@', '\\']) || authority.chars().any(|c| c.is_whitespace()) {
+        return None;
+    }
+
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(v6) => {
+            let (host, after) = v6.split_once(']')?;
+            (host, after.strip_prefix(':'))
+        },
+        None => match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    let port = match port {
+        Some(port) => port.parse().ok()?,
+        None => default_port,
+    };
+
+    (!host.is_empty()).then_some((host, port))
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || v4.is_documentation()
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (b & 0xc0) == 64))
+        },
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_public(IpAddr::V4(v4)),
+            None => {
+                let first = v6.segments()[0];
+                !(v6.is_loopback()
+                    || v6.is_unspecified()
+                    || v6.is_multicast()
+                    // Unique local fc00::/7 and link-local fe80::/10
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            },
+        },
+    }
+}
+
+/// Periodically re-runs the pipeline for every subscription and posts availability changes
+/// to its webhook. Disabled unless `WEBHOOK_CHECK_HOURS` is set.
+pub fn spawn(state: Arc<AppState>) {
+    if !enabled(&state.config) {
+        return;
+    }
+
+    let interval = Duration::from_secs(state.config.webhook_check_hours * 60 * 60);
+    info!(interval_hours = state.config.webhook_check_hours, "webhook subscriptions enabled");
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+
+            let subs = match subscriptions::Entity::find()
+                .filter(subscriptions::Column::Confirmed.eq(true))
+                .all(state.cache.db())
+                .await
+            {
+                Ok(subs) => subs,
+                Err(err) => {
+                    warn!(error = %err, "failed to load subscriptions");
+                    continue;
+                },
+            };
+
+            for sub in subs {
+                if let Err(err) = check_subscription(&state, sub.clone()).await {
+                    warn!(
+                        username = %sub.username,
+                        country = %sub.country,
+                        error = %err,
+                        "subscription check failed"
+                    );
+                }
+            }
+        }
+    });
+}
+
+async fn check_subscription(state: &AppState, sub: subscriptions::Model) -> anyhow::Result<()> {
+    let today = today_in("UTC");
+
+    let watchlist = crate::scraper::fetch_watchlist(
+        &state.http,
+        &FilmSource::Watchlist(sub.username.clone()),
+        state.config.letterboxd_delay_ms,
+        today.year().saturating_sub(3),
+        state.config.max_watchlist_films,
+        WatchlistOrder::Release,
+    )
+    .await?
+    // Films on pages that weren't read would look removed from the watchlist
+    .complete()?;
+
+    // Partial runs may be missing details, so they're skipped rather than compared
+    let ProcessOutcome::Films(films) = processor::process(
+        &state.http,
+        &state.cache,
+        &*state.tmdb,
+        watchlist.films,
+        CountryCode::parse(&sub.country)?,
+        crate::processor::ProcessOptions::from_config(&state.config),
+        false,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+
+    let snapshot = processor::availability_snapshot(&films);
+
+    // The first check only records a baseline so subscribers aren't sent their whole watchlist
+    let previous =
+        match sub.snapshot.as_deref().map(serde_json::from_str::<HashMap<i32, FilmAvailability>>) {
+            Some(Ok(previous)) => Some(previous),
+            Some(Err(err)) => {
+                // Comparing against nothing would report every film as changed
+                warn!(
+                    username = %sub.username,
+                    country = %sub.country,
+                    error = %err,
+                    "unreadable subscription snapshot, recording a new baseline"
+                );
+                None
+            },
+            None => None,
+        };
+
+    if let Some(previous) = previous {
+        let changes = processor::availability_changes(&previous, &films);
+
+        if !changes.is_empty() {
+            let payload = WebhookPayload {
+                username: &sub.username,
+                country: &sub.country,
+                results_url: state.public_url(&format!(
+                    "/release-dates?username={}&country={}",
+                    urlencoding::encode(&sub.username),
+                    urlencoding::encode(&sub.country)
+                )),
+                changes: &changes,
+            };
+            // The address may have changed since the subscription was made
+            check_webhook_url(&sub.webhook_url).await?;
+            state
+                .webhook_http
+                .post(&sub.webhook_url)
+                .json(&payload)
+                .send()
+                .await?
+                .error_for_status()
+                .context("webhook rejected notification")?;
+
+            info!(
+                username = %sub.username,
+                country = %sub.country,
+                change_count = changes.len(),
+                "sent availability webhook"
+            );
+        }
+    }
+
+    let mut model: subscriptions::ActiveModel = sub.into();
+    model.snapshot = Set(Some(serde_json::to_string(&snapshot)?));
+    model.checked_at = Set(Some(jiff::Timestamp::now().as_second()));
+    model.update(state.cache.db()).await?;
+
+    Ok(())
+}
+
+pub async fn unsubscribe(
+    state: &AppState,
+    username: &str,
+    country: CountryCode,
+    webhook_url: &str,
+) -> AppResult<u64> {
+    let result = subscriptions::Entity::delete_many()
+        .filter(subscriptions::Column::Username.eq(username))
+        .filter(subscriptions::Column::Country.eq(country.as_str()))
+        .filter(subscriptions::Column::WebhookUrl.eq(webhook_url))
+        .exec(state.cache.db())
+        .await?;
+    Ok(result.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webhook_host_reads_host_and_port() {
+        assert_eq!(webhook_host("https://hooks.example.com/a?b"), Some(("hooks.example.com", 443)));
+        assert_eq!(webhook_host("http://example.com:8080"), Some(("example.com", 8080)));
+        assert_eq!(webhook_host("http://[::1]:9000/hook"), Some(("::1", 9000)));
+    }
+
+    #[test]
+    fn webhook_host_refuses_ambiguous_urls() {
+        assert_eq!(webhook_host("http://example.com@127.0.0.1/"), None);
+        assert_eq!(webhook_host("http://127.0.0.1\\@example.com/"), None);
+        assert_eq!(webhook_host("ftp://example.com/"), None);
+        assert_eq!(webhook_host("https:///path"), None);
+    }
+
+    #[test]
+    fn internal_addresses_are_not_public() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{ip} should be rejected");
+        }
+        assert!(is_public("93.184.216.34".parse().unwrap()));
+        assert!(is_public("2606:4700::1111".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn loopback_webhooks_are_rejected() {
+        assert!(check_webhook_url("http://localhost:3000/hook").await.is_err());
+        assert!(check_webhook_url("http://127.0.0.1/hook").await.is_err());
+    }
+}
@@ -1,75 +1,215 @@
+use std::sync::LazyLock;
+
 use hypertext::{Raw, maud, prelude::*};
 
 use crate::{
-    countries::{COUNTRIES, get_country_name},
+    config::Config,
+    countries::{COUNTRIES, CountryCode, get_country_flag_emoji, get_country_name},
+    image_cache::{ImageKind, image_url},
     models::{
-        FilmWithReleases, ProviderType, ReleaseCategory, ReleaseDate, ReleaseType, WatchProvider,
+        CountryProviders, FilmSource, FilmWithReleases, Layout, MatchConfidence, ProviderType,
+        ReleaseCategory, ReleaseColumn, ReleaseDate, ReleaseDateChange, ReleaseType, SortOrder,
+        WatchProvider, WatchlistOrder,
     },
+    processor::DryRunSummary,
+    scraper::film_url,
 };
 
 const TAILWIND_CDN: &str = "https://cdn.tailwindcss.com";
 const DATASTAR_CDN: &str =
     "https://cdn.jsdelivr.net/npm/@sudodevnull/datastar@0.19.9/dist/datastar.js";
 
-pub fn index_page(saved_username: Option<&str>, saved_country: Option<&str>) -> String {
+/// Window for the "releasing soon" count on the results page.
+const SOON_DAYS: i64 = 30;
+
+/// Months ahead offered by the "next months only" link on the results page.
+const DEFAULT_WINDOW_MONTHS: u32 = 6;
+
+/// The country list never changes, so its options are rendered once and reused. The saved
+/// country is marked selected client-side on load.
+static COUNTRY_OPTIONS: LazyLock<String> = LazyLock::new(|| {
+    maud! {
+        @for country in COUNTRIES {
+            div
+                id=(format!("country-option-{}", country.code))
+                role="option"
+                aria-selected="false"
+                class="country-option px-3 py-2 text-slate-200 hover:bg-slate-600 cursor-pointer focus:bg-orange-900 focus:outline-none"
+                data-code=(country.code)
+                data-name=(country.name)
+                tabindex="-1"
+            {
+                (country.name)
+            }
+        }
+    }
+    .render()
+    .into_inner()
+});
+
+pub fn index_page(
+    saved_username: Option<&str>,
+    saved_country: Option<&str>,
+    forced_country: Option<&str>,
+    mock_mode: bool,
+    nojs: bool,
+) -> String {
     let country_name = saved_country.map(get_country_name);
+    // The plain form doesn't call the country selector script
+    let script_handler = |handler: &'static str| (!nojs).then_some(handler);
 
     page(
         "Timeboxd - upcoming film releases from your Letterboxd watchlist",
         maud! {
             div class="min-h-screen bg-slate-900" {
                 div class="max-w-2xl mx-auto px-4 py-12 sm:px-6" {
+                    @if mock_mode {
+                        (mock_banner())
+                    }
                     div class="bg-slate-800 shadow-xl rounded-lg p-6 sm:p-8 border border-slate-700" {
                         h1 class="text-2xl sm:text-3xl font-bold text-slate-100" { "Timeboxd" }
                         p class="mt-2 text-slate-400" { "Upcoming film release dates for your Letterboxd watchlist." }
 
+                        @if !nojs {
+                            noscript {
+                                p class="mt-4 text-sm text-slate-400" {
+                                    "JavaScript is off. "
+                                    a class="text-orange-500 hover:text-orange-400" href="/?nojs=true" { "Use the basic form" }
+                                }
+                            }
+                        }
+
                         form class="mt-8 space-y-6" method="get" action="/release-dates" {
+                            @if nojs {
+                                input type="hidden" name="nojs" value="true";
+                            }
                             div {
-                                label class="block text-sm font-medium text-slate-300" for="username" { "Letterboxd username" }
+                                label class="block text-sm font-medium text-slate-300" for="username" { "Letterboxd username or list link" }
                                 input
                                     class="mt-2 w-full rounded-md border border-slate-600 bg-slate-700 text-slate-100 px-3 py-2 placeholder-slate-400 focus:border-orange-500 focus:outline-none focus:ring-1 focus:ring-orange-500"
                                     name="username"
                                     id="username"
                                     value=[saved_username]
-                                    oninput="validateForm()"
-                                    onkeydown="handleUsernameKeydown(event)";
+                                    required[nojs]
+                                    oninput=[script_handler("validateForm()")]
+                                    onkeydown=[script_handler("handleUsernameKeydown(event)")];
                             }
 
-                            div {
-                                label class="block text-sm font-medium text-slate-300" for="country-search" { "Country" }
-                                div class="relative mt-2" {
+                            @if let Some(code) = forced_country {
+                                div {
+                                    label class="block text-sm font-medium text-slate-300" for="country-search" { "Country" }
                                     input
                                         type="text"
                                         id="country-search"
-                                        autocomplete="off"
-                                        class="w-full rounded-md border border-slate-600 bg-slate-700 text-slate-100 px-3 py-2 placeholder-slate-400 focus:border-orange-500 focus:outline-none focus:ring-1 focus:ring-orange-500"
-                                        value=[country_name]
-                                        onkeyup="filterCountries()"
-                                        oninput="validateForm()"
-                                        onkeydown="handleCountryKeydown(event)"
-                                        onfocus="document.getElementById('country-dropdown').classList.remove('hidden')"
-                                        ;
-                                    input type="hidden" name="country" id="country" value=[saved_country];
-                                    div id="country-dropdown" class="hidden absolute z-10 mt-1 w-full bg-slate-700 border border-slate-600 rounded-md shadow-lg max-h-60 overflow-y-auto" {
+                                        class="mt-2 w-full rounded-md border border-slate-600 bg-slate-700 text-slate-400 px-3 py-2 cursor-not-allowed"
+                                        value=(get_country_name(code))
+                                        disabled;
+                                    input type="hidden" name="country" id="country" value=(code);
+                                    p class="mt-2 text-xs text-slate-500" { "This site only covers release dates for " (get_country_name(code)) "." }
+                                }
+                            } @else if nojs {
+                                div {
+                                    label class="block text-sm font-medium text-slate-300" for="country" { "Country" }
+                                    select
+                                        class="mt-2 w-full rounded-md border border-slate-600 bg-slate-700 text-slate-100 px-3 py-2 focus:border-orange-500 focus:outline-none focus:ring-1 focus:ring-orange-500"
+                                        name="country"
+                                        id="country"
+                                        required
+                                    {
+                                        option value="" { "Select a country" }
                                         @for country in COUNTRIES {
-                                            div
-                                                class="country-option px-3 py-2 text-slate-200 hover:bg-slate-600 cursor-pointer focus:bg-orange-900 focus:outline-none"
-                                                data-code=(country.code)
-                                                data-name=(country.name)
-                                                tabindex="-1"
-                                                onclick=(format!("selectCountry('{}', '{}')", country.code, country.name))
-                                            {
-                                                (country.name)
+                                            option value=(country.code) selected[saved_country == Some(country.code)] { (country.name) }
+                                        }
+                                    }
+                                }
+                            } @else {
+                                div {
+                                    label class="block text-sm font-medium text-slate-300" for="country-search" { "Country" }
+                                    div class="relative mt-2" {
+                                        input
+                                            type="text"
+                                            id="country-search"
+                                            role="combobox"
+                                            aria-autocomplete="list"
+                                            aria-expanded="false"
+                                            aria-controls="country-dropdown"
+                                            aria-activedescendant=""
+                                            autocomplete="off"
+                                            class="w-full rounded-md border border-slate-600 bg-slate-700 text-slate-100 px-3 py-2 placeholder-slate-400 focus:border-orange-500 focus:outline-none focus:ring-1 focus:ring-orange-500"
+                                            value=[country_name]
+                                            onkeyup="scheduleFilterCountries()"
+                                            oninput="validateForm()"
+                                            onkeydown="handleCountryKeydown(event)"
+                                            onfocus="openCountryDropdown()"
+                                            ;
+                                        input type="hidden" name="country" id="country" value=[saved_country];
+                                        div id="country-dropdown" role="listbox" aria-label="Countries" onclick="handleCountryClick(event)" class="hidden absolute z-10 mt-1 w-full bg-slate-700 border border-slate-600 rounded-md shadow-lg max-h-60 overflow-y-auto" {
+                                            (Raw::dangerously_create(COUNTRY_OPTIONS.as_str()))
+                                            div id="country-no-results" role="option" aria-disabled="true" class="hidden px-3 py-2 text-sm text-slate-400 italic" {
+                                                "No countries match"
                                             }
                                         }
                                     }
+                                    p class="mt-2 text-xs text-slate-500" { "Select a country to see release dates for that region." }
+                                }
+                            }
+
+                            div {
+                                label class="block text-sm font-medium text-slate-300" for="also" { "Also show (optional)" }
+                                input
+                                    class="mt-2 w-full rounded-md border border-slate-600 bg-slate-700 text-slate-100 px-3 py-2 placeholder-slate-400 focus:border-orange-500 focus:outline-none focus:ring-1 focus:ring-orange-500"
+                                    name="also"
+                                    id="also"
+                                    placeholder="GB, US"
+                                    autocomplete="off";
+                                p class="mt-2 text-xs text-slate-500" { "Up to 3 more country codes to show side by side, without fallbacks." }
+                            }
+
+                            div {
+                                label class="block text-sm font-medium text-slate-300" for="compare_providers" { "Compare streaming with (optional)" }
+                                input
+                                    class="mt-2 w-full rounded-md border border-slate-600 bg-slate-700 text-slate-100 px-3 py-2 placeholder-slate-400 focus:border-orange-500 focus:outline-none focus:ring-1 focus:ring-orange-500"
+                                    name="compare_providers"
+                                    id="compare_providers"
+                                    placeholder="US"
+                                    maxlength="2"
+                                    autocomplete="off";
+                                p class="mt-2 text-xs text-slate-500" { "A country code to compare subscription services with, for films already out." }
+                            }
+
+                            div {
+                                label class="block text-sm font-medium text-slate-300" for="order" { "Read watchlist by" }
+                                select
+                                    class="mt-2 w-full rounded-md border border-slate-600 bg-slate-700 text-slate-100 px-3 py-2 focus:border-orange-500 focus:outline-none focus:ring-1 focus:ring-orange-500"
+                                    name="order"
+                                    id="order" {
+                                    option value="release" selected { "Release date" }
+                                    option value="added" { "When added, newest first" }
+                                    option value="added-earliest" { "When added, oldest first" }
+                                    option value="rating" { "Average rating" }
+                                    option value="popular" { "Popularity" }
                                 }
-                                p class="mt-2 text-xs text-slate-500" { "Select a country to see release dates for that region." }
+                                p class="mt-2 text-xs text-slate-500" { "Decides which films are kept when a long watchlist is cut short." }
+                            }
+
+                            div class="flex items-center gap-2" {
+                                input
+                                    class="h-4 w-4 rounded border-slate-600 bg-slate-700 accent-orange-600"
+                                    type="checkbox"
+                                    name="forget"
+                                    id="forget"
+                                    value="true";
+                                label class="text-sm text-slate-400" for="forget" { "Don't remember me on this device" }
                             }
 
-                             button id="submit-button" class="w-full rounded-md bg-orange-600 px-4 py-2 font-semibold text-white hover:bg-orange-700 focus:outline-none focus:ring-1 focus:ring-orange-500 disabled:opacity-50 disabled:cursor-not-allowed" type="submit" disabled { "Find release dates" }
+                             button id="submit-button" class="w-full rounded-md bg-orange-600 px-4 py-2 font-semibold text-white hover:bg-orange-700 focus:outline-none focus:ring-1 focus:ring-orange-500 disabled:opacity-50 disabled:cursor-not-allowed" type="submit" { "Find release dates" }
+                        }
+                        @if !nojs {
+                            (country_selector_script())
+                        }
+                        p class="mt-6 text-sm text-slate-500" {
+                            a class="text-orange-500 hover:text-orange-400" href="/compare" { "Compare two watchlists" }
                         }
-                        (country_selector_script())
                     }
                 }
             }
@@ -77,12 +217,10 @@ pub fn index_page(saved_username: Option<&str>, saved_country: Option<&str>) ->
     )
 }
 
-pub fn processing_page(username: &str, country: &str) -> String {
-    let url = format!(
-        "/process?username={}&country={}",
-        urlencoding::encode(username),
-        urlencoding::encode(country)
-    );
+pub fn processing_page(source: &FilmSource, country: &str, view: &ResultsView) -> String {
+    let username = source.display_name();
+    let url = view.process_url(source, country);
+    let nojs_url = ResultsView { nojs: true, ..view.clone() }.process_url(source, country);
 
     page(
         &format!("Upcoming film releases for {username} - Timeboxd"),
@@ -94,36 +232,290 @@ pub fn processing_page(username: &str, country: &str) -> String {
                         h1 class="mt-6 text-xl font-semibold text-slate-100" { "Processing" }
                         p class="mt-2 text-slate-400" { "Fetching watchlist and checking release dates." }
                         p class="mt-2 text-sm text-slate-500" { "This may take a minute for large watchlists." }
+                        noscript {
+                            p class="mt-4" {
+                                a class="text-orange-500 hover:text-orange-400" href=(nojs_url) { "Show results" }
+                            }
+                        }
                     }
                 }
             }
             script { (Raw::dangerously_create(format!("
-                fetch('{}')
-                    .then(response => response.text())
-                    .then(html => {{
-                        document.getElementById('content').innerHTML = html;
-                        document.title = 'Upcoming film releases for {} - Timeboxd';
-                    }})
-                    .catch(error => {{
-                        document.getElementById('content').innerHTML = '<div class=\"bg-slate-800 shadow-xl rounded-lg p-8 border border-slate-700\"><h1 class=\"text-2xl font-bold text-slate-100\">Error</h1><p class=\"mt-4 text-slate-400\">' + error.message + '</p></div>';
-                    }});
+                function load() {{
+                    const tz = Intl.DateTimeFormat().resolvedOptions().timeZone || '';
+                    fetch('{}&tz=' + encodeURIComponent(tz))
+                        .then(response => response.text().then(html => {{
+                            document.getElementById('content').innerHTML = html;
+                            // Busy server: the response says where we are in the queue, so retry
+                            if (response.status === 503 && response.headers.has('x-queue-position')) {{
+                                const retrySecs = Number(response.headers.get('retry-after')) || 2;
+                                setTimeout(load, retrySecs * 1000);
+                            }} else {{
+                                const soon = document.querySelector('[data-soon-count]');
+                                const prefix = soon ? '(' + soon.dataset.soonCount + ' soon) ' : '';
+                                document.title = prefix + 'Upcoming film releases for {} - Timeboxd';
+                                restoreSections();
+                            }}
+                        }}))
+                        .catch(error => {{
+                            document.getElementById('content').innerHTML = '<div class=\"bg-slate-800 shadow-xl rounded-lg p-8 border border-slate-700\"><h1 class=\"text-2xl font-bold text-slate-100\">Error</h1><p class=\"mt-4 text-slate-400\">' + error.message + '</p></div>';
+                        }});
+                }}
+                load();
             ", url, username))) }
+            (results_script())
+        },
+    )
+}
+
+/// Results as a whole page, for `/process` requests that a browser opened directly.
+pub fn results_page(username: &str, content: &str) -> String {
+    page(
+        &format!("Upcoming film releases for {username} - Timeboxd"),
+        maud! {
+            div class="min-h-screen bg-slate-900" {
+                (Raw::dangerously_create(content))
+            }
+            (results_script())
         },
     )
 }
 
-pub fn results_fragment(username: &str, country: &str, films: &[FilmWithReleases]) -> String {
+/// Helpers for the results page. Defined on the processing page because results are swapped in
+/// with `innerHTML`, which doesn't run their `<script>` tags.
+fn results_script() -> impl Renderable {
+    maud! {
+        script {
+            (Raw::dangerously_create(r#"
+                // Hides upcoming films whose first upcoming date is past the chip's window:
+                // `week`, `month`, a number of days, or empty for no filter
+                function filterUpcoming(chip) {
+                    const range = chip.dataset.window;
+                    const today = new Date();
+                    today.setHours(0, 0, 0, 0);
+
+                    let end = null;
+                    if (range === 'week') {
+                        end = new Date(today);
+                        end.setDate(today.getDate() + (7 - today.getDay()) % 7);
+                    } else if (range === 'month') {
+                        end = new Date(today.getFullYear(), today.getMonth() + 1, 0);
+                    } else if (range) {
+                        end = new Date(today);
+                        end.setDate(today.getDate() + Number(range));
+                    }
+
+                    document.querySelectorAll('#upcoming [data-first-date]').forEach(card => {
+                        card.hidden = end !== null && new Date(card.dataset.firstDate + 'T00:00:00') > end;
+                    });
+                    chip.parentElement.querySelectorAll('button').forEach(b => {
+                        b.setAttribute('aria-pressed', b === chip ? 'true' : 'false');
+                    });
+                }
+
+                // Collapsible result sections keep their open/closed state per browser
+                function restoreSections() {
+                    document.querySelectorAll('details[data-section]').forEach(section => {
+                        if (localStorage.getItem('section:' + section.dataset.section) === 'closed') {
+                            section.open = false;
+                        }
+                    });
+                }
+
+                function rememberSection(section) {
+                    localStorage.setItem('section:' + section.dataset.section, section.open ? 'open' : 'closed');
+                }
+
+                // Scrolls to and briefly highlights a random upcoming film still shown
+                function surpriseMe() {
+                    const cards = [...document.querySelectorAll('[data-category="upcoming"]')]
+                        .filter(card => !card.hidden);
+                    if (cards.length === 0) {
+                        return;
+                    }
+
+                    const card = cards[Math.floor(Math.random() * cards.length)];
+                    card.scrollIntoView({ behavior: 'smooth', block: 'center' });
+                    card.classList.add('ring-2', 'ring-orange-500');
+                    setTimeout(() => card.classList.remove('ring-2', 'ring-orange-500'), 2500);
+                }
+            "#))
+        }
+    }
+}
+
+/// How the viewer asked to see their results.
+#[derive(Clone, Debug)]
+pub struct ResultsView {
+    pub sort: SortOrder,
+    pub layout: Layout,
+    pub today: jiff::civil::Date,
+    /// Countries shown alongside the requested one
+    pub also: Vec<CountryCode>,
+    /// Leave films without release dates out of the page
+    pub hide_undated: bool,
+    /// Only show releases from today to this many months ahead; 0 shows every release
+    pub window_months: u32,
+    /// Unix time of the viewer's last visit, for marking new films
+    pub since: Option<i64>,
+    /// With `since`, only list the new films
+    pub new_only: bool,
+    pub order: WatchlistOrder,
+    /// TMDB provider ids of the viewer's streaming services
+    pub services: Vec<i32>,
+    /// Only list subscription streaming, hiding rent and buy
+    pub flatrate_only: bool,
+    /// Country whose streaming services are compared with the requested one's
+    pub compare_providers: Option<CountryCode>,
+    /// Links go straight to whole results pages, for browsers without JavaScript
+    pub nojs: bool,
+}
+
+impl ResultsView {
+    pub fn cache_key(&self) -> String {
+        format!(
+            "{}|{}|{}|{}|{}|{}|{:?}|{}|{}|{:?}|{}|{:?}|{}",
+            self.sort.as_query(),
+            self.layout.as_query(),
+            self.today,
+            also_query(&self.also),
+            self.hide_undated,
+            self.window_months,
+            self.since,
+            self.new_only,
+            self.order.as_query(),
+            self.services,
+            self.flatrate_only,
+            self.compare_providers,
+            self.nojs
+        )
+    }
+
+    /// The query string that requests these results, shared by `/release-dates` and
+    /// `/process`. `today` comes from the browser instead.
+    fn query(&self, source: &FilmSource, country: &str) -> String {
+        let mut query = format!(
+            "username={}&country={}&sort={}&layout={}&hide_undated={}&flatrate_only={}&window={}&\
+             order={}{}",
+            urlencoding::encode(&source.input()),
+            urlencoding::encode(country),
+            self.sort.as_query(),
+            self.layout.as_query(),
+            self.hide_undated,
+            self.flatrate_only,
+            self.window_months,
+            self.order.as_query(),
+            also_query(&self.also)
+        );
+        if let Some(since) = self.since {
+            query.push_str(&format!("&since={since}&new_only={}", self.new_only));
+        }
+        if let Some(other) = self.compare_providers {
+            query.push_str(&format!("&compare_providers={other}"));
+        }
+        if self.nojs {
+            query.push_str("&nojs=true");
+        }
+        // Always given, so clearing every service overrides the cookie
+        let services: Vec<String> = self.services.iter().map(|id| id.to_string()).collect();
+        query.push_str(&format!("&services={}", services.join(",")));
+        query
+    }
+
+    fn url(&self, source: &FilmSource, country: &str) -> String {
+        format!("/release-dates?{}", self.query(source, country))
+    }
+
+    pub fn process_url(&self, source: &FilmSource, country: &str) -> String {
+        format!("/process?{}", self.query(source, country))
+    }
+}
+
+/// The `&also=` query parameter for extra countries, or nothing when there are none.
+fn also_query<S: AsRef<str>>(also: &[S]) -> String {
+    let codes: Vec<&str> = also.iter().map(AsRef::as_ref).collect();
+    if codes.is_empty() { String::new() } else { format!("&also={}", codes.join(",")) }
+}
+
+/// Why the results may not cover the whole watchlist or every film's details.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Coverage {
+    /// Only the first this many recent films were read
+    pub film_cap: Option<usize>,
+    /// A watchlist page couldn't be fetched, so later films are missing
+    pub watchlist_interrupted: bool,
+    /// Some films or details couldn't be loaded or saved in time
+    pub partial: bool,
+}
+
+pub fn results_fragment(
+    source: &FilmSource,
+    country: &str,
+    films: &[FilmWithReleases],
+    coverage: Coverage,
+    view: &ResultsView,
+    config: &Config,
+    mock_mode: bool,
+) -> String {
+    let Coverage { film_cap, watchlist_interrupted, partial } = coverage;
+    let ResultsView {
+        sort,
+        layout,
+        today,
+        hide_undated,
+        window_months,
+        since,
+        new_only,
+        flatrate_only,
+        ..
+    } = *view;
+    let new_count = films.iter().filter(|f| f.is_new).count();
+    let since_label = since
+        .and_then(|s| jiff::Timestamp::from_second(s).ok())
+        .map(|t| t.strftime("%-d %b %Y").to_string());
+    let toggle_new_only_url =
+        ResultsView { new_only: !new_only, ..view.clone() }.url(source, country);
+    let compact = layout == Layout::Compact;
+    let subscriptions_enabled = crate::subscriptions::enabled(config);
+    let recent_months = config.recent_release_months;
+    let columns = config.release_columns.as_slice();
+    let proxy_images = config.proxy_images;
     let country_name = get_country_name(country);
-    let letterboxd_user_url = format!("https://letterboxd.com/{}/", username);
+    let username = source.username();
+    let letterboxd_user_url = source.letterboxd_url();
+    let has_owner_signals = films.iter().any(|f| f.liked || f.rating.is_some());
+    let toggle_sort = match sort {
+        SortOrder::Release => SortOrder::Liked,
+        SortOrder::Liked => SortOrder::Release,
+    };
+    let toggle_sort_url = ResultsView { sort: toggle_sort, ..view.clone() }.url(source, country);
+    let toggle_layout_url = ResultsView {
+        layout: if compact { Layout::Cards } else { Layout::Compact },
+        ..view.clone()
+    }
+    .url(source, country);
+    let toggle_window_url = ResultsView {
+        window_months: if window_months > 0 { 0 } else { DEFAULT_WINDOW_MONTHS },
+        ..view.clone()
+    }
+    .url(source, country);
+    let toggle_undated_url =
+        ResultsView { hide_undated: !hide_undated, ..view.clone() }.url(source, country);
+    let toggle_flatrate_url =
+        ResultsView { flatrate_only: !flatrate_only, ..view.clone() }.url(source, country);
+    let list_class = if compact {
+        "bg-slate-800 rounded border border-slate-700 divide-y divide-slate-700"
+    } else {
+        "space-y-2"
+    };
 
-    let today: jiff::civil::Date = jiff::Zoned::now().into();
     let current_year = today.year();
     let min_year = current_year - 1;
 
     fn sort_by_first_release_date(films: &mut Vec<&FilmWithReleases>) {
         films.sort_by(|a, b| {
-            let a_first_date = a.theatrical.first().or_else(|| a.streaming.first()).map(|r| r.date);
-            let b_first_date = b.theatrical.first().or_else(|| b.streaming.first()).map(|r| r.date);
+            let a_first_date = a.first_release().map(|r| r.date);
+            let b_first_date = b.first_release().map(|r| r.date);
 
             match (a_first_date, b_first_date) {
                 (Some(ad), Some(bd)) => ad.cmp(&bd).then(a.title.cmp(&b.title)),
@@ -136,8 +528,8 @@ pub fn results_fragment(username: &str, country: &str, films: &[FilmWithReleases
 
     fn sort_by_release_date(films: &mut Vec<&FilmWithReleases>) {
         films.sort_by(|a, b| {
-            let a_date = a.theatrical.first().or_else(|| a.streaming.first()).map(|r| r.date);
-            let b_date = b.theatrical.first().or_else(|| b.streaming.first()).map(|r| r.date);
+            let a_date = a.first_release().map(|r| r.date);
+            let b_date = b.first_release().map(|r| r.date);
 
             match (a_date, b_date) {
                 (Some(ad), Some(bd)) => ad.cmp(&bd).then(a.title.cmp(&b.title)),
@@ -165,7 +557,8 @@ pub fn results_fragment(username: &str, country: &str, films: &[FilmWithReleases
     let mut local_already_available_films: Vec<_> = films
         .iter()
         .filter(|f| f.category == ReleaseCategory::LocalAlreadyAvailable)
-        .filter(|f| f.year.is_some_and(|y| y >= min_year))
+        // Judge recency by the local release rather than the production year
+        .filter(|f| is_recent_release(f.releases().map(|r| r.date), today, recent_months))
         .collect();
     let mut no_releases: Vec<_> = films
         .iter()
@@ -177,8 +570,72 @@ pub fn results_fragment(username: &str, country: &str, films: &[FilmWithReleases
     sort_by_release_date(&mut local_already_available_films);
     sort_by_year(&mut no_releases);
 
+    if sort == SortOrder::Liked {
+        // Stable sort keeps release order within equally rated films
+        for list in
+            [&mut local_upcoming_films, &mut local_already_available_films, &mut no_releases]
+        {
+            list.sort_by_key(|f| (std::cmp::Reverse(f.liked), std::cmp::Reverse(f.rating)));
+        }
+    }
+
+    let upcoming_text = upcoming_clipboard_text(&local_upcoming_films);
+
+    let mut fallback_sources: Vec<&str> = films
+        .iter()
+        .flat_map(|f| f.releases())
+        .filter_map(|r| r.source_country.as_deref())
+        .filter(|source| *source != country)
+        .collect();
+    fallback_sources.sort_unstable();
+    fallback_sources.dedup();
+
+    let soon_cutoff = today.checked_add(jiff::Span::new().days(SOON_DAYS)).unwrap_or(today);
+    let soon_count = films
+        .iter()
+        .filter(|f| f.releases().any(|r| r.date >= today && r.date <= soon_cutoff))
+        .count();
+
+    // Streaming services seen in the results, to pick the viewer's own from
+    let mut stream_services: Vec<&WatchProvider> = films
+        .iter()
+        .flat_map(|f| f.streaming_providers.iter())
+        .filter(|p| p.provider_type == ProviderType::Stream)
+        .collect();
+    stream_services.sort_by(|a, b| a.provider_name.cmp(&b.provider_name));
+    stream_services.dedup_by_key(|p| p.provider_id);
+    let service_links: Vec<(&str, bool, String)> = stream_services
+        .iter()
+        .map(|provider| {
+            let selected = view.services.contains(&provider.provider_id);
+            let mut services = view.services.clone();
+            if selected {
+                services.retain(|id| *id != provider.provider_id);
+            } else {
+                services.push(provider.provider_id);
+                services.sort_unstable();
+            }
+            let url = ResultsView { services, ..view.clone() }.url(source, country);
+            (provider.provider_name.as_str(), selected, url)
+        })
+        .collect();
+    let tracked_count = local_upcoming_films.len() + local_already_available_films.len();
+    let on_services_count = local_upcoming_films
+        .iter()
+        .chain(local_already_available_films.iter())
+        .filter(|f| !f.streaming.iter().any(|r| r.date > today))
+        .filter(|f| {
+            f.streaming_providers.iter().any(|p| {
+                p.provider_type == ProviderType::Stream && view.services.contains(&p.provider_id)
+            })
+        })
+        .count();
+
     content_div(maud! {
         div class="max-w-4xl mx-auto px-3 py-4 sm:px-6" {
+             @if mock_mode {
+                 (mock_banner())
+             }
              div class="flex items-start justify-between gap-4" {
                  div class="flex-1 min-w-0" {
                      h1 class="text-xl sm:text-2xl font-bold text-slate-100" { "Timeboxd" }
@@ -186,29 +643,177 @@ pub fn results_fragment(username: &str, country: &str, films: &[FilmWithReleases
                      p class="mt-1 text-sm text-slate-400" {
                          a class="text-orange-500 hover:text-orange-400" href=(letterboxd_user_url) target="_blank" rel="noopener noreferrer" {
                              "@" (username)
+                             @if let FilmSource::List { slug, .. } = source {
+                                 " / " (slug)
+                             }
+                         }
+                         " · " (get_country_flag_emoji(country)) " " (country_name)
+                     }
+                     @if soon_count > 0 {
+                         p class="mt-1 text-sm text-emerald-400" data-soon-count=(soon_count) {
+                             (soon_count) @if soon_count == 1 { " film" } @else { " films" } " releasing in the next " (SOON_DAYS) " days"
+                         }
+                     }
+                     p class="mt-1 text-sm" {
+                         @if has_owner_signals {
+                             a class="text-orange-500 hover:text-orange-400" href=(toggle_sort_url) {
+                                 @match toggle_sort {
+                                     SortOrder::Liked => "Show liked films first",
+                                     SortOrder::Release => "Sort by release date",
+                                 }
+                             }
+                             span class="text-slate-600" { " · " }
+                         }
+                         a class="text-orange-500 hover:text-orange-400" href=(toggle_layout_url) {
+                             @if compact { "Show cards" } @else { "Compact view" }
+                         }
+                         span class="text-slate-600" { " · " }
+                         a class="text-orange-500 hover:text-orange-400" href=(toggle_flatrate_url) {
+                             @if flatrate_only { "Include rent and buy" } @else { "Subscriptions only" }
+                         }
+                         span class="text-slate-600" { " · " }
+                         @if window_months > 0 {
+                             span class="text-slate-400" { "Next " (window_months) " months only · " }
+                             a class="text-orange-500 hover:text-orange-400" href=(toggle_window_url) { "Show all dates" }
+                         } @else {
+                             a class="text-orange-500 hover:text-orange-400" href=(toggle_window_url) {
+                                 "Next " (DEFAULT_WINDOW_MONTHS) " months only"
+                             }
+                         }
+                     }
+                     @if let Some(since_label) = &since_label {
+                         p class="mt-1 text-sm" {
+                             span class="text-sky-400" title="Films timeboxd first saw on any watchlist since then" { (new_count) " new to timeboxd since " (since_label) }
+                             span class="text-slate-600" { " · " }
+                             a class="text-orange-500 hover:text-orange-400" href=(toggle_new_only_url) {
+                                 @if new_only { "Show all films" } @else { "Only new films" }
+                             }
+                         }
+                     }
+                     @if !service_links.is_empty() || !view.services.is_empty() {
+                         details class="mt-1 text-sm" {
+                             summary class="cursor-pointer text-slate-400 hover:text-slate-300" {
+                                 @if view.services.is_empty() {
+                                     "Choose your streaming services"
+                                 } @else {
+                                     span class="text-emerald-400" {
+                                         (on_services_count) " of " (tracked_count) " watchable now on your services"
+                                     }
+                                 }
+                             }
+                             p class="mt-1 flex flex-wrap gap-x-3 gap-y-1" {
+                                 @for (name, selected, url) in &service_links {
+                                     @if *selected {
+                                         a class="text-emerald-400 hover:text-emerald-300" href=(url) title="Remove from your services" { "✓ " (name) }
+                                     } @else {
+                                         a class="text-slate-500 hover:text-slate-300" href=(url) title="Add to your services" { (name) }
+                                     }
+                                 }
+                             }
                          }
-                         " · " (country_name)
                      }
                  }
                  a class="mt-2 text-sm text-orange-500 hover:text-orange-400 flex-shrink-0" href="/" { "New query" }
               }
 
+            @if !fallback_sources.is_empty() {
+                p class="mt-4 text-xs text-slate-500" {
+                    "Dates marked " (get_country_flag_emoji(country)) " " (country) " are for " (country_name) ". "
+                    "Where " (country_name) " has no date yet, dates from "
+                    @for (i, source) in fallback_sources.iter().enumerate() {
+                        @if i > 0 { " or " }
+                        span class="text-amber-400/80" title=(get_country_name(source)) {
+                            (get_country_flag_emoji(source)) " " (source)
+                        }
+                    }
+                    " are shown instead."
+                }
+            }
+
+            @if let Some(cap) = film_cap {
+                div class="mt-4 bg-slate-800 rounded-lg p-3 border border-slate-700" {
+                    p class="text-sm text-slate-400" { "Showing the first " (cap) " recent films of your watchlist." }
+                }
+            }
+
+            @if watchlist_interrupted {
+                div class="mt-4 bg-slate-800 rounded-lg p-3 border border-slate-700" role="status" {
+                    p class="text-sm text-amber-500/80" { "Couldn't fetch your full watchlist from Letterboxd, so some films are missing. Reload to try again." }
+                }
+            }
+
+            @if partial {
+                div class="mt-4 bg-slate-800 rounded-lg p-3 border border-slate-700" role="status" {
+                    p class="text-sm text-amber-500/80" { "Some films or details couldn't be loaded or saved in time, so these results may be incomplete. Reload to try again." }
+                }
+            }
+
             @if films.is_empty() {
                 div class="mt-4 bg-slate-800 shadow-xl rounded-lg p-4 border border-slate-700" {
                     p class="text-slate-400" { "No films found in watchlist." }
                 }
             } @else {
+                nav class="mt-4 flex flex-wrap gap-x-4 gap-y-1 text-sm" aria-label="Result sections" {
+                    @if !local_upcoming_films.is_empty() {
+                        a class="text-orange-500 hover:text-orange-400" href="#upcoming" { "Upcoming (" (local_upcoming_films.len()) ")" }
+                    }
+                    @if !local_already_available_films.is_empty() {
+                        a class="text-orange-500 hover:text-orange-400" href="#recent" onclick="document.getElementById('recent').open = true" { "Recent (" (local_already_available_films.len()) ")" }
+                    }
+                    @if !no_releases.is_empty() && !hide_undated {
+                        a class="text-orange-500 hover:text-orange-400" href="#no-release-dates" onclick="document.getElementById('no-release-dates').open = true" { "No release dates (" (no_releases.len()) ")" }
+                    }
+                }
+
                 @if !local_upcoming_films.is_empty() {
-                    div class="mt-4" {
-                        h2 class="text-lg font-semibold text-slate-200 mb-2" { "Upcoming releases" }
+                    div id="upcoming" class="mt-4 scroll-mt-4" {
+                        div class="flex items-center justify-between gap-2 mb-2" {
+                            h2 class="text-lg font-semibold text-slate-200" { "Upcoming releases" }
+                            div class="flex gap-3" {
+                                @if local_upcoming_films.len() > 1 {
+                                    button
+                                        type="button"
+                                        class="text-sm text-orange-500 hover:text-orange-400"
+                                        onclick="surpriseMe()"
+                                    {
+                                        "Surprise me"
+                                    }
+                                }
+                                button
+                                    type="button"
+                                    class="text-sm text-orange-500 hover:text-orange-400"
+                                    data-copy-text=(upcoming_text)
+                                    onclick="navigator.clipboard.writeText(this.dataset.copyText).then(() => { this.textContent = 'Copied'; })"
+                                {
+                                    "Copy list"
+                                }
+                            }
+                        }
                         @if country == "NZ" {
                             p class="text-sm text-slate-400 mb-2" { "Falls back to Australia then US release dates if no local dates found" }
                         } @else {
                             p class="text-sm text-slate-400 mb-2" { "Falls back to US release dates if no local dates found" }
                         }
-                        div class="space-y-2" {
+                        div class="flex flex-wrap gap-2 mb-2" role="group" aria-label="Filter upcoming releases" {
+                            @for (label, window) in [("All", ""), ("This week", "week"), ("This month", "month"), ("Next 90 days", "90")] {
+                                button
+                                    type="button"
+                                    class="rounded-full border border-slate-600 px-3 py-0.5 text-xs text-slate-300 hover:border-orange-500 aria-pressed:border-orange-500 aria-pressed:bg-orange-600 aria-pressed:text-white"
+                                    aria-pressed=(if window.is_empty() { "true" } else { "false" })
+                                    data-window=(window)
+                                    onclick="filterUpcoming(this)"
+                                {
+                                    (label)
+                                }
+                            }
+                        }
+                        div class=(list_class) {
                             @for film in &local_upcoming_films {
-                                (film_card(film))
+                                @if compact {
+                                    (film_row(film, today, proxy_images, flatrate_only))
+                                } @else {
+                                    (film_card(film, country, columns, today, proxy_images, flatrate_only))
+                                }
                             }
                         }
                     }
@@ -217,28 +822,52 @@ pub fn results_fragment(username: &str, country: &str, films: &[FilmWithReleases
 
 
                 @if !local_already_available_films.is_empty() {
-                    div class="mt-6" {
-                        h2 class="text-lg font-semibold text-slate-200 mb-2" { "Recent releases" }
-                        p class="text-sm text-slate-400 mb-2" { "Films released in the last year" }
+                    details id="recent" class="mt-6 scroll-mt-4 group" open data-section="recent" ontoggle="rememberSection(this)" {
+                        summary class="cursor-pointer list-none mb-2" {
+                            h2 class="inline text-lg font-semibold text-slate-200" { "Recent releases" }
+                            span class="ml-2 text-sm text-slate-500 group-open:hidden" { "Show" }
+                            span class="ml-2 text-sm text-slate-500 hidden group-open:inline" { "Hide" }
+                        }
+                        p class="text-sm text-slate-400 mb-2" { "Films released in the last " (recent_months) " months" }
                         @if country == "NZ" {
                             p class="text-sm text-slate-400 mb-2" { "Falls back to Australia then US release dates if no local dates found" }
                         } @else {
                             p class="text-sm text-slate-400 mb-2" { "Falls back to US release dates if no local dates found" }
                         }
-                        div class="space-y-2" {
+                        div class=(list_class) {
                             @for film in &local_already_available_films {
-                                (film_card(film))
+                                @if compact {
+                                    (film_row(film, today, proxy_images, flatrate_only))
+                                } @else {
+                                    (film_card(film, country, columns, today, proxy_images, flatrate_only))
+                                }
                             }
                         }
                     }
                 }
 
-                @if !no_releases.is_empty() {
-                    div class="mt-6" {
-                        h2 class="text-lg font-semibold text-slate-200 mb-2" { "No release dates found" }
-                        div class="space-y-2" {
+                @if !no_releases.is_empty() && hide_undated {
+                    p class="mt-6 text-sm text-slate-500" {
+                        (no_releases.len()) @if no_releases.len() == 1 { " film" } @else { " films" } " without release dates hidden · "
+                        a class="text-orange-500 hover:text-orange-400" href=(toggle_undated_url) { "Show them" }
+                    }
+                } @else if !no_releases.is_empty() {
+                    details id="no-release-dates" class="mt-6 scroll-mt-4 group" open data-section="no-release-dates" ontoggle="rememberSection(this)" {
+                        summary class="cursor-pointer list-none mb-2" {
+                            h2 class="inline text-lg font-semibold text-slate-200" { "No release dates found" }
+                            span class="ml-2 text-sm text-slate-500 group-open:hidden" { "Show" }
+                            span class="ml-2 text-sm text-slate-500 hidden group-open:inline" { "Hide" }
+                        }
+                        p class="text-sm text-slate-400 mb-2" {
+                            a class="text-orange-500 hover:text-orange-400" href=(toggle_undated_url) { "Always hide films without release dates" }
+                        }
+                        div class=(list_class) {
                             @for film in &no_releases {
-                                (film_card(film))
+                                @if compact {
+                                    (film_row(film, today, proxy_images, flatrate_only))
+                                } @else {
+                                    (film_card(film, country, columns, today, proxy_images, flatrate_only))
+                                }
                             }
                         }
                     }
@@ -250,6 +879,113 @@ pub fn results_fragment(username: &str, country: &str, films: &[FilmWithReleases
                     }
                 }
             }
+
+            // Subscriptions check watchlists, so they aren't offered for lists
+            @if subscriptions_enabled && matches!(source, FilmSource::Watchlist(_)) {
+                (subscription_form(username, country, "/subscribe", "Notify me"))
+            }
+        }
+    })
+}
+
+fn subscription_form(username: &str, country: &str, action: &str, label: &str) -> impl Renderable {
+    maud! {
+        form class="mt-6 bg-slate-800 rounded-lg p-4 border border-slate-700" method="post" action=(action) {
+            p class="text-sm text-slate-300" { "Get a webhook POST when a film here becomes available or gains streaming providers." }
+            input type="hidden" name="username" value=(username);
+            input type="hidden" name="country" value=(country);
+            div class="mt-3 flex gap-2" {
+                input
+                    class="flex-1 min-w-0 rounded-md border border-slate-600 bg-slate-700 text-slate-100 px-3 py-2 text-sm placeholder-slate-400 focus:border-orange-500 focus:outline-none focus:ring-1 focus:ring-orange-500"
+                    type="url"
+                    name="webhook_url"
+                    placeholder="https://example.com/webhook"
+                    required;
+                button class="rounded-md bg-orange-600 px-3 py-2 text-sm font-semibold text-white hover:bg-orange-700" type="submit" { (label) }
+            }
+        }
+    }
+}
+
+/// Where a subscription stands after the request that rendered the page.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SubscriptionStatus {
+    /// Waiting for the link sent to the webhook to be opened
+    Pending,
+    Confirmed,
+    Removed,
+}
+
+pub fn subscription_page(
+    username: &str,
+    country: &str,
+    webhook_url: &str,
+    status: SubscriptionStatus,
+) -> String {
+    let country_name = get_country_name(country);
+
+    page(
+        "Notifications - Timeboxd",
+        maud! {
+            div class="min-h-screen bg-slate-900" {
+                div class="max-w-2xl mx-auto px-4 py-12 sm:px-6" {
+                    div class="bg-slate-800 shadow-xl rounded-lg p-6 sm:p-8 border border-slate-700" {
+                        @if status == SubscriptionStatus::Pending {
+                            h1 class="text-2xl font-bold text-slate-100" { "Check your webhook" }
+                            p class="mt-2 text-slate-400" {
+                                "A confirmation link was posted to " (webhook_url) ". Open it to start notifications for @" (username) "'s watchlist in " (country_name) "."
+                            }
+                        } @else if status == SubscriptionStatus::Confirmed {
+                            h1 class="text-2xl font-bold text-slate-100" { "Subscribed" }
+                            p class="mt-2 text-slate-400" {
+                                "Changes to @" (username) "'s watchlist in " (country_name) " will be posted to " (webhook_url) "."
+                            }
+                            form class="mt-4" method="post" action="/unsubscribe" {
+                                input type="hidden" name="username" value=(username);
+                                input type="hidden" name="country" value=(country);
+                                input type="hidden" name="webhook_url" value=(webhook_url);
+                                button class="text-sm text-orange-500 hover:text-orange-400" type="submit" { "Unsubscribe" }
+                            }
+                        } @else {
+                            h1 class="text-2xl font-bold text-slate-100" { "Unsubscribed" }
+                            p class="mt-2 text-slate-400" { "No more notifications will be sent to " (webhook_url) "." }
+                        }
+                        a class="mt-6 inline-block text-orange-500 hover:text-orange-400" href="/" { "Back" }
+                    }
+                }
+            }
+        },
+    )
+}
+
+pub fn dry_run_fragment(username: &str, country: &str, summary: &DryRunSummary) -> String {
+    let rows = [
+        ("Films in range", summary.films),
+        ("Films cached", summary.cached_films),
+        ("TMDB searches", summary.film_searches),
+        ("Release lookups", summary.release_requests),
+        ("Release lookups cached", summary.cached_release_requests),
+        ("Release fetches", summary.release_fetches),
+        ("Provider lookups", summary.provider_requests),
+        ("Provider lookups cached", summary.cached_provider_requests),
+        ("Provider fetches", summary.provider_fetches),
+    ];
+
+    content_div(maud! {
+        div class="max-w-2xl mx-auto px-3 py-12 sm:px-6" {
+            div class="bg-slate-800 shadow-xl rounded-lg p-6 sm:p-8 border border-slate-700" {
+                h1 class="text-2xl font-bold text-slate-100" { "Dry run" }
+                p class="mt-1 text-sm text-slate-400" { "@" (username) " · " (get_country_name(country)) }
+                dl class="mt-4 grid grid-cols-2 gap-x-4 gap-y-1 text-sm" {
+                    @for (label, value) in rows {
+                        dt class="text-slate-400" { (label) }
+                        dd class="text-slate-200 text-right" { (value) }
+                    }
+                    dt class="text-slate-300 font-semibold" { "Estimated TMDB calls" }
+                    dd class="text-slate-100 font-semibold text-right" { (summary.tmdb_calls()) }
+                }
+                p class="mt-4 text-xs text-slate-500" { "Release and provider counts only cover films already matched to TMDB." }
+            }
         }
     })
 }
@@ -266,6 +1002,164 @@ pub fn error_fragment(message: String) -> String {
     })
 }
 
+pub fn queued_fragment(position: usize) -> String {
+    content_div(maud! {
+        div class="max-w-xl mx-auto px-6 py-12" {
+            div class="bg-slate-800 shadow-xl rounded-lg p-8 text-center border border-slate-700" {
+                div class="mx-auto h-12 w-12 rounded-full border-4 border-slate-700 border-t-orange-600 animate-spin" {}
+                h1 class="mt-6 text-xl font-semibold text-slate-100" { "Waiting in queue" }
+                p class="mt-2 text-slate-400" {
+                    @match position {
+                        0 | 1 => { "You're next. Other watchlists are being processed." }
+                        2 => { "1 request ahead of you." }
+                        _ => { (position - 1) " requests ahead of you." }
+                    }
+                }
+                p class="mt-2 text-sm text-slate-500" { "This page will continue automatically." }
+            }
+        }
+    })
+}
+
+/// A film on only one member's watchlist, shown without release dates.
+pub struct ListedFilm {
+    pub title: String,
+    pub year: Option<i16>,
+    pub slug: String,
+}
+
+pub fn compare_form_page(
+    user_a: &str,
+    user_b: &str,
+    saved_country: Option<&str>,
+    forced_country: Option<&str>,
+) -> String {
+    let input_class = "mt-2 w-full rounded-md border border-slate-600 bg-slate-700 text-slate-100 \
+                       px-3 py-2 placeholder-slate-400 focus:border-orange-500 focus:outline-none \
+                       focus:ring-1 focus:ring-orange-500";
+
+    page(
+        "Compare watchlists - Timeboxd",
+        maud! {
+            div class="min-h-screen bg-slate-900" {
+                div class="max-w-2xl mx-auto px-4 py-12 sm:px-6" {
+                    div class="bg-slate-800 shadow-xl rounded-lg p-6 sm:p-8 border border-slate-700" {
+                        h1 class="text-2xl sm:text-3xl font-bold text-slate-100" { "Compare watchlists" }
+                        p class="mt-2 text-slate-400" { "Films two Letterboxd members both want to see, and when they're out." }
+
+                        form class="mt-8 space-y-6" method="get" action="/compare" {
+                            div {
+                                label class="block text-sm font-medium text-slate-300" for="user_a" { "First username" }
+                                input class=(input_class) name="user_a" id="user_a" value=(user_a) required;
+                            }
+                            div {
+                                label class="block text-sm font-medium text-slate-300" for="user_b" { "Second username" }
+                                input class=(input_class) name="user_b" id="user_b" value=(user_b) required;
+                            }
+                            @if let Some(code) = forced_country {
+                                input type="hidden" name="country" value=(code);
+                            } @else {
+                                div {
+                                    label class="block text-sm font-medium text-slate-300" for="country" { "Country code" }
+                                    input
+                                        class=(input_class)
+                                        name="country"
+                                        id="country"
+                                        value=[saved_country]
+                                        placeholder="NZ"
+                                        maxlength="2"
+                                        required;
+                                }
+                            }
+                            button class="w-full rounded-md bg-orange-600 px-4 py-2 font-semibold text-white hover:bg-orange-700 focus:outline-none focus:ring-1 focus:ring-orange-500" type="submit" { "Compare" }
+                        }
+                        a class="mt-6 inline-block text-sm text-orange-500 hover:text-orange-400" href="/" { "Back" }
+                    }
+                }
+            }
+        },
+    )
+}
+
+pub fn compare_page(
+    sources: (&FilmSource, &FilmSource),
+    country: &str,
+    shared: &[FilmWithReleases],
+    unique: (&[ListedFilm], &[ListedFilm]),
+    today: jiff::civil::Date,
+    config: &Config,
+) -> String {
+    let (name_a, name_b) = (sources.0.display_name(), sources.1.display_name());
+    let mut shared: Vec<&FilmWithReleases> = shared.iter().collect();
+    // Undated films last
+    shared.sort_by_key(|film| {
+        let first_date = film.releases().map(|r| r.date).min();
+        (first_date.is_none(), first_date)
+    });
+    let listed = |films: &[ListedFilm], owner: &str| {
+        let heading = format!("Only {owner} ({})", films.len());
+        maud! {
+            section class="mt-8" {
+                h2 class="text-lg font-semibold text-slate-100" { (heading) }
+                @if films.is_empty() {
+                    p class="mt-2 text-sm text-slate-500" { "Nothing else on this watchlist." }
+                } @else {
+                    ul class="mt-2 divide-y divide-slate-700 text-sm" {
+                        @for film in films {
+                            li class="px-3 py-1.5" {
+                                a class="text-slate-100 hover:text-orange-500" href=(format!("https://letterboxd.com/film/{}/", film.slug)) target="_blank" rel="noopener noreferrer" {
+                                    (film.title)
+                                    @if let Some(year) = film.year {
+                                        span class="ml-1.5 text-slate-400" { "(" (year) ")" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        .render()
+        .into_inner()
+    };
+    let only_a = listed(unique.0, &name_a);
+    let only_b = listed(unique.1, &name_b);
+
+    page(
+        &format!("{name_a} and {name_b} - Timeboxd"),
+        maud! {
+            div class="min-h-screen bg-slate-900" {
+                div class="max-w-3xl mx-auto px-4 py-12 sm:px-6" {
+                    div class="bg-slate-800 shadow-xl rounded-lg p-6 sm:p-8 border border-slate-700" {
+                        h1 class="text-2xl font-bold text-slate-100" { (name_a) " and " (name_b) }
+                        p class="mt-2 text-slate-400" {
+                            "Release dates for " (get_country_flag_emoji(country)) " " (get_country_name(country))
+                        }
+
+                        section class="mt-8" {
+                            h2 class="text-lg font-semibold text-slate-100" { "Both want to see (" (shared.len()) ")" }
+                            @if shared.is_empty() {
+                                p class="mt-2 text-sm text-slate-500" { "No recent films in common." }
+                            } @else {
+                                div class="mt-2 divide-y divide-slate-700" {
+                                    @for film in &shared {
+                                        (film_row(film, today, config.proxy_images, config.flatrate_only))
+                                    }
+                                }
+                            }
+                        }
+
+                        (Raw::dangerously_create(&only_a))
+                        (Raw::dangerously_create(&only_b))
+
+                        a class="mt-8 inline-block text-sm text-orange-500 hover:text-orange-400" href="/compare" { "Compare others" }
+                    }
+                }
+            }
+        },
+    )
+}
+
 pub fn error_page(message: String) -> String {
     page(
         "Error",
@@ -301,15 +1195,135 @@ fn page(title: &str, body: impl Renderable) -> String {
     .into_inner()
 }
 
+/// Shown when no TMDB credentials are configured, so the canned dates aren't mistaken for real
+/// ones.
+fn mock_banner() -> impl Renderable {
+    maud! {
+        div class="mb-4 rounded-lg border border-amber-700 bg-amber-950/60 p-3 text-sm text-amber-300" role="alert" {
+            strong { "Demo data. " }
+            "No TMDB access token is configured, so the release dates and streaming services shown are made up. Set "
+            code { "TMDB_ACCESS_TOKEN" }
+            " to see real dates."
+        }
+    }
+}
+
 fn content_div(inner: impl Renderable) -> String {
     maud! { div id="content" { (inner) } }.render().into_inner()
 }
 
-fn film_card(film: &FilmWithReleases) -> impl Renderable + '_ {
-    let letterboxd_url = format!("https://letterboxd.com/film/{}/", film.letterboxd_slug);
+pub fn film_card_fragment(
+    film: &FilmWithReleases,
+    country: &str,
+    config: &Config,
+    today: jiff::civil::Date,
+    flatrate_only: bool,
+) -> String {
+    film_card(film, country, &config.release_columns, today, config.proxy_images, flatrate_only)
+        .render()
+        .into_inner()
+}
+
+pub fn refresh_error_fragment(slug: &str, message: &str) -> String {
+    maud! {
+        div id=(film_card_id(slug)) class="bg-slate-800 shadow-xl rounded p-3 border border-slate-700" {
+            p class="text-sm text-slate-400" { (message) }
+        }
+    }
+    .render()
+    .into_inner()
+}
+
+pub fn film_card_id(slug: &str) -> String {
+    format!("film-{slug}")
+}
+
+pub fn report_button_id(slug: &str) -> String {
+    format!("report-{slug}")
+}
+
+pub fn report_ack_fragment(slug: &str, message: &str) -> String {
+    maud! {
+        span id=(report_button_id(slug)) class="text-slate-500" { " · " (message) }
+    }
+    .render()
+    .into_inner()
+}
+
+fn film_card<'a>(
+    film: &'a FilmWithReleases,
+    country: &'a str,
+    columns: &'a [ReleaseColumn],
+    today: jiff::civil::Date,
+    proxy_images: bool,
+    flatrate_only: bool,
+) -> impl Renderable + 'a {
+    let letterboxd_url = film_url(&film.letterboxd_slug);
+    // Sends the browser's time zone so the refreshed card judges upcoming dates like the page
+    let also: Vec<String> = film.other_countries.iter().map(|c| c.country.clone()).collect();
+    let compare = film.compare_providers.as_ref().map(|c| c.country.as_str()).unwrap_or_default();
+    let refresh_action = format!(
+        "$$post('/refresh?slug={}&country={}{}&flatrate_only={flatrate_only}&\
+         compare_providers={compare}&tz=' + \
+         encodeURIComponent(Intl.DateTimeFormat().resolvedOptions().timeZone))",
+        urlencoding::encode(&film.letterboxd_slug),
+        urlencoding::encode(country),
+        also_query(&also)
+    );
+    let report_action = format!(
+        "$$post('/report?slug={}&tmdb_id={}')",
+        urlencoding::encode(&film.letterboxd_slug),
+        film.tmdb_id
+    );
+    let first_upcoming_date =
+        film.releases().map(|r| r.date).filter(|d| *d >= today).min().map(|d| d.to_string());
+    let split_columns = |theatrical: &[ReleaseDate], streaming: &[ReleaseDate]| {
+        columns
+            .iter()
+            .map(|column| {
+                let releases: Vec<ReleaseDate> = theatrical
+                    .iter()
+                    .chain(streaming.iter())
+                    .filter(|r| column.types.contains(&r.release_type))
+                    .cloned()
+                    .collect();
+                (column, releases)
+            })
+            .collect::<Vec<_>>()
+    };
+    let column_releases = split_columns(&film.theatrical, &film.streaming);
+    let other_column_releases: Vec<_> = film
+        .other_countries
+        .iter()
+        .map(|other| (other.country.as_str(), split_columns(&other.theatrical, &other.streaming)))
+        .collect();
+    let grid_columns = match columns.len() {
+        1 => "grid-cols-1",
+        2 => "grid-cols-2",
+        _ => "grid-cols-2 sm:grid-cols-3",
+    };
+    // Providers for a film whose streaming release is still ahead are pre-announcements
+    let streaming_upcoming = film.streaming.iter().any(|r| r.date > today);
+    let (category, badge_label, badge_class) = match film.category {
+        ReleaseCategory::LocalUpcoming if film.limited_only => {
+            ("upcoming", "Limited", "bg-purple-900/60 text-purple-300")
+        },
+        ReleaseCategory::LocalUpcoming => {
+            ("upcoming", "Upcoming", "bg-orange-900/60 text-orange-300")
+        },
+        ReleaseCategory::LocalAlreadyAvailable => {
+            ("available", "Available", "bg-emerald-900/60 text-emerald-300")
+        },
+        ReleaseCategory::NoReleases => ("none", "No dates", "bg-slate-700 text-slate-400"),
+    };
 
     maud! {
-        div class="bg-slate-800 shadow-xl rounded p-3 flex gap-3 border border-slate-700" {
+        div
+            id=(film_card_id(&film.letterboxd_slug))
+            class="bg-slate-800 shadow-xl rounded p-3 flex gap-3 border border-slate-700"
+            data-first-date=[first_upcoming_date.as_deref()]
+            data-category=(category)
+        {
             @if let Some(poster_path) = &film.poster_path {
                 a
                     class="block flex-shrink-0 w-16 sm:w-20"
@@ -319,7 +1333,7 @@ fn film_card(film: &FilmWithReleases) -> impl Renderable + '_ {
                 {
                     img
                         class="w-16 h-24 sm:w-20 sm:h-30 object-cover rounded"
-                        src=(format!("https://image.tmdb.org/t/p/w200{}", poster_path))
+                        src=(image_url(ImageKind::Poster, poster_path, proxy_images))
                         alt=(format!("{} poster", film.title))
                         loading="lazy"
                         width="80"
@@ -340,46 +1354,215 @@ fn film_card(film: &FilmWithReleases) -> impl Renderable + '_ {
                                     span class="ml-1.5 font-normal text-slate-400" { "(" (year) ")" }
                                 }
                             }
+                            @if film.liked {
+                                span class="ml-1.5 text-sm text-orange-500" title="Liked" { "♥" }
+                            }
+                            @if let Some(rating) = film.rating {
+                                span class="ml-1.5 text-sm font-normal text-emerald-400" title="Your rating" { (format_rating(rating)) }
+                            }
                         }
                         div class="mt-0.5 text-xs" {
+                            @if film.is_new {
+                                span class="mr-1.5 rounded px-1 py-0.5 bg-sky-900/60 text-sky-300" title="First seen by timeboxd, on any watchlist, since your last visit" { "New" }
+                            }
+                            span class=(format!("mr-1.5 rounded px-1 py-0.5 {badge_class}")) { (badge_label) }
                             a class="text-slate-500 hover:text-slate-400" href=(format!("https://www.themoviedb.org/movie/{}", film.tmdb_id)) target="_blank" rel="noopener noreferrer" {
                                 "TMDB"
                             }
+                            span class="text-slate-600" { " · " }
+                            button type="button" class="text-slate-500 hover:text-slate-400" title="Refresh release dates for this film" data-on-click=(refresh_action) {
+                                "Refresh"
+                            }
+                            @if let Some(cached_at) = film.releases_cached_at {
+                                span class="text-slate-600" title="When release dates were last fetched from TMDB" {
+                                    " · updated " (format_age(cached_at))
+                                }
+                            }
+                            @if film.match_confidence == MatchConfidence::Low {
+                                span class="text-amber-500/80" title="This film was matched to TMDB by title search and may be the wrong film" {
+                                    " · match may be wrong"
+                                }
+                            }
+                            span id=(report_button_id(&film.letterboxd_slug)) {
+                                span class="text-slate-600" { " · " }
+                                button type="button" class="text-slate-500 hover:text-slate-400" title="Report that this card shows the wrong film" data-on-click=(report_action) {
+                                    "Wrong film?"
+                                }
+                            }
                         }
                     }
                 }
 
-                div class="mt-2 grid grid-cols-2 sm:grid-cols-2 gap-3" {
-                    (release_list("Theatrical", &film.theatrical, ReleaseType::Theatrical))
-                    (release_list("Streaming", &film.streaming, ReleaseType::Digital))
+                div class=(format!("mt-2 grid {} gap-3", grid_columns)) {
+                    @for (column, releases) in &column_releases {
+                        (release_list(&column.label, releases, &film.date_changes, &column.types, country))
+                    }
+                }
+
+                @if !film.fallback_releases.is_empty() {
+                    div class="mt-2" {
+                        (release_list("Other dates", &film.fallback_releases, &film.date_changes, &[], country))
+                    }
+                }
+
+                @if let Some(date) = film.estimated_streaming {
+                    p class="mt-2 text-xs text-slate-500" title="Estimated from the usual gap between cinema and streaming releases, not an announced date" {
+                        "~Expected streaming " (date.strftime("%b %Y").to_string()) " (estimate)"
+                    }
+                }
+
+                @for (other, releases_by_column) in &other_column_releases {
+                    div class="mt-3 border-t border-slate-700 pt-2" {
+                        h3 class="text-xs font-semibold text-slate-400" {
+                            (get_country_flag_emoji(other)) " " (get_country_name(other))
+                        }
+                        div class=(format!("mt-1 grid {} gap-3", grid_columns)) {
+                            @for (column, releases) in releases_by_column {
+                                (release_list(&column.label, releases, &film.date_changes, &column.types, other))
+                            }
+                        }
+                    }
+                }
+
+                @if film.providers_unavailable {
+                    p class="mt-3 border-t border-slate-700 pt-3 text-xs text-slate-500" title="The streaming provider lookup failed; refresh the film to try again" {
+                        "Streaming providers unavailable right now"
+                    }
+                } @else if film.streaming_providers.iter().any(|p| !flatrate_only || p.provider_type == ProviderType::Stream) {
+                    (provider_list(&film.streaming_providers, streaming_upcoming, proxy_images, flatrate_only))
+                }
+
+                @if let Some(compare) = film.compare_providers.as_ref().filter(|_| !film.providers_unavailable) {
+                    (provider_comparison(country, &film.streaming_providers, compare, proxy_images))
+                }
+
+                @if !film.released_regions.is_empty() {
+                    (released_regions_detail(&film.released_regions))
+                }
+            }
+        }
+    }
+}
+
+/// A single-line summary of a film for the compact layout: title, year, next date and provider
+/// logos, without the poster or per-column release lists.
+fn film_row(
+    film: &FilmWithReleases,
+    today: jiff::civil::Date,
+    proxy_images: bool,
+    flatrate_only: bool,
+) -> impl Renderable + '_ {
+    let letterboxd_url = film_url(&film.letterboxd_slug);
+    let dates = film.releases().map(|r| r.date);
+    let first_upcoming_date = dates.clone().filter(|d| *d >= today).min();
+    let shown_date = first_upcoming_date.or_else(|| dates.max());
+    let category = match film.category {
+        ReleaseCategory::LocalUpcoming => "upcoming",
+        ReleaseCategory::LocalAlreadyAvailable => "available",
+        ReleaseCategory::NoReleases => "none",
+    };
+    let mut providers: Vec<&WatchProvider> = film
+        .streaming_providers
+        .iter()
+        .filter(|p| !flatrate_only || p.provider_type == ProviderType::Stream)
+        .collect();
+    providers.sort_by_key(|p| p.display_priority);
+    let mut seen = std::collections::HashSet::new();
+    providers.retain(|p| seen.insert(p.provider_id));
+
+    maud! {
+        div
+            id=(film_card_id(&film.letterboxd_slug))
+            class="flex items-center gap-3 px-3 py-1.5 text-sm"
+            data-first-date=[first_upcoming_date.map(|d| d.to_string())]
+            data-category=(category)
+        {
+            a class="flex-1 min-w-0 truncate text-slate-100 hover:text-orange-500" href=(letterboxd_url) target="_blank" rel="noopener noreferrer" {
+                (film.title)
+                @if let Some(year) = film.year {
+                    span class="ml-1.5 text-slate-400" { "(" (year) ")" }
+                }
+                @if film.is_new {
+                    span class="ml-1.5 rounded px-1 text-xs bg-sky-900/60 text-sky-300" title="First seen by timeboxd, on any watchlist, since your last visit" { "New" }
+                }
+            }
+            @if !providers.is_empty() {
+                div class="hidden sm:flex flex-shrink-0 gap-1" {
+                    @for provider in &providers {
+                        img
+                            class="w-5 h-5 rounded"
+                            src=(image_url(ImageKind::Logo, &provider.logo_path, proxy_images))
+                            alt=(provider.provider_name)
+                            title=(provider.provider_name)
+                            loading="lazy"
+                            width="20"
+                            height="20";
+                    }
+                }
+            }
+            span class="flex-shrink-0 w-24 text-right tabular-nums text-slate-300" {
+                @if let Some(date) = shown_date {
+                    (date.to_string())
+                } @else {
+                    span class="text-slate-500" { "No dates" }
                 }
+            }
+        }
+    }
+}
+
+fn released_regions_detail(regions: &[String]) -> impl Renderable + '_ {
+    let label = match regions.len() {
+        1 => "Already out in 1 region".to_string(),
+        n => format!("Already out in {n} regions"),
+    };
 
-                @if !film.streaming_providers.is_empty() {
-                    (provider_list(&film.streaming_providers))
+    maud! {
+        details class="mt-2 text-xs text-slate-400" {
+            summary class="cursor-pointer text-slate-500 hover:text-slate-400" { (label) }
+            ul class="mt-1 flex flex-wrap gap-x-3 gap-y-0.5" {
+                @for code in regions {
+                    li title=(code) {
+                        (get_country_flag_emoji(code)) " " (get_country_name(code))
+                    }
                 }
             }
         }
     }
 }
 
-fn provider_list(providers: &[WatchProvider]) -> impl Renderable + '_ {
-    let stream_providers: Vec<_> =
-        providers.iter().filter(|p| p.provider_type == ProviderType::Stream).collect();
-    let rent_providers: Vec<_> =
-        providers.iter().filter(|p| p.provider_type == ProviderType::Rent).collect();
-    let buy_providers: Vec<_> =
-        providers.iter().filter(|p| p.provider_type == ProviderType::Buy).collect();
+/// With `flatrate_only`, rent and buy offers are left out so only subscription streaming shows.
+fn provider_list(
+    providers: &[WatchProvider],
+    upcoming: bool,
+    proxy_images: bool,
+    flatrate_only: bool,
+) -> impl Renderable + '_ {
+    let of_type = |provider_type: ProviderType| {
+        if flatrate_only && provider_type != ProviderType::Stream {
+            return Vec::new();
+        }
+        let mut matching: Vec<_> =
+            providers.iter().filter(|p| p.provider_type == provider_type).collect();
+        matching.sort_by_key(|p| p.display_priority);
+        matching
+    };
+    let stream_providers = of_type(ProviderType::Stream);
+    let rent_providers = of_type(ProviderType::Rent);
+    let buy_providers = of_type(ProviderType::Buy);
 
     maud! {
         div class="mt-3 border-t border-slate-700 pt-3" {
-            h3 class="text-xs font-semibold text-slate-400 uppercase tracking-wide mb-2" { "Available now" }
+            h3 class="text-xs font-semibold text-slate-400 uppercase tracking-wide mb-2" {
+                @if upcoming { "Coming to" } @else { "Available now" }
+            }
             div class="space-y-2" {
                 @if !stream_providers.is_empty() {
                     div class="flex items-center gap-2" {
                         span class="text-xs text-slate-500 w-12" { "Stream" }
                         div class="flex flex-wrap gap-1.5" {
                             @for provider in &stream_providers {
-                                (provider_icon(provider))
+                                (provider_icon(provider, proxy_images))
                             }
                         }
                     }
@@ -389,7 +1572,7 @@ fn provider_list(providers: &[WatchProvider]) -> impl Renderable + '_ {
                         span class="text-xs text-slate-500 w-12" { "Rent" }
                         div class="flex flex-wrap gap-1.5" {
                             @for provider in &rent_providers {
-                                (provider_icon(provider))
+                                (provider_icon(provider, proxy_images))
                             }
                         }
                     }
@@ -399,7 +1582,7 @@ fn provider_list(providers: &[WatchProvider]) -> impl Renderable + '_ {
                         span class="text-xs text-slate-500 w-12" { "Buy" }
                         div class="flex flex-wrap gap-1.5" {
                             @for provider in &buy_providers {
-                                (provider_icon(provider))
+                                (provider_icon(provider, proxy_images))
                             }
                         }
                     }
@@ -409,7 +1592,67 @@ fn provider_list(providers: &[WatchProvider]) -> impl Renderable + '_ {
     }
 }
 
-fn provider_icon(provider: &WatchProvider) -> impl Renderable + '_ {
+/// Subscription services in `country` and in the compared country side by side, with the ones
+/// only one of them has highlighted.
+fn provider_comparison<'a>(
+    country: &'a str,
+    providers: &'a [WatchProvider],
+    compare: &'a CountryProviders,
+    proxy_images: bool,
+) -> impl Renderable + 'a {
+    let streaming = |providers: &'a [WatchProvider]| {
+        let mut matching: Vec<&WatchProvider> =
+            providers.iter().filter(|p| p.provider_type == ProviderType::Stream).collect();
+        matching.sort_by_key(|p| p.display_priority);
+        let mut seen = std::collections::HashSet::new();
+        matching.retain(|p| seen.insert(p.provider_id));
+        matching
+    };
+    let here = streaming(providers);
+    let there = streaming(&compare.providers);
+    let shared =
+        here.iter().filter(|p| there.iter().any(|o| o.provider_id == p.provider_id)).count();
+    let columns = [(country, here.clone(), there.clone()), (compare.country.as_str(), there, here)];
+
+    maud! {
+        div class="mt-3 border-t border-slate-700 pt-3" {
+            h3 class="text-xs font-semibold text-slate-400 uppercase tracking-wide mb-2" {
+                "Streaming compared"
+                @if shared > 0 {
+                    span class="ml-1.5 normal-case font-normal text-slate-500" { "· " (shared) " in both" }
+                }
+            }
+            div class="grid grid-cols-2 gap-3" {
+                @for (code, own, other) in &columns {
+                    div {
+                        p class="text-xs text-slate-500 mb-1" {
+                            (get_country_flag_emoji(code)) " " (get_country_name(code))
+                        }
+                        @if own.is_empty() {
+                            p class="text-xs text-slate-500" { "Not streaming" }
+                        } @else {
+                            div class="flex flex-wrap gap-1.5" {
+                                @for provider in own {
+                                    @if other.iter().any(|o| o.provider_id == provider.provider_id) {
+                                        div class="opacity-60" { (provider_icon(provider, proxy_images)) }
+                                    } @else {
+                                        div class="rounded ring-2 ring-emerald-500/70" title=(format!("Only in {}", get_country_name(code))) {
+                                            (provider_icon(provider, proxy_images))
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn provider_icon(provider: &WatchProvider, proxy_images: bool) -> impl Renderable + '_ {
+    let logo_url = image_url(ImageKind::Logo, &provider.logo_path, proxy_images);
+
     maud! {
         @if let Some(link) = &provider.link {
             a
@@ -421,7 +1664,7 @@ fn provider_icon(provider: &WatchProvider) -> impl Renderable + '_ {
             {
                 img
                     class="w-7 h-7 rounded"
-                    src=(format!("https://image.tmdb.org/t/p/w92{}", provider.logo_path))
+                    src=(logo_url.clone())
                     alt=(provider.provider_name)
                     loading="lazy"
                     width="28"
@@ -431,7 +1674,7 @@ fn provider_icon(provider: &WatchProvider) -> impl Renderable + '_ {
             span title=(provider.provider_name) class="block" {
                 img
                     class="w-7 h-7 rounded"
-                    src=(format!("https://image.tmdb.org/t/p/w92{}", provider.logo_path))
+                    src=(logo_url.clone())
                     alt=(provider.provider_name)
                     loading="lazy"
                     width="28"
@@ -444,13 +1687,24 @@ fn provider_icon(provider: &WatchProvider) -> impl Renderable + '_ {
 fn release_list<'a>(
     label: &'a str,
     releases: &'a [ReleaseDate],
-    kind: ReleaseType,
+    date_changes: &'a [ReleaseDateChange],
+    types: &[ReleaseType],
+    country: &'a str,
 ) -> impl Renderable + 'a {
-    let border = match kind {
-        ReleaseType::Theatrical => "border-purple-400",
-        ReleaseType::Digital => "border-blue-400",
+    let moved_from = |rel: &ReleaseDate| {
+        date_changes
+            .iter()
+            .find(|c| {
+                c.release_type == rel.release_type
+                    && c.new_date == rel.date
+                    && rel.source_country.as_deref() == Some(c.country.as_str())
+            })
+            .map(|c| c.previous_date)
     };
 
+    let border =
+        if types.contains(&ReleaseType::Digital) { "border-blue-400" } else { "border-purple-400" };
+
     maud! {
         div class=(format!("border-l-3 {} pl-2.5", border)) {
             h3 class="text-xs font-semibold text-slate-400 uppercase tracking-wide" { (label) }
@@ -460,9 +1714,25 @@ fn release_list<'a>(
                 ul class="mt-1 space-y-0.5" {
                     @for rel in releases {
                         li class="text-sm text-slate-300" {
-                            span class="font-medium" { (format_date(rel)) }
-                            @if let Some(note) = &rel.note {
-                                span class="text-slate-500" { " · " (note) }
+                            span class="font-medium" title=[rel.raw_note.as_deref().or(rel.note.as_deref())] { (format_date(rel)) }
+                            @if rel.release_type == ReleaseType::TheatricalLimited {
+                                span class="ml-1.5 rounded bg-slate-700 px-1 py-0.5 text-xs text-slate-400" { "Limited" }
+                            }
+                            @if rel.release_type == ReleaseType::Premiere {
+                                span class="ml-1.5 rounded bg-slate-700 px-1 py-0.5 text-xs text-slate-400" { "Premiere" }
+                            }
+                            @if rel.release_type == ReleaseType::Physical {
+                                span class="ml-1.5 rounded bg-slate-700 px-1 py-0.5 text-xs text-slate-400" { "Physical" }
+                            }
+                            @if let Some(source) = &rel.source_country {
+                                span class=(if source == country { "text-slate-500" } else { "text-amber-400/80" }) title=(get_country_name(source)) {
+                                    " · " (get_country_flag_emoji(source)) " " (source)
+                                }
+                            }
+                            @if let Some(previous) = moved_from(rel) {
+                                span class="ml-1.5 text-xs text-amber-400" title="Release date changed" {
+                                    "moved from " (previous.strftime("%-d %b").to_string()) " → " (rel.date.strftime("%-d %b").to_string())
+                                }
                             }
                         }
                     }
@@ -472,6 +1742,56 @@ fn release_list<'a>(
     }
 }
 
+/// Whether the latest of `dates` up to `today` falls within the last `months` months, counting
+/// the day exactly `months` before today. Windows too long to subtract cover every date.
+fn is_recent_release(
+    dates: impl Iterator<Item = jiff::civil::Date>,
+    today: jiff::civil::Date,
+    months: i64,
+) -> bool {
+    let cutoff = jiff::Span::new()
+        .try_months(months)
+        .ok()
+        .and_then(|span| today.checked_sub(span).ok())
+        .unwrap_or(jiff::civil::Date::MIN);
+    dates.filter(|d| *d <= today).max().is_some_and(|d| d >= cutoff)
+}
+
+fn format_rating(half_stars: u8) -> String {
+    let mut stars = "★".repeat((half_stars / 2) as usize);
+    if half_stars % 2 == 1 {
+        stars.push('½');
+    }
+    stars
+}
+
+fn format_age(timestamp: i64) -> String {
+    let seconds = jiff::Timestamp::now().as_second().saturating_sub(timestamp).max(0);
+    match seconds {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{}m ago", seconds / 60),
+        3600..86400 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86400),
+    }
+}
+
+fn upcoming_clipboard_text(films: &[&FilmWithReleases]) -> String {
+    films
+        .iter()
+        .map(|film| {
+            let title = match film.year {
+                Some(year) => format!("{} ({})", film.title, year),
+                None => film.title.clone(),
+            };
+            match film.first_release() {
+                Some(rel) => format!("{} - {}", title, format_date(rel)),
+                None => title,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 fn format_date(rel: &ReleaseDate) -> String {
     rel.date.strftime("%-d %b %Y").to_string()
 }
@@ -482,11 +1802,38 @@ fn country_selector_script() -> impl Renderable {
             (Raw::dangerously_create(r#"
                 let selectedIndex = -1;
 
+                function openCountryDropdown() {
+                    document.getElementById('country-dropdown').classList.remove('hidden');
+                    document.getElementById('country-search').setAttribute('aria-expanded', 'true');
+                }
+
+                function closeCountryDropdown() {
+                    document.getElementById('country-dropdown').classList.add('hidden');
+                    const input = document.getElementById('country-search');
+                    input.setAttribute('aria-expanded', 'false');
+                    input.setAttribute('aria-activedescendant', '');
+                    selectedIndex = -1;
+                }
+
+                function markSelectedCountry(code) {
+                    const options = document.getElementsByClassName('country-option');
+                    for (let i = 0; i < options.length; i++) {
+                        options[i].setAttribute('aria-selected', options[i].getAttribute('data-code') === code ? 'true' : 'false');
+                    }
+                }
+
+                function handleCountryClick(event) {
+                    const option = event.target.closest('.country-option');
+                    if (option) {
+                        selectCountry(option.getAttribute('data-code'), option.getAttribute('data-name'));
+                    }
+                }
+
                 function selectCountry(code, name) {
                     document.getElementById('country').value = code;
                     document.getElementById('country-search').value = name;
-                    document.getElementById('country-dropdown').classList.add('hidden');
-                    selectedIndex = -1;
+                    markSelectedCountry(code);
+                    closeCountryDropdown();
                     validateForm();
                     document.getElementById('submit-button').focus();
                 }
@@ -539,10 +1886,13 @@ fn country_selector_script() -> impl Renderable {
 
                 function highlightOption(index) {
                     const visible = getVisibleOptions();
+                    const input = document.getElementById('country-search');
+                    input.setAttribute('aria-activedescendant', '');
                     visible.forEach((opt, i) => {
                         if (i === index) {
                             opt.classList.add('bg-blue-100');
                             opt.scrollIntoView({ block: 'nearest' });
+                            input.setAttribute('aria-activedescendant', opt.id);
                         } else {
                             opt.classList.remove('bg-blue-100');
                         }
@@ -568,22 +1918,27 @@ fn country_selector_script() -> impl Renderable {
                     }
 
                     selectedIndex = -1;
-                    if (hasVisible) {
-                        dropdown.classList.remove('hidden');
+                    input.setAttribute('aria-activedescendant', '');
+                    document.getElementById('country-no-results').classList.toggle('hidden', hasVisible);
+                    openCountryDropdown();
+                }
+
+                let filterTimer = null;
+
+                function scheduleFilterCountries() {
+                    clearTimeout(filterTimer);
+                    const filter = document.getElementById('country-search').value;
+                    if (filter.length <= 2) {
+                        filterCountries();
+                    } else {
+                        filterTimer = setTimeout(filterCountries, 100);
                     }
                 }
 
                 const searchInput = document.getElementById('country-search');
                 const dropdown = document.getElementById('country-dropdown');
                 
-                function focusOption(index) {
-                    const visible = getVisibleOptions();
-                    if (index >= 0 && index < visible.length) {
-                        visible[index].focus();
-                    }
-                }
-                
-                searchInput.addEventListener('keydown', function(e) {
+                searchInput?.addEventListener('keydown', function(e) {
                     const isOpen = !dropdown.classList.contains('hidden');
                     const visible = getVisibleOptions();
                     
@@ -591,24 +1946,22 @@ fn country_selector_script() -> impl Renderable {
                         case 'ArrowDown':
                             e.preventDefault();
                             if (!isOpen) {
-                                dropdown.classList.remove('hidden');
+                                openCountryDropdown();
                             }
                             if (visible.length > 0) {
                                 selectedIndex = selectedIndex < 0 ? 0 : (selectedIndex + 1) % visible.length;
                                 highlightOption(selectedIndex);
-                                focusOption(selectedIndex);
                             }
                             break;
                             
                         case 'ArrowUp':
                             e.preventDefault();
                             if (!isOpen) {
-                                dropdown.classList.remove('hidden');
+                                openCountryDropdown();
                             }
                             if (visible.length > 0) {
                                 selectedIndex = selectedIndex <= 0 ? visible.length - 1 : selectedIndex - 1;
                                 highlightOption(selectedIndex);
-                                focusOption(selectedIndex);
                             }
                             break;
                             
@@ -635,15 +1988,13 @@ fn country_selector_script() -> impl Renderable {
                         case 'Escape':
                             if (isOpen) {
                                 e.preventDefault();
-                                dropdown.classList.add('hidden');
-                                selectedIndex = -1;
-                                searchInput.focus();
+                                closeCountryDropdown();
                             }
                             break;
                     }
                 });
                 
-                dropdown.addEventListener('keydown', function(e) {
+                dropdown?.addEventListener('keydown', function(e) {
                     const visible = getVisibleOptions();
                     const focusedElement = document.activeElement;
                     const currentIndex = visible.indexOf(focusedElement);
@@ -654,16 +2005,16 @@ fn country_selector_script() -> impl Renderable {
                             if (visible.length > 0) {
                                 selectedIndex = currentIndex < 0 ? 0 : (currentIndex + 1) % visible.length;
                                 highlightOption(selectedIndex);
-                                focusOption(selectedIndex);
+                                searchInput.focus();
                             }
                             break;
                             
                         case 'ArrowUp':
                             e.preventDefault();
                             if (visible.length > 0) {
-                                selectedIndex = currentIndex <= 0 ? visible.length - 1 : selectedIndex - 1;
+                                selectedIndex = currentIndex <= 0 ? visible.length - 1 : currentIndex - 1;
                                 highlightOption(selectedIndex);
-                                focusOption(selectedIndex);
+                                searchInput.focus();
                             }
                             break;
                             
@@ -677,8 +2028,7 @@ fn country_selector_script() -> impl Renderable {
                             
                         case 'Escape':
                             e.preventDefault();
-                            dropdown.classList.add('hidden');
-                            selectedIndex = -1;
+                            closeCountryDropdown();
                             searchInput.focus();
                             break;
                     }
@@ -686,16 +2036,81 @@ fn country_selector_script() -> impl Renderable {
 
                 document.addEventListener('click', function(event) {
                     if (dropdown && searchInput && !dropdown.contains(event.target) && event.target !== searchInput) {
-                        dropdown.classList.add('hidden');
-                        selectedIndex = -1;
+                        closeCountryDropdown();
                     }
                 });
 
                 // Initialize form validation on page load
                 document.addEventListener('DOMContentLoaded', function() {
+                    markSelectedCountry(document.getElementById('country').value);
                     validateForm();
                 });
             "#))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use jiff::civil::date;
+
+    use super::*;
+
+    #[test]
+    fn recent_window_includes_the_exact_cutoff_day() {
+        let today = date(2025, 6, 15);
+
+        assert!(is_recent_release([date(2024, 6, 15)].into_iter(), today, 12));
+        assert!(!is_recent_release([date(2024, 6, 14)].into_iter(), today, 12));
+    }
+
+    #[test]
+    fn recent_window_judges_the_latest_past_release() {
+        let today = date(2025, 6, 15);
+        let dates = [date(2023, 1, 1), date(2025, 1, 10), date(2025, 9, 1)];
+
+        assert!(is_recent_release(dates.into_iter(), today, 12));
+        // Only a future date doesn't make a film recent
+        assert!(!is_recent_release([date(2025, 9, 1)].into_iter(), today, 12));
+        assert!(!is_recent_release(std::iter::empty(), today, 12));
+    }
+
+    #[test]
+    fn results_query_keeps_unusual_list_names_intact() {
+        let view = ResultsView {
+            sort: SortOrder::default(),
+            layout: Layout::default(),
+            today: date(2025, 6, 15),
+            also: Vec::new(),
+            hide_undated: false,
+            window_months: 0,
+            since: None,
+            new_only: false,
+            order: WatchlistOrder::default(),
+            services: Vec::new(),
+            flatrate_only: false,
+            compare_providers: None,
+            nojs: false,
+        };
+        let source =
+            FilmSource::List { username: "ümit".to_string(), slug: "café-noir".to_string() };
+
+        let query = view.query(&source, "NZ");
+        let username = query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("username="))
+            .and_then(|value| urlencoding::decode(value).ok())
+            .unwrap();
+
+        assert!(query.is_ascii());
+        assert_eq!(username, "https://letterboxd.com/%C3%BCmit/list/caf%C3%A9-noir/");
+        assert_eq!(crate::scraper::parse_letterboxd_url(&username), Some(source));
+    }
+
+    #[test]
+    fn oversized_recent_window_covers_everything() {
+        let today = date(2025, 6, 15);
+
+        assert!(is_recent_release([date(1900, 1, 1)].into_iter(), today, i64::MAX));
+    }
+}
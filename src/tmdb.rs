@@ -1,5 +1,6 @@
-use std::{num::NonZeroU32, sync::Arc};
+use std::{collections::HashMap, num::NonZeroU32, sync::Arc};
 
+use futures::{StreamExt, stream};
 use governor::{
     Quota, RateLimiter,
     clock::DefaultClock,
@@ -7,40 +8,165 @@ use governor::{
 };
 use jiff::{civil::Date, fmt::temporal::DateTimeParser};
 use serde::Deserialize;
-use tracing::{debug, warn};
+use tracing::{debug, error, warn};
+use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
 
 use crate::{
-    error::AppResult,
+    countries::CountryCode,
+    error::{AppResult, InvalidTmdbCredentials},
     models::{
-        CountryReleases, ProviderType, ReleaseDate, ReleaseDatesResult, ReleaseType, WatchProvider,
+        ALREADY_AVAILABLE_NOTE, CountryReleases, MatchConfidence, ProviderType, ReleaseDate,
+        ReleaseDatesResult, ReleaseType, WatchProvider, dedup_release_dates, today_in,
     },
+    sources::ReleaseSource,
 };
 
+enum TmdbAuth {
+    Bearer(String),
+    ApiKey(String),
+}
+
+impl TmdbAuth {
+    fn from_credentials(access_token: &str, api_key: &str) -> Option<Self> {
+        let access_token = access_token.trim();
+        let api_key = api_key.trim();
+
+        if !access_token.is_empty() {
+            // v3 API keys are 32 hex characters, v4 read access tokens are JWTs
+            if is_v3_api_key(access_token) {
+                return Some(TmdbAuth::ApiKey(access_token.to_string()));
+            }
+            return Some(TmdbAuth::Bearer(access_token.to_string()));
+        }
+
+        (!api_key.is_empty()).then(|| TmdbAuth::ApiKey(api_key.to_string()))
+    }
+}
+
+fn is_v3_api_key(credential: &str) -> bool {
+    credential.len() == 32 && credential.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+const MIN_CONFIDENT_POPULARITY: f64 = 1.0;
+
+pub struct SearchMatch {
+    pub tmdb_id: i32,
+    pub poster_path: Option<String>,
+    pub confidence: MatchConfidence,
+}
+
+/// Title, year and poster of a TMDB film.
+pub struct MovieSummary {
+    pub title: String,
+    pub year: Option<i16>,
+    pub poster_path: Option<String>,
+}
+
 pub struct TmdbClient {
     client: wreq::Client,
-    access_token: String,
+    auth: Option<TmdbAuth>,
     base_url: String,
     limiter: Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>,
+    rps: u32,
+    already_available_months: i64,
+    release_types: Vec<ReleaseType>,
+    /// Fetched too, but kept in their own list for films without other dates
+    fallback_release_types: Vec<ReleaseType>,
+    /// Search results below either threshold are not accepted as matches
+    min_match_popularity: f64,
+    min_match_votes: u32,
 }
 
 impl TmdbClient {
-    pub fn new(client: wreq::Client, access_token: String, base_url: String, rps: u32) -> Self {
-        if access_token.trim().is_empty() {
-            warn!("TMDB_ACCESS_TOKEN not provided, using mock data");
+    pub fn new(
+        client: wreq::Client,
+        access_token: String,
+        api_key: String,
+        base_url: String,
+        rps: u32,
+        already_available_months: i64,
+        release_types: Vec<ReleaseType>,
+    ) -> Self {
+        let auth = TmdbAuth::from_credentials(&access_token, &api_key);
+        match &auth {
+            Some(TmdbAuth::Bearer(_)) => debug!("using TMDB bearer token auth"),
+            Some(TmdbAuth::ApiKey(_)) => debug!("using TMDB v3 api_key auth"),
+            None => warn!("TMDB_ACCESS_TOKEN or TMDB_API_KEY not provided, using mock data"),
         }
 
         let limiter =
             Arc::new(RateLimiter::direct(Quota::per_second(NonZeroU32::new(rps.max(1)).unwrap())));
-        Self { client, access_token, base_url, limiter }
+        Self {
+            client,
+            auth,
+            base_url,
+            limiter,
+            rps: rps.max(1),
+            already_available_months,
+            release_types,
+            fallback_release_types: Vec::new(),
+            min_match_popularity: 0.0,
+            min_match_votes: 0,
+        }
+    }
+
+    /// Only accept search results with at least this TMDB popularity and vote count, so
+    /// obscure films sharing a title aren't matched. Zero accepts every result.
+    pub fn with_min_match(mut self, popularity: f64, votes: u32) -> Self {
+        self.min_match_popularity = popularity;
+        self.min_match_votes = votes;
+        self
+    }
+
+    pub fn with_fallback_release_types(mut self, types: Vec<ReleaseType>) -> Self {
+        self.fallback_release_types = types;
+        self
+    }
+
+    pub fn rps(&self) -> u32 {
+        self.rps
     }
 
+    /// No access token or API key is configured, so canned data is served instead of TMDB's.
+    pub fn is_mock(&self) -> bool {
+        self.auth.is_none()
+    }
+
+    fn authorize(&self, req: wreq::RequestBuilder) -> wreq::RequestBuilder {
+        match &self.auth {
+            Some(TmdbAuth::Bearer(token)) => req.bearer_auth(token),
+            Some(TmdbAuth::ApiKey(key)) => req.query(&[("api_key", key)]),
+            None => req,
+        }
+    }
+
+    async fn send(&self, req: wreq::RequestBuilder) -> AppResult<wreq::Response> {
+        let resp = req.send().await?;
+        if resp.status() == wreq::StatusCode::UNAUTHORIZED {
+            error!("TMDB returned 401 Unauthorized — check TMDB_ACCESS_TOKEN or TMDB_API_KEY");
+            return Err(anyhow::Error::new(InvalidTmdbCredentials).into());
+        }
+        Ok(resp.error_for_status()?)
+    }
+
+    /// Requests an image from TMDB's image CDN, which needs no credentials or rate limiting.
+    pub async fn get_image(&self, size: &str, file: &str) -> AppResult<wreq::Response> {
+        let url = format!("{}/{size}/{file}", crate::image_cache::TMDB_IMAGE_BASE_URL);
+        Ok(self.client.get(url).send().await?)
+    }
+
+    /// Searches for one query; callers try each of `search_variants` until a confident match.
     pub async fn search_movie(
         &self,
         title: &str,
         year: Option<i16>,
-    ) -> AppResult<Option<(i32, Option<String>)>> {
-        if self.access_token.trim().is_empty() {
-            return Ok(Some((550, None)));
+    ) -> AppResult<Option<SearchMatch>> {
+        if self.is_mock() {
+            return Ok(Some(SearchMatch {
+                tmdb_id: 550,
+                poster_path: None,
+                confidence: MatchConfidence::High,
+            }));
         }
 
         self.limiter.until_ready().await;
@@ -48,24 +174,41 @@ impl TmdbClient {
         debug!(title = %title, year = ?year, "TMDB API: searching movie");
 
         let url = format!("{}/search/movie", self.base_url.trim_end_matches('/'));
-        let mut req = self
-            .client
-            .get(url)
-            .bearer_auth(&self.access_token)
-            .query(&[("query", &title.to_string())]);
+        let mut req = self.authorize(self.client.get(url)).query(&[("query", &title.to_string())]);
         if let Some(year) = year {
             req = req.query(&[("year", year)]);
         }
 
-        let resp: SearchResponse = req.send().await?.error_for_status()?.json().await?;
-        let result = resp.results.into_iter().next().map(|m| (m.id, m.poster_path));
-        debug!(title = %title, result = ?result, "TMDB API: search result");
+        let resp: SearchResponse = self.send(req).await?.json().await?;
+        let result_count = resp.results.len();
+        let result = resp
+            .results
+            .into_iter()
+            .find(|m| {
+                m.popularity >= self.min_match_popularity && m.vote_count >= self.min_match_votes
+            })
+            .map(|m| SearchMatch {
+                confidence: search_confidence(&m, year),
+                tmdb_id: m.id,
+                poster_path: m.poster_path,
+            });
+        debug!(
+            title = %title,
+            tmdb_id = ?result.as_ref().map(|m| m.tmdb_id),
+            confidence = ?result.as_ref().map(|m| m.confidence),
+            result_count,
+            "TMDB API: search result"
+        );
         Ok(result)
     }
 
-    pub async fn get_movie_details(&self, tmdb_id: i32) -> AppResult<Option<String>> {
-        if self.access_token.trim().is_empty() {
-            return Ok(None);
+    pub async fn get_movie_details(&self, tmdb_id: i32) -> AppResult<MovieSummary> {
+        if self.is_mock() {
+            return Ok(MovieSummary {
+                title: "Fight Club".to_string(),
+                year: Some(1999),
+                poster_path: None,
+            });
         }
 
         self.limiter.until_ready().await;
@@ -74,40 +217,41 @@ impl TmdbClient {
 
         let url = format!("{}/movie/{}", self.base_url.trim_end_matches('/'), tmdb_id);
 
-        let resp: MovieDetails = self
-            .client
-            .get(url)
-            .bearer_auth(&self.access_token)
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await?;
+        let resp: MovieDetails =
+            self.send(self.authorize(self.client.get(url))).await?.json().await?;
 
         debug!(tmdb_id = tmdb_id, poster_path = ?resp.poster_path, "TMDB API: movie details result");
-        Ok(resp.poster_path)
+        Ok(MovieSummary {
+            year: release_year(resp.release_date.as_deref()),
+            title: resp.title,
+            poster_path: resp.poster_path,
+        })
     }
 
     pub async fn get_release_dates(
         &self,
         tmdb_id: i32,
-        country: &str,
+        country: CountryCode,
     ) -> AppResult<ReleaseDatesResult> {
-        // Use mock data if access token is not provided
-        if self.access_token.trim().is_empty() {
-            let today: Date = jiff::Timestamp::now().to_zoned(jiff::tz::TimeZone::UTC).date();
+        // Use mock data if no TMDB credentials are configured
+        if self.is_mock() {
+            let today = today_in("UTC");
             let future_date = today + jiff::Span::new().years(1);
 
             let theatrical = vec![ReleaseDate {
                 date: future_date,
                 release_type: ReleaseType::Theatrical,
                 note: Some("Mock theatrical release".to_string()),
+                raw_note: None,
+                source_country: None,
             }];
 
             let streaming = vec![ReleaseDate {
                 date: future_date + jiff::Span::new().months(3),
                 release_type: ReleaseType::Digital,
                 note: Some("Mock streaming release".to_string()),
+                raw_note: None,
+                source_country: None,
             }];
 
             return Ok(ReleaseDatesResult {
@@ -115,8 +259,11 @@ impl TmdbClient {
                     country: country.to_string(),
                     theatrical,
                     streaming,
+                    fallback: vec![],
                 },
                 all_countries: vec![],
+                released_countries: vec![],
+                confirmed_empty: false,
             });
         }
 
@@ -127,107 +274,75 @@ impl TmdbClient {
         let url =
             format!("{}/movie/{}/release_dates", self.base_url.trim_end_matches('/'), tmdb_id);
 
-        let resp: ReleaseDatesResponse = self
-            .client
-            .get(url)
-            .bearer_auth(&self.access_token)
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await?;
+        let resp: ReleaseDatesResponse =
+            self.send(self.authorize(self.client.get(url))).await?.json().await?;
 
-        let today: Date = jiff::Zoned::now().into();
+        // Release data is cached and shared by every user, so classify it in UTC
+        let today = today_in("UTC");
 
+        let confirmed_empty = resp.results.is_empty();
         let mut all_countries = Vec::new();
+        let mut released_countries = Vec::new();
 
-        for res in resp.results {
-            let country_code = res.iso_3166_1.clone();
-            let mut theatrical_future = Vec::new();
-            let mut streaming_future = Vec::new();
-            let mut theatrical_past = Vec::new();
-            let mut streaming_past = Vec::new();
+        // Only include "Already available" if the latest release is within the configured window
+        let window_start = jiff::Span::new()
+            .try_months(self.already_available_months)
+            .ok()
+            .and_then(|span| today.checked_sub(span).ok())
+            .unwrap_or(Date::MIN);
 
+        for res in resp.results {
+            let mut releases = Vec::new();
             for rd in res.release_dates {
+                // Types that aren't fetched are dropped so films with only those dates fall into
+                // "No release dates found"
                 let Some(kind) = ReleaseType::from_tmdb_code(rd.type_) else {
                     continue;
                 };
+                if !self.release_types.contains(&kind)
+                    && !self.fallback_release_types.contains(&kind)
+                {
+                    continue;
+                }
                 let timestamp =
                     DateTimeParser::new().parse_timestamp(rd.release_date.as_bytes())?;
                 let date: Date = timestamp.to_zoned(jiff::tz::TimeZone::UTC).date();
-                let note = rd.note.and_then(|s| {
-                    let s = s.trim();
-                    (!s.is_empty()).then(|| s.to_string())
-                });
-                let out = ReleaseDate { date, release_type: kind, note };
-
-                if date >= today {
-                    match kind {
-                        ReleaseType::Theatrical => theatrical_future.push(out),
-                        ReleaseType::Digital => streaming_future.push(out),
-                    }
-                } else {
-                    match kind {
-                        ReleaseType::Theatrical => theatrical_past.push(out),
-                        ReleaseType::Digital => streaming_past.push(out),
-                    }
+                let note = rd.note.as_deref().and_then(normalize_note);
+                let raw_note = rd
+                    .note
+                    .filter(|raw| !raw.trim().is_empty() && note.as_deref() != Some(raw.as_str()));
+                if raw_note.is_some() {
+                    debug!(tmdb_id, raw_note = ?raw_note, note = ?note, "normalized release note");
                 }
+                releases.push(ReleaseDate {
+                    date,
+                    release_type: kind,
+                    note,
+                    raw_note,
+                    source_country: None,
+                });
             }
 
-            theatrical_future.sort_by_key(|r| r.date);
-            streaming_future.sort_by_key(|r| r.date);
-            theatrical_past.sort_by_key(|r| r.date);
-            streaming_past.sort_by_key(|r| r.date);
-
-            theatrical_future
-                .dedup_by_key(|r| (r.date, r.release_type.as_tmdb_code(), r.note.clone()));
-            streaming_future
-                .dedup_by_key(|r| (r.date, r.release_type.as_tmdb_code(), r.note.clone()));
-
-            let _has_future_theatrical = !theatrical_future.is_empty();
-            let _has_future_streaming = !streaming_future.is_empty();
-            let has_past_theatrical = !theatrical_past.is_empty();
-            let has_past_streaming = !streaming_past.is_empty();
-
-            let mut theatrical = theatrical_future;
-            let mut streaming = streaming_future;
-
-            // Only include "Already available" if the latest release is within the last 2 years
-            let two_years_ago = today - jiff::Span::new().years(2);
-
-            if has_past_theatrical && theatrical.is_empty() {
-                if let Some(latest) = theatrical_past.into_iter().max_by_key(|r| r.date) {
-                    if latest.date >= two_years_ago {
-                        theatrical.push(ReleaseDate {
-                            date: latest.date,
-                            release_type: ReleaseType::Theatrical,
-                            note: Some("Already available".to_string()),
-                        });
-                    }
-                }
+            let (releases, released) = country_releases(
+                res.iso_3166_1,
+                releases,
+                &self.fallback_release_types,
+                today,
+                window_start,
+            );
+            if released {
+                released_countries.push(releases.country.clone());
             }
-
-            if has_past_streaming && streaming.is_empty() {
-                if let Some(latest) = streaming_past.into_iter().max_by_key(|r| r.date) {
-                    if latest.date >= two_years_ago {
-                        streaming.push(ReleaseDate {
-                            date: latest.date,
-                            release_type: ReleaseType::Digital,
-                            note: Some("Already available".to_string()),
-                        });
-                    }
-                }
-            }
-
-            all_countries.push(CountryReleases { country: country_code, theatrical, streaming });
+            all_countries.push(releases);
         }
 
         let requested_country =
-            all_countries.iter().find(|c| c.country == country).cloned().unwrap_or_else(|| {
+            all_countries.iter().find(|c| country == c.country).cloned().unwrap_or_else(|| {
                 CountryReleases {
                     country: country.to_string(),
                     theatrical: vec![],
                     streaming: vec![],
+                    fallback: vec![],
                 }
             });
 
@@ -240,15 +355,23 @@ impl TmdbClient {
             "TMDB API: release dates result"
         );
 
-        Ok(ReleaseDatesResult { requested_country, all_countries })
+        released_countries.sort();
+        released_countries.dedup();
+
+        Ok(ReleaseDatesResult {
+            requested_country,
+            all_countries,
+            released_countries,
+            confirmed_empty,
+        })
     }
 
     pub async fn get_watch_providers(
         &self,
         tmdb_id: i32,
-        country: &str,
+        country: CountryCode,
     ) -> AppResult<(Vec<WatchProvider>, Option<String>)> {
-        if self.access_token.trim().is_empty() {
+        if self.is_mock() {
             return Ok((
                 vec![WatchProvider {
                     provider_id: 8,
@@ -256,6 +379,7 @@ impl TmdbClient {
                     logo_path: "/pbpMk2JmcoNnQwx5JGpXngfoWtp.jpg".to_string(),
                     link: None,
                     provider_type: ProviderType::Stream,
+                    display_priority: 0,
                 }],
                 Some("https://www.themoviedb.org/movie/550/watch".to_string()),
             ));
@@ -268,57 +392,32 @@ impl TmdbClient {
         let url =
             format!("{}/movie/{}/watch/providers", self.base_url.trim_end_matches('/'), tmdb_id);
 
-        let resp: WatchProvidersResponse = self
-            .client
-            .get(url)
-            .bearer_auth(&self.access_token)
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await?;
+        let resp: WatchProvidersResponse =
+            self.send(self.authorize(self.client.get(url))).await?.json().await?;
 
-        let country_data = resp.results.get(country);
+        let country_data = resp.results.get(country.as_str());
 
         let (providers, link) = match country_data {
             Some(data) => {
-                let mut providers = Vec::new();
-
-                if let Some(flatrate) = &data.flatrate {
-                    for p in flatrate {
-                        providers.push(WatchProvider {
-                            provider_id: p.provider_id,
-                            provider_name: p.provider_name.clone(),
-                            logo_path: p.logo_path.clone(),
-                            link: data.link.clone(),
-                            provider_type: ProviderType::Stream,
-                        });
-                    }
-                }
-
-                if let Some(rent) = &data.rent {
-                    for p in rent {
-                        if !providers.iter().any(|existing| existing.provider_id == p.provider_id) {
-                            providers.push(WatchProvider {
-                                provider_id: p.provider_id,
-                                provider_name: p.provider_name.clone(),
-                                logo_path: p.logo_path.clone(),
-                                link: data.link.clone(),
-                                provider_type: ProviderType::Rent,
-                            });
-                        }
-                    }
-                }
-
-                if let Some(buy) = &data.buy {
-                    for p in buy {
+                let mut providers: Vec<WatchProvider> = Vec::new();
+
+                // A provider offering several options is listed once, under the cheapest
+                let offers = [
+                    (&data.flatrate, ProviderType::Stream),
+                    (&data.flatrate_and_buy, ProviderType::Stream),
+                    (&data.rent, ProviderType::Rent),
+                    (&data.buy, ProviderType::Buy),
+                ];
+                for (entries, provider_type) in offers {
+                    for p in entries.iter().flatten() {
                         if !providers.iter().any(|existing| existing.provider_id == p.provider_id) {
                             providers.push(WatchProvider {
                                 provider_id: p.provider_id,
                                 provider_name: p.provider_name.clone(),
                                 logo_path: p.logo_path.clone(),
                                 link: data.link.clone(),
-                                provider_type: ProviderType::Buy,
+                                provider_type,
+                                display_priority: p.display_priority,
                             });
                         }
                     }
@@ -338,6 +437,220 @@ impl TmdbClient {
 
         Ok((providers, link))
     }
+
+    /// Watch providers for many films in one region. TMDB has no bulk providers endpoint,
+    /// so this fans out one request per film with at most `concurrency` in flight. Films whose
+    /// lookup fails are logged and left out of the map.
+    pub async fn get_watch_providers_bulk(
+        &self,
+        tmdb_ids: &[i32],
+        country: CountryCode,
+        concurrency: usize,
+    ) -> AppResult<HashMap<i32, Vec<WatchProvider>>> {
+        let items: Vec<(i32, AppResult<Vec<WatchProvider>>)> = stream::iter(tmdb_ids.to_vec())
+            .map(|tmdb_id| async move {
+                let result = self.get_watch_providers(tmdb_id, country).await;
+                (tmdb_id, result.map(|(providers, _link)| providers))
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await;
+
+        let mut providers = HashMap::with_capacity(items.len());
+        for (tmdb_id, result) in items {
+            match result {
+                Ok(found) => {
+                    providers.insert(tmdb_id, found);
+                },
+                Err(err) if err.is_invalid_tmdb_credentials() => return Err(err),
+                Err(err) => warn!(tmdb_id, error = %err, "failed to fetch watch providers"),
+            }
+        }
+
+        Ok(providers)
+    }
+}
+
+/// Later variants only run when earlier ones miss or match weakly, and each one costs a
+/// rate-limited request, so keep the list short.
+const MAX_SEARCH_VARIANTS: usize = 3;
+
+/// Search queries to try for a Letterboxd title: as given, then with the leading article
+/// fixed and diacritics removed, then without the last subtitle.
+pub fn search_variants(title: &str) -> Vec<String> {
+    let title = title.trim();
+    let normalized = strip_diacritics(&normalize_article(title));
+    let without_subtitle = [": ", " - ", " – "]
+        .iter()
+        .filter_map(|sep| normalized.rfind(sep))
+        .max()
+        .map(|at| normalized[..at].trim().to_string())
+        .filter(|main| !main.is_empty());
+
+    let mut variants = vec![title.to_string()];
+    for variant in std::iter::once(normalized).chain(without_subtitle) {
+        if !variants.iter().any(|v| v.eq_ignore_ascii_case(&variant)) {
+            variants.push(variant);
+        }
+    }
+    variants.truncate(MAX_SEARCH_VARIANTS);
+    variants
+}
+
+/// Drops a leading article, including the `Title, The` form used by some catalogues.
+fn normalize_article(title: &str) -> String {
+    const ARTICLES: [&str; 3] = ["The", "A", "An"];
+
+    for article in ARTICLES {
+        if let Some(rest) = title.strip_suffix(article).and_then(|t| t.strip_suffix(", ")) {
+            return rest.trim().to_string();
+        }
+        if let Some(rest) = title.strip_prefix(article).and_then(|t| t.strip_prefix(' ')) {
+            return rest.trim().to_string();
+        }
+    }
+    title.to_string()
+}
+
+fn strip_diacritics(title: &str) -> String {
+    title.nfd().filter(|c| !is_combining_mark(*c)).collect()
+}
+
+/// Notes that say nothing about the release.
+const JUNK_NOTES: &[&str] = &["", "-", "n/a", "na", "none", "null", "unknown", "tba", "tbd", "?"];
+
+/// Common notes written in several ways, keyed by their lowercase letters and digits only.
+const CANONICAL_NOTES: &[(&str, &str)] = &[
+    ("imax", "IMAX"),
+    ("3d", "3D"),
+    ("4dx", "4DX"),
+    ("screenx", "ScreenX"),
+    ("dolbycinema", "Dolby Cinema"),
+    ("rerelease", "Re-release"),
+    ("reissue", "Re-release"),
+    ("restoration", "Restoration"),
+    ("4krestoration", "4K restoration"),
+    ("limited", "Limited"),
+    ("limitedrelease", "Limited"),
+    ("premiere", "Premiere"),
+    ("worldpremiere", "World premiere"),
+    ("festival", "Festival"),
+    ("filmfestival", "Festival"),
+    ("vod", "VOD"),
+    ("svod", "SVOD"),
+    ("tvod", "TVOD"),
+    ("dvd", "DVD"),
+    ("bluray", "Blu-ray"),
+];
+
+/// Splits one country's dates into theatrical, streaming and fallback lists, and reports whether
+/// any theatrical or streaming date is already past. Fallback types never land in the other two
+/// lists, so a premiere or physical date can't hide or replace a theatrical or digital one.
+fn country_releases(
+    country: String,
+    releases: Vec<ReleaseDate>,
+    fallback_types: &[ReleaseType],
+    today: Date,
+    window_start: Date,
+) -> (CountryReleases, bool) {
+    let mut theatrical = Vec::new();
+    let mut streaming = Vec::new();
+    let mut fallback = Vec::new();
+    for rel in releases {
+        if fallback_types.contains(&rel.release_type) {
+            fallback.push(rel);
+            continue;
+        }
+        match rel.release_type {
+            ReleaseType::Premiere | ReleaseType::TheatricalLimited | ReleaseType::Theatrical => {
+                theatrical.push(rel)
+            },
+            ReleaseType::Digital => streaming.push(rel),
+            ReleaseType::Physical => fallback.push(rel),
+        }
+    }
+
+    let released = theatrical.iter().chain(&streaming).any(|r| r.date < today);
+    keep_upcoming(&mut [&mut theatrical, &mut streaming], today, window_start);
+    keep_upcoming(&mut [&mut fallback], today, window_start);
+
+    (CountryReleases { country, theatrical, streaming, fallback }, released)
+}
+
+/// Drops past dates from `lists`. Past releases are only candidates for lists with no upcoming
+/// dates, and a single "Already available" entry is synthesised from the most recent one across
+/// them, if it falls after `window_start`.
+fn keep_upcoming(lists: &mut [&mut Vec<ReleaseDate>], today: Date, window_start: Date) {
+    let mut latest_past: Option<(usize, ReleaseDate)> = None;
+    for (index, list) in lists.iter_mut().enumerate() {
+        let (mut upcoming, past): (Vec<_>, Vec<_>) = list.drain(..).partition(|r| r.date >= today);
+        dedup_release_dates(&mut upcoming);
+
+        if upcoming.is_empty()
+            && let Some(latest) =
+                past.into_iter().filter(|r| r.date >= window_start).max_by_key(|r| r.date)
+            && latest_past.as_ref().is_none_or(|(_, current)| latest.date >= current.date)
+        {
+            latest_past = Some((index, latest));
+        }
+        **list = upcoming;
+    }
+
+    if let Some((index, latest)) = latest_past {
+        lists[index].push(ReleaseDate {
+            date: latest.date,
+            release_type: latest.release_type,
+            note: Some(ALREADY_AVAILABLE_NOTE.to_string()),
+            // TMDB's own note for the date, which the label replaces
+            raw_note: latest.raw_note.or(latest.note),
+            source_country: None,
+        });
+    }
+}
+
+/// Cleans up a release note from TMDB for display: collapses whitespace and surrounding
+/// brackets or quotes, drops placeholders and links, and gives common notes one spelling.
+fn normalize_note(raw: &str) -> Option<String> {
+    let collapsed = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+    let note = collapsed
+        .trim_matches(|c: char| matches!(c, '(' | ')' | '[' | ']' | '"' | '\'' | '.' | ','))
+        .trim();
+
+    if JUNK_NOTES.contains(&note.to_lowercase().as_str())
+        || !note.chars().any(char::is_alphanumeric)
+        || note.contains("://")
+    {
+        return None;
+    }
+
+    let key: String =
+        note.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect();
+    if let Some((_, canonical)) = CANONICAL_NOTES.iter().find(|(k, _)| *k == key) {
+        return Some(canonical.to_string());
+    }
+
+    // All-lowercase notes read as typos next to the rest
+    let mut chars = note.chars();
+    match chars.next() {
+        Some(first) if note.chars().all(|c| !c.is_uppercase()) => {
+            Some(first.to_uppercase().chain(chars).collect())
+        },
+        _ => Some(note.to_string()),
+    }
+}
+
+fn release_year(release_date: Option<&str>) -> Option<i16> {
+    release_date.and_then(|d| d.get(..4)).and_then(|y| y.parse().ok())
+}
+
+fn search_confidence(movie: &SearchMovie, year: Option<i16>) -> MatchConfidence {
+    let year_matches = year.is_some() && year == release_year(movie.release_date.as_deref());
+
+    if year_matches && movie.popularity >= MIN_CONFIDENT_POPULARITY {
+        MatchConfidence::High
+    } else {
+        MatchConfidence::Low
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -349,10 +662,18 @@ struct SearchResponse {
 struct SearchMovie {
     id: i32,
     poster_path: Option<String>,
+    release_date: Option<String>,
+    #[serde(default)]
+    popularity: f64,
+    #[serde(default)]
+    vote_count: u32,
 }
 
 #[derive(Debug, Deserialize)]
 struct MovieDetails {
+    #[serde(default)]
+    title: String,
+    release_date: Option<String>,
     poster_path: Option<String>,
 }
 
@@ -385,6 +706,7 @@ struct WatchProvidersResponse {
 struct WatchProviderCountry {
     link: Option<String>,
     flatrate: Option<Vec<WatchProviderEntry>>,
+    flatrate_and_buy: Option<Vec<WatchProviderEntry>>,
     rent: Option<Vec<WatchProviderEntry>>,
     buy: Option<Vec<WatchProviderEntry>>,
 }
@@ -394,4 +716,244 @@ struct WatchProviderEntry {
     provider_id: i32,
     provider_name: String,
     logo_path: String,
+    #[serde(default = "default_display_priority")]
+    display_priority: i32,
+}
+
+fn default_display_priority() -> i32 {
+    i32::MAX
+}
+
+impl ReleaseSource for TmdbClient {
+    fn rps(&self) -> u32 {
+        TmdbClient::rps(self)
+    }
+
+    async fn search_movie(&self, title: &str, year: Option<i16>) -> AppResult<Option<SearchMatch>> {
+        TmdbClient::search_movie(self, title, year).await
+    }
+
+    async fn get_movie_details(&self, tmdb_id: i32) -> AppResult<MovieSummary> {
+        TmdbClient::get_movie_details(self, tmdb_id).await
+    }
+
+    async fn get_release_dates(
+        &self,
+        tmdb_id: i32,
+        country: CountryCode,
+    ) -> AppResult<ReleaseDatesResult> {
+        TmdbClient::get_release_dates(self, tmdb_id, country).await
+    }
+
+    async fn get_watch_providers(
+        &self,
+        tmdb_id: i32,
+        country: CountryCode,
+    ) -> AppResult<(Vec<WatchProvider>, Option<String>)> {
+        TmdbClient::get_watch_providers(self, tmdb_id, country).await
+    }
+
+    async fn get_watch_providers_bulk(
+        &self,
+        tmdb_ids: &[i32],
+        country: CountryCode,
+        concurrency: usize,
+    ) -> AppResult<HashMap<i32, Vec<WatchProvider>>> {
+        TmdbClient::get_watch_providers_bulk(self, tmdb_ids, country, concurrency).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::civil::date;
+
+    use super::*;
+
+    fn release(date: Date, release_type: ReleaseType) -> ReleaseDate {
+        ReleaseDate { date, release_type, note: None, raw_note: None, source_country: None }
+    }
+
+    fn split(releases: Vec<ReleaseDate>) -> (CountryReleases, bool) {
+        let today = date(2025, 6, 1);
+        country_releases(
+            "NZ".to_string(),
+            releases,
+            &[ReleaseType::Premiere, ReleaseType::Physical],
+            today,
+            date(2023, 6, 1),
+        )
+    }
+
+    fn already_available(releases: &CountryReleases) -> Vec<&ReleaseDate> {
+        releases
+            .theatrical
+            .iter()
+            .chain(&releases.streaming)
+            .filter(|r| r.note.as_deref() == Some(ALREADY_AVAILABLE_NOTE))
+            .collect()
+    }
+
+    #[test]
+    fn past_theatrical_and_digital_give_one_already_available_entry() {
+        let (releases, released) = split(vec![
+            release(date(2024, 11, 1), ReleaseType::Theatrical),
+            release(date(2025, 2, 14), ReleaseType::Digital),
+        ]);
+
+        assert!(released);
+        let entries = already_available(&releases);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].date, date(2025, 2, 14));
+        assert_eq!(entries[0].release_type, ReleaseType::Digital);
+        assert!(releases.theatrical.is_empty());
+    }
+
+    #[test]
+    fn later_theatrical_rerun_takes_the_already_available_entry() {
+        let (releases, _) = split(vec![
+            release(date(2024, 9, 1), ReleaseType::Digital),
+            release(date(2025, 4, 20), ReleaseType::Theatrical),
+            release(date(2024, 6, 1), ReleaseType::Theatrical),
+        ]);
+
+        let entries = already_available(&releases);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].date, date(2025, 4, 20));
+        assert!(releases.streaming.is_empty());
+    }
+
+    #[test]
+    fn past_dates_before_the_window_are_not_already_available() {
+        let (releases, released) = split(vec![
+            release(date(2021, 3, 1), ReleaseType::Theatrical),
+            release(date(2022, 1, 1), ReleaseType::Digital),
+        ]);
+
+        assert!(released);
+        assert!(already_available(&releases).is_empty());
+    }
+
+    #[test]
+    fn future_physical_date_keeps_past_digital_release() {
+        let (releases, released) = split(vec![
+            release(date(2025, 3, 1), ReleaseType::Digital),
+            release(date(2025, 8, 1), ReleaseType::Physical),
+        ]);
+
+        assert!(released);
+        assert_eq!(releases.streaming.len(), 1);
+        assert_eq!(releases.streaming[0].date, date(2025, 3, 1));
+        assert_eq!(releases.streaming[0].note.as_deref(), Some(ALREADY_AVAILABLE_NOTE));
+        assert_eq!(releases.fallback.len(), 1);
+        assert_eq!(releases.fallback[0].release_type, ReleaseType::Physical);
+    }
+
+    #[test]
+    fn later_physical_date_does_not_replace_digital_already_available() {
+        let (releases, _) = split(vec![
+            release(date(2025, 1, 10), ReleaseType::Digital),
+            release(date(2025, 4, 1), ReleaseType::Physical),
+        ]);
+
+        assert_eq!(releases.streaming.len(), 1);
+        assert_eq!(releases.streaming[0].release_type, ReleaseType::Digital);
+        assert_eq!(releases.fallback.len(), 1);
+        assert_eq!(releases.fallback[0].release_type, ReleaseType::Physical);
+    }
+
+    #[test]
+    fn future_premiere_keeps_past_theatrical_run() {
+        let (releases, _) = split(vec![
+            release(date(2025, 2, 1), ReleaseType::Theatrical),
+            release(date(2025, 9, 5), ReleaseType::Premiere),
+        ]);
+
+        assert_eq!(releases.theatrical.len(), 1);
+        assert_eq!(releases.theatrical[0].release_type, ReleaseType::Theatrical);
+        assert_eq!(releases.fallback[0].release_type, ReleaseType::Premiere);
+    }
+
+    #[test]
+    fn same_day_releases_drop_only_the_bare_duplicate() {
+        let noted = |note: Option<&str>| ReleaseDate {
+            note: note.map(str::to_string),
+            ..release(date(2025, 7, 1), ReleaseType::Theatrical)
+        };
+        let (releases, _) = split(vec![
+            noted(None),
+            noted(Some("IMAX")),
+            noted(Some("IMAX")),
+            noted(Some("Re-release")),
+            release(date(2025, 7, 1), ReleaseType::Digital),
+        ]);
+
+        let notes: Vec<_> = releases.theatrical.iter().map(|r| r.note.as_deref()).collect();
+        assert_eq!(notes, [Some("IMAX"), Some("Re-release")]);
+        assert_eq!(releases.streaming.len(), 1);
+    }
+
+    #[test]
+    fn notes_are_cleaned_up() {
+        assert_eq!(normalize_note(""), None);
+        assert_eq!(normalize_note("   "), None);
+        assert_eq!(normalize_note("IMAX").as_deref(), Some("IMAX"));
+        assert_eq!(normalize_note("imax").as_deref(), Some("IMAX"));
+        assert_eq!(normalize_note(" re-release ").as_deref(), Some("Re-release"));
+        assert_eq!(normalize_note("(Re Release)").as_deref(), Some("Re-release"));
+        assert_eq!(normalize_note("N/A"), None);
+        assert_eq!(normalize_note("tba"), None);
+        assert_eq!(normalize_note("https://example.com/tickets"), None);
+        assert_eq!(
+            normalize_note("Sydney  Film   Festival").as_deref(),
+            Some("Sydney Film Festival")
+        );
+        assert_eq!(normalize_note("special screening").as_deref(), Some("Special screening"));
+    }
+
+    #[test]
+    fn already_available_keeps_the_tmdb_note() {
+        let past = ReleaseDate {
+            note: Some("Festival".to_string()),
+            raw_note: Some("film festival".to_string()),
+            ..release(date(2025, 3, 1), ReleaseType::Theatrical)
+        };
+        let (releases, _) = split(vec![past]);
+
+        assert_eq!(releases.theatrical[0].note.as_deref(), Some(ALREADY_AVAILABLE_NOTE));
+        assert_eq!(releases.theatrical[0].raw_note.as_deref(), Some("film festival"));
+    }
+
+    #[test]
+    fn premiere_is_theatrical_when_not_a_fallback_type() {
+        let (releases, _) = country_releases(
+            "NZ".to_string(),
+            vec![release(date(2025, 9, 5), ReleaseType::Premiere)],
+            &[ReleaseType::Physical],
+            date(2025, 6, 1),
+            date(2023, 6, 1),
+        );
+
+        assert_eq!(releases.theatrical.len(), 1);
+        assert!(releases.fallback.is_empty());
+    }
+
+    #[test]
+    fn search_variants_handle_tricky_titles() {
+        assert_eq!(search_variants("The Thing"), ["The Thing", "Thing"]);
+        assert_eq!(search_variants("Thing, The"), ["Thing, The", "Thing"]);
+        assert_eq!(search_variants("  Amélie "), ["Amélie", "Amelie"]);
+        assert_eq!(
+            search_variants("Léon: The Professional"),
+            ["Léon: The Professional", "Leon: The Professional", "Leon"]
+        );
+        assert_eq!(
+            search_variants("Mission: Impossible – Dead Reckoning"),
+            ["Mission: Impossible – Dead Reckoning", "Mission: Impossible"]
+        );
+        // Words that merely start like an article, or are one, stay whole
+        assert_eq!(search_variants("Anora"), ["Anora"]);
+        assert_eq!(search_variants("Another Round"), ["Another Round"]);
+        assert_eq!(search_variants("The"), ["The"]);
+        assert_eq!(search_variants("Spider-Man"), ["Spider-Man"]);
+    }
 }